@@ -0,0 +1,59 @@
+//! Lexer throughput over generated sources of growing size.
+//!
+//! Run with `cargo bench --bench lexer`. Time per line should stay flat as
+//! the files grow; a rising column means lexing went superlinear again.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use wind::frontend::lexer::Lexer;
+
+const SIZES: [usize; 4] = [10_000, 20_000, 40_000, 80_000];
+const RUNS: usize = 5;
+
+/// A `.sz` source of `lines` lines, rounded up to whole functions, mixing
+/// every kind of token.
+fn generate(lines: usize) -> String {
+    let mut src = String::new();
+    for i in 0..lines.div_ceil(8) {
+        writeln!(src, "/// Function number {i}.").unwrap();
+        writeln!(src, "fn func_{i}(a: u64, b: u64) => u64 {{").unwrap();
+        writeln!(src, "    let x: u64 = a * {i} + 0x{i:x}_u64; // mixed radix").unwrap();
+        writeln!(src, "    let s = \"value {i}\\n\";").unwrap();
+        writeln!(src, "    /* block {i} */ let größe = 1.5e3;").unwrap();
+        writeln!(src, "    while x >= b && b != 0 {{ x -= b; x <<= 1; }}").unwrap();
+        writeln!(src, "    return x % b;").unwrap();
+        writeln!(src, "}}").unwrap();
+    }
+    src
+}
+
+/// Fastest of `RUNS` lexes of `src`, and the number of tokens.
+fn measure(src: &str) -> (Duration, usize) {
+    let mut best = Duration::MAX;
+    let mut tokens = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let mut lexer = Lexer::new(src, false);
+        lexer.lex().expect("generated sources lex");
+        best = best.min(start.elapsed());
+        tokens = lexer.tokens.len();
+    }
+    (best, tokens)
+}
+
+fn main() {
+    let dir = std::env::temp_dir().join("wind-lexer-bench");
+    std::fs::create_dir_all(&dir).expect("create the bench directory");
+
+    println!("{:>8} {:>10} {:>10} {:>12}", "lines", "tokens", "time", "ns/line");
+    for lines in SIZES {
+        let path = dir.join(format!("generated_{lines}.sz"));
+        std::fs::write(&path, generate(lines)).expect("write the generated source");
+        let src = std::fs::read_to_string(&path).expect("read the generated source");
+
+        let (time, tokens) = measure(&src);
+        let per_line = time.as_nanos() / lines as u128;
+        println!("{:>8} {:>10} {:>8.2}ms {:>12}", lines, tokens, time.as_secs_f64() * 1e3, per_line);
+    }
+}
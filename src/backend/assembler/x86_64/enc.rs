@@ -17,9 +17,48 @@ pub mod opmod {
         (modb << 6) | ((reg & 0x7) << 3) | (rm & 0x7)
     }
 
+    /// ModR/M (plus SIB and displacement) for a `[base + offset]` operand.
+    pub fn encode_mem(reg: u8, base: u8, offset: i32) -> Vec<u8> {
+        let d32 = offset > i8::MAX as i32 || offset < i8::MIN as i32;
+        // rbp/r13 have no displacement-less form, that encoding means RIP-relative.
+        let modb = if offset == 0 && base & 0x7 != 0b101 { MEM_ADDR } else if d32 { MEM_ADDR_DISP32 } else { MEM_ADDR_DISP8 };
+
+        let mut code = vec![encode(modb, reg, base)];
+        if base & 0x7 == SIB {
+            // rsp/r12 as base require a SIB byte with no index
+            code.push(0x24);
+        }
+        match modb {
+            MEM_ADDR_DISP8 => code.push(offset as u8),
+            MEM_ADDR_DISP32 => code.extend(offset.to_le_bytes()),
+            _ => {}
+        }
+        code
+    }
+
+    /// REX prefix for an operation of `size` bytes with `reg` in ModR/M.reg and `rm` in ModR/M.rm.
+    pub fn rex(size: u8, reg: u8, rm: u8) -> Option<u8> {
+        if size == 8 || reg > 7 || rm > 7 || (size == 1 && (reg > 3 || rm > 3)) {
+            Some(0x40 | (((size == 8) as u8) << 3) | (((reg > 7) as u8) << 2) | ((rm > 7) as u8))
+        } else {
+            None
+        }
+    }
+
+    /// Whether `imm` can be encoded in an immediate of `size` bytes, either signed or unsigned.
+    pub fn fits_imm(imm: isize, size: u8) -> bool {
+        if size >= 8 {
+            return true;
+        }
+        let bits = size as u32 * 8;
+        imm >= -(1isize << (bits - 1)) && imm < (1isize << bits)
+    }
+
     macro_rules! push_rex {
         ($code:expr, $dst:expr, $src:expr) => {
-            if $dst.size == 8 || $dst.id > 7 || $src.size == 8 || $src.id > 7 {
+            // spl, bpl, sil and dil are only reachable with a REX prefix
+            if $dst.size == 8 || $dst.id > 7 || $src.size == 8 || $src.id > 7
+                || ($dst.size == 1 && $dst.id > 3) || ($src.size == 1 && $src.id > 3) {
                 let rex = 0x40 
                     | ((($src.id > 7) as u8) << 2) 
                     | ((($dst.size == 8) as u8) << 3) 
@@ -28,7 +67,7 @@ pub mod opmod {
             }
         };
         ($code:expr, $dst:expr) => {
-            if $dst.size == 8 || $dst.id > 7 {
+            if $dst.size == 8 || $dst.id > 7 || ($dst.size == 1 && $dst.id > 3) {
                 let rex = 0x40 
                     | ((($dst.size == 8) as u8) << 3) 
                     | (($dst.id > 7) as u8);
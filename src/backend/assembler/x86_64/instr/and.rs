@@ -0,0 +1,14 @@
+use super::instructions::{*, enc::opmod::push_rex};
+use super::common;
+
+pub trait And {
+    fn and(self) -> Vec<u8>;
+}
+
+impl And for (GPR, GPR) {
+    common::tb1_gprgpr_instr!(and, [0x20, 0x21, 0x21, 0x21]);
+}
+
+impl And for (GPR, isize) {
+    common::tb2_gprimm_instr!(and, [0x80, 0x81, 0x81, 0x81], 0b100);
+}
@@ -0,0 +1,22 @@
+use super::instructions::{*, enc::opmod::push_rex};
+use super::common;
+
+pub trait Cmp {
+    fn cmp(self) -> Vec<u8>;
+}
+
+impl Cmp for (GPR, GPR) {
+    common::tb1_gprgpr_instr!(cmp, [0x38, 0x39, 0x39, 0x39]);
+}
+
+impl Cmp for (GPR, isize) {
+    common::tb2_gprimm_instr!(cmp, [0x80, 0x81, 0x81, 0x81], 0b111);
+}
+
+impl Cmp for (GPR, RegPtr) {
+    common::tb1_gprrptr_instr!(cmp, [0x3A, 0x3B, 0x3B, 0x3B]);
+}
+
+impl Cmp for (RegPtr, GPR) {
+    common::tb1_rptrgpr_instr!(cmp, [0x38, 0x39, 0x39, 0x39]);
+}
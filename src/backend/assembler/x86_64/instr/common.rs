@@ -8,6 +8,7 @@ macro_rules! tb1_gprgpr_instr {
             if dst.size != src.size {
                 panic!("Error: src and dst registers are not the same size");
             }
+            if dst.size == 2 { code.push(0x66); }
 
             push_rex!(code, dst, src);
 
@@ -27,6 +28,7 @@ macro_rules! tb1_gprgpr_instr {
             if dst.size != src.size {
                 panic!("Error: src and dst registers are not the same size");
             }
+            if dst.size == 2 { code.push(0x66); }
 
             push_rex!(code, dst, src);
 
@@ -44,20 +46,21 @@ macro_rules! tb1_gprimm_instr {
         fn $name(self) -> Vec<u8> {
             let (dst, src) = self;
             let mut code = Vec::new();
-    
+
+            if dst.size == 2 { code.push(0x66); }
             push_rex!(code, dst);
-    
-            if src as usize > (1 << ((dst.size*8)-1)) {
+
+            if !opmod::fits_imm(src, dst.size) {
                 panic!("Error: imm src is too large for the destination register");
             }
-    
+
             const OPCODE: [u8; 4] = $opcodes;
-            code.push(OPCODE[dst.size.trailing_zeros() as usize] | dst.id);
-            
+            code.push(OPCODE[dst.size.trailing_zeros() as usize] | (dst.id & 0x7));
+
             for byte in src.to_le_bytes().iter().take(dst.size as usize) {
                 code.push(*byte);
             }
-    
+
             code
         }
     };
@@ -67,20 +70,21 @@ macro_rules! tb1_gprimm_instr {
             let (orig_dst, orig_src) = self;
             let (dst, src) = if $invert { (orig_src, orig_dst) } else { (orig_dst, orig_src) };
             let mut code = Vec::new();
-    
+
+            if dst.size == 2 { code.push(0x66); }
             push_rex!(code, dst);
-    
-            if src as usize > (1 << ((dst.size*8)-1)) {
+
+            if !opmod::fits_imm(src, dst.size) {
                 panic!("Error: imm src is too large for the destination register");
             }
-    
+
             const OPCODE: [u8; 4] = $opcodes;
-            code.push(OPCODE[dst.size.trailing_zeros() as usize] | dst.id);
-            
+            code.push(OPCODE[dst.size.trailing_zeros() as usize] | (dst.id & 0x7));
+
             for byte in src.to_le_bytes().iter().take(dst.size as usize) {
                 code.push(*byte);
             }
-    
+
             code
         }
     };
@@ -92,15 +96,16 @@ macro_rules! tb2_gprimm_instr {
         fn $name(self) -> Vec<u8> {
             let (dst, src) = self;
             let mut code = Vec::new();
-    
+
+            if dst.size == 2 { code.push(0x66); }
             push_rex!(code, dst);
-    
-            if src as usize > (1 << ((dst.size*8)-1)) {
+
+            if !opmod::fits_imm(src, dst.size.min(4)) {
                 panic!("Error: imm src is too large for the destination register");
             }
 
             let d32 = src as i32 > i8::MAX as i32 || (src as i32) < i8::MIN as i32;
-    
+
             const OPCODE: [u8; 4] = $opcodes;
             code.push(if d32 {OPCODE[dst.size.trailing_zeros() as usize]} else { 0x83 });
 
@@ -111,15 +116,15 @@ macro_rules! tb2_gprimm_instr {
                     dst.id
                 )
             );
-            
+
             if d32 {
-                for byte in src.to_le_bytes().iter().take(dst.size as usize) {
+                for byte in src.to_le_bytes().iter().take(dst.size.min(4) as usize) {
                     code.push(*byte);
                 }
             } else {
                 code.push(src as u8);
             }
-    
+
             code
         }
     };
@@ -131,37 +136,20 @@ macro_rules! tb1_gprrptr_instr {
         fn $name(self) -> Vec<u8> {
             let (dst, src) = self;
             let mut code = Vec::new();
-    
+
             if dst.size != src.size {
                 panic!("Error: mem and dst reg are not the same size");
             }
-            
-            push_rex!(code, dst);
-    
-            const OPCODE: [u8; 4] = $opcodes;
-            code.push(OPCODE[dst.size.trailing_zeros() as usize] | dst.id);
-    
-            let d32: bool = src.offset > i8::MAX as i32 || src.offset < i8::MIN as i32;
-            
-            code.push(
-                opmod::encode(
-                    if src.offset == 0 {opmod::MEM_ADDR} else if d32 {opmod::MEM_ADDR_DISP32} else {opmod::MEM_ADDR_DISP8},
-                    dst.id,
-                    src.reg.id
-                )
-            );
-    
-            if src.offset != 0 {
-                if d32 {
-                    for byte in src.offset.to_le_bytes().iter().take(4) {
-                        code.push(*byte);
-                    }
-                }
-                else {
-                    code.push(src.offset as u8);
-                }
+            if dst.size == 2 { code.push(0x66); }
+
+            if let Some(rex) = opmod::rex(dst.size, dst.id, src.reg.id) {
+                code.push(rex);
             }
-    
+
+            const OPCODE: [u8; 4] = $opcodes;
+            code.push(OPCODE[dst.size.trailing_zeros() as usize]);
+            code.extend(opmod::encode_mem(dst.id, src.reg.id, src.offset));
+
             code
         }
     };
@@ -173,36 +161,20 @@ macro_rules! tb1_rptrgpr_instr {
         fn $name(self) -> Vec<u8> {
             let (dst, src) = self;
             let mut code = Vec::new();
-    
+
             if dst.size != src.size {
                 panic!("Error: src reg and mem are not the same size");
             }
             if dst.size == 2 { code.push(0x66); }
-    
-            push_rex!(code, src);
-            let d32: bool = dst.offset > i8::MAX as i32 || dst.offset < i8::MIN as i32;
-    
-            const OPCODE: [u8; 4] = $opcodes;
-            code.push(OPCODE[dst.reg.size.trailing_zeros() as usize]);
-            code.push(
-                opmod::encode(
-                    if dst.offset == 0 {opmod::MEM_ADDR} else if d32 {opmod::MEM_ADDR_DISP32} else {opmod::MEM_ADDR_DISP8},
-                    src.id,
-                    dst.reg.id
-                )
-            );
-    
-            if dst.offset != 0 {
-                if d32 {
-                    for byte in dst.offset.to_le_bytes().iter().take(4) {
-                        code.push(*byte);
-                    }
-                }
-                else {
-                    code.push(dst.offset as u8);
-                }
+
+            if let Some(rex) = opmod::rex(src.size, src.id, dst.reg.id) {
+                code.push(rex);
             }
-    
+
+            const OPCODE: [u8; 4] = $opcodes;
+            code.push(OPCODE[dst.size.trailing_zeros() as usize]);
+            code.extend(opmod::encode_mem(src.id, dst.reg.id, dst.offset));
+
             code
         }
     };
@@ -214,35 +186,27 @@ macro_rules! tb1_rptrimm_instr {
         fn $name(self) -> Vec<u8>  {
             let (dst, src) = self;
             let mut code = Vec::new();
-    
+
             if dst.size == 2 { code.push(0x66); }
-    
-            push_rex!(code, GPR{id: dst.reg.id, size: dst.size});
-    
-            if src as usize > (1 << ((dst.size*8)-1)) {
+
+            if let Some(rex) = opmod::rex(dst.size, 0, dst.reg.id) {
+                code.push(rex);
+            }
+
+            if !opmod::fits_imm(src, dst.size.min(4)) {
                 panic!("Error: imm src is too large for the destination register");
             }
-    
+
             const OPCODE: [u8; 4] = $opcodes;
             code.push(OPCODE[dst.size.trailing_zeros() as usize]);
-            code.push(
-                opmod::encode(
-                    if dst.offset == 0 {opmod::MEM_ADDR} else {opmod::MEM_ADDR_DISP8},
-                    $opbits,
-                    dst.reg.id
-                )
-            );
-    
-            if dst.offset != 0 {
-                code.push(dst.offset as u8);
-            }
-    
+            code.extend(opmod::encode_mem($opbits, dst.reg.id, dst.offset));
+
             for byte in src.to_le_bytes().iter().take((dst.size).min(4) as usize) {
                 code.push(*byte);
             }
-    
+
             code
         }
     };
 }
-pub (crate) use tb1_rptrimm_instr;
\ No newline at end of file
+pub (crate) use tb1_rptrimm_instr;
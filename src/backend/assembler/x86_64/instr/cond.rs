@@ -0,0 +1,86 @@
+use super::instructions::*;
+
+/// Condition codes, the low nibble of the `Jcc` and `SETcc` opcodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cond {
+    O = 0x0,
+    NO = 0x1,
+    /// Unsigned `<`
+    B = 0x2,
+    /// Unsigned `>=`
+    AE = 0x3,
+    E = 0x4,
+    NE = 0x5,
+    /// Unsigned `<=`
+    BE = 0x6,
+    /// Unsigned `>`
+    A = 0x7,
+    S = 0x8,
+    NS = 0x9,
+    P = 0xA,
+    NP = 0xB,
+    L = 0xC,
+    GE = 0xD,
+    LE = 0xE,
+    G = 0xF,
+}
+
+impl Cond {
+    /// The condition holding exactly when `self` does not.
+    pub fn negate(self) -> Cond {
+        match self {
+            Cond::O => Cond::NO,
+            Cond::NO => Cond::O,
+            Cond::B => Cond::AE,
+            Cond::AE => Cond::B,
+            Cond::E => Cond::NE,
+            Cond::NE => Cond::E,
+            Cond::BE => Cond::A,
+            Cond::A => Cond::BE,
+            Cond::S => Cond::NS,
+            Cond::NS => Cond::S,
+            Cond::P => Cond::NP,
+            Cond::NP => Cond::P,
+            Cond::L => Cond::GE,
+            Cond::GE => Cond::L,
+            Cond::LE => Cond::G,
+            Cond::G => Cond::LE,
+        }
+    }
+}
+
+pub trait Setcc {
+    fn setcc(self, cond: Cond) -> Vec<u8>;
+}
+
+impl Setcc for GPR {
+    fn setcc(self, cond: Cond) -> Vec<u8> {
+        let mut code = Vec::new();
+        if self.size != 1 {
+            panic!("Error: setcc writes a byte register");
+        }
+        if let Some(rex) = opmod::rex(1, 0, self.id) {
+            code.push(rex);
+        }
+        code.push(0x0F);
+        code.push(0x90 | cond as u8);
+        code.push(opmod::encode(opmod::REG, 0, self.id));
+        code
+    }
+}
+
+pub trait Jcc {
+    fn jcc(self, cond: Cond) -> Vec<u8>;
+}
+
+impl Jcc for isize {
+    fn jcc(self, cond: Cond) -> Vec<u8> {
+        let addr_rel = self;
+        let mut code = vec![0x0F, 0x80 | cond as u8];
+        for byte in (addr_rel-6).to_le_bytes().iter().take(4) {
+            code.push(*byte);
+        }
+
+        code
+    }
+}
@@ -0,0 +1,34 @@
+use super::instructions::*;
+
+pub trait Div {
+    fn div(self) -> Vec<u8>;
+}
+
+pub trait Idiv {
+    fn idiv(self) -> Vec<u8>;
+}
+
+fn group3(src: GPR, opbits: u8) -> Vec<u8> {
+    let mut code = Vec::new();
+    if src.size == 2 { code.push(0x66); }
+    if let Some(rex) = opmod::rex(src.size, 0, src.id) {
+        code.push(rex);
+    }
+    code.push(if src.size == 1 { 0xF6 } else { 0xF7 });
+    code.push(opmod::encode(opmod::REG, opbits, src.id));
+    code
+}
+
+/// Unsigned division of `rdx:rax` by the register.
+impl Div for GPR {
+    fn div(self) -> Vec<u8> {
+        group3(self, 0b110)
+    }
+}
+
+/// Signed division of `rdx:rax` by the register.
+impl Idiv for GPR {
+    fn idiv(self) -> Vec<u8> {
+        group3(self, 0b111)
+    }
+}
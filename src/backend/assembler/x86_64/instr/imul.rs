@@ -0,0 +1,25 @@
+use super::instructions::*;
+
+pub trait Imul {
+    fn imul(self) -> Vec<u8>;
+}
+
+impl Imul for (GPR, GPR) {
+    fn imul(self) -> Vec<u8> {
+        let (dst, src) = self;
+        let mut code = Vec::new();
+
+        if dst.size != src.size || dst.size == 1 {
+            panic!("Error: imul needs same-sized registers of at least 16 bits");
+        }
+        if dst.size == 2 { code.push(0x66); }
+
+        if let Some(rex) = opmod::rex(dst.size, dst.id, src.id) {
+            code.push(rex);
+        }
+        code.push(0x0F);
+        code.push(0xAF);
+        code.push(opmod::encode(opmod::REG, dst.id, src.id));
+        code
+    }
+}
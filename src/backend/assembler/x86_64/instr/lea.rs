@@ -1,4 +1,4 @@
-use super::instructions::{*, enc::opmod::push_rex};
+use super::instructions::*;
 
 pub trait Lea {
     fn lea(self) -> Vec<u8>;
@@ -8,33 +8,31 @@ impl Lea for (GPR, RegPtr) {
     fn lea(self) -> Vec<u8> {
         let (dst, src) = self;
         let mut code = Vec::new();
-        push_rex!(code, dst);
-        code.push(0x8D);
 
-        // support only 32-bit displacement
-        if src.reg.id == 16 {
-            // RIP-relative addressing
+        if src.reg == RIP {
+            // RIP-relative addressing, always a 32-bit displacement
+            if let Some(rex) = opmod::rex(dst.size, dst.id, 0) {
+                code.push(rex);
+            }
+            code.push(0x8D);
             code.push(
                 enc::opmod::encode(
-                    0b00,
+                    opmod::MEM_ADDR,
                     dst.id,
                     0b101
                 )
             );
+            for byte in src.offset.to_le_bytes().iter().take(4) {
+                code.push(*byte);
+            }
         } else {
-            code.push(
-                enc::opmod::encode(
-                    0b10,
-                    dst.id,
-                    0b101
-                )
-            );
+            if let Some(rex) = opmod::rex(dst.size, dst.id, src.reg.id) {
+                code.push(rex);
+            }
+            code.push(0x8D);
+            code.extend(opmod::encode_mem(dst.id, src.reg.id, src.offset));
         }
 
-        for byte in src.offset.to_le_bytes().iter().take(4) {
-            code.push(*byte);
-        }
-        
         code
     }
 }
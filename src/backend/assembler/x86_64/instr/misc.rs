@@ -0,0 +1,12 @@
+pub fn ret() -> Vec<u8> {
+    vec![0xC3]
+}
+
+pub fn leave() -> Vec<u8> {
+    vec![0xC9]
+}
+
+/// Sign-extends `rax` into `rdx:rax`.
+pub fn cqo() -> Vec<u8> {
+    vec![0x48, 0x99]
+}
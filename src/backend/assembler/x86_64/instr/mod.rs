@@ -1,19 +1,47 @@
 pub mod common;
 
 pub mod mov;
+pub mod movx;
 pub mod add;
 pub mod sub;
+pub mod imul;
+pub mod mul;
+pub mod div;
+pub mod and;
+pub mod or;
+pub mod xor;
+pub mod cmp;
+pub mod test;
+pub mod shift;
+pub mod push;
+pub mod pop;
 pub mod jmp;
+pub mod cond;
 pub mod call;
 pub mod lea;
+pub mod misc;
 
 pub mod instructions {
     pub use super::super::{x86::opmod, regs::*, mem::*, enc};
 
     pub use super::mov::Mov;
+    pub use super::movx::{Movzx, Movsx};
     pub use super::add::Add;
     pub use super::sub::Sub;
+    pub use super::imul::Imul;
+    pub use super::mul::Mul;
+    pub use super::div::{Div, Idiv};
+    pub use super::and::And;
+    pub use super::or::Or;
+    pub use super::xor::Xor;
+    pub use super::cmp::Cmp;
+    pub use super::test::Test;
+    pub use super::shift::{Shl, Shr, Sar};
+    pub use super::push::Push;
+    pub use super::pop::Pop;
     pub use super::jmp::Jmp;
+    pub use super::cond::{Cond, Setcc, Jcc};
     pub use super::call::Call;
     pub use super::lea::Lea;
-}
\ No newline at end of file
+    pub use super::misc;
+}
@@ -0,0 +1,76 @@
+use super::instructions::*;
+
+pub trait Movzx {
+    fn movzx(self) -> Vec<u8>;
+}
+
+pub trait Movsx {
+    fn movsx(self) -> Vec<u8>;
+}
+
+/// Opcode of the extending move for a source of `size` bytes.
+fn ext_opcode(size: u8, signed: bool) -> Vec<u8> {
+    match (size, signed) {
+        (1, false) => vec![0x0F, 0xB6],
+        (2, false) => vec![0x0F, 0xB7],
+        (1, true) => vec![0x0F, 0xBE],
+        (2, true) => vec![0x0F, 0xBF],
+        // movsxd
+        (4, true) => vec![0x63],
+        _ => panic!("Error: cannot extend a {} byte operand", size),
+    }
+}
+
+fn ext_rr(dst: GPR, src: GPR, signed: bool) -> Vec<u8> {
+    let mut code = Vec::new();
+    if dst.size <= src.size {
+        panic!("Error: extension must widen the operand");
+    }
+    if dst.size == 2 { code.push(0x66); }
+    // Without a REX prefix, even an empty one, sil/dil/spl/bpl would read as dh/bh/ah/ch
+    let byte_rex = (src.size == 1 && src.id > 3).then_some(0x40);
+    if let Some(rex) = opmod::rex(dst.size, dst.id, src.id).or(byte_rex) {
+        code.push(rex);
+    }
+    code.extend(ext_opcode(src.size, signed));
+    code.push(opmod::encode(opmod::REG, dst.id, src.id));
+    code
+}
+
+fn ext_rm(dst: GPR, src: RegPtr, signed: bool) -> Vec<u8> {
+    let mut code = Vec::new();
+    if dst.size <= src.size {
+        panic!("Error: extension must widen the operand");
+    }
+    if dst.size == 2 { code.push(0x66); }
+    if let Some(rex) = opmod::rex(dst.size, dst.id, src.reg.id) {
+        code.push(rex);
+    }
+    code.extend(ext_opcode(src.size, signed));
+    code.extend(opmod::encode_mem(dst.id, src.reg.id, src.offset));
+    code
+}
+
+impl Movzx for (GPR, GPR) {
+    fn movzx(self) -> Vec<u8> {
+        ext_rr(self.0, self.1, false)
+    }
+}
+
+impl Movzx for (GPR, RegPtr) {
+    fn movzx(self) -> Vec<u8> {
+        ext_rm(self.0, self.1, false)
+    }
+}
+
+impl Movsx for (GPR, GPR) {
+    fn movsx(self) -> Vec<u8> {
+        ext_rr(self.0, self.1, true)
+    }
+}
+
+impl Movsx for (GPR, RegPtr) {
+    fn movsx(self) -> Vec<u8> {
+        ext_rm(self.0, self.1, true)
+    }
+}
@@ -0,0 +1,19 @@
+use super::instructions::*;
+
+pub trait Mul {
+    fn mul(self) -> Vec<u8>;
+}
+
+/// Unsigned widening multiplication of `rax` by the register into `rdx:rax`.
+impl Mul for GPR {
+    fn mul(self) -> Vec<u8> {
+        let mut code = Vec::new();
+        if self.size == 2 { code.push(0x66); }
+        if let Some(rex) = opmod::rex(self.size, 0, self.id) {
+            code.push(rex);
+        }
+        code.push(if self.size == 1 { 0xF6 } else { 0xF7 });
+        code.push(opmod::encode(opmod::REG, 0b100, self.id));
+        code
+    }
+}
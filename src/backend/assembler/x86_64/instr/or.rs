@@ -0,0 +1,10 @@
+use super::instructions::{*, enc::opmod::push_rex};
+use super::common;
+
+pub trait Or {
+    fn or(self) -> Vec<u8>;
+}
+
+impl Or for (GPR, GPR) {
+    common::tb1_gprgpr_instr!(or, [0x08, 0x09, 0x09, 0x09]);
+}
@@ -0,0 +1,14 @@
+pub trait Pop {
+    fn pop(self) -> Vec<u8>;
+}
+
+impl Pop for super::instructions::GPR {
+    fn pop(self) -> Vec<u8> {
+        let mut code = Vec::new();
+        if self.id > 7 {
+            code.push(0x41);
+        }
+        code.push(0x58 | (self.id & 0x7));
+        code
+    }
+}
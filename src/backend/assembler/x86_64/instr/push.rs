@@ -0,0 +1,14 @@
+pub trait Push {
+    fn push(self) -> Vec<u8>;
+}
+
+impl Push for super::instructions::GPR {
+    fn push(self) -> Vec<u8> {
+        let mut code = Vec::new();
+        if self.id > 7 {
+            code.push(0x41);
+        }
+        code.push(0x50 | (self.id & 0x7));
+        code
+    }
+}
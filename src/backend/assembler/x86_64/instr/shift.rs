@@ -0,0 +1,59 @@
+use super::instructions::*;
+
+pub trait Shl {
+    fn shl(self) -> Vec<u8>;
+}
+
+pub trait Shr {
+    fn shr(self) -> Vec<u8>;
+}
+
+pub trait Sar {
+    fn sar(self) -> Vec<u8>;
+}
+
+fn group2(dst: GPR, opbits: u8, imm: Option<u8>) -> Vec<u8> {
+    let mut code = Vec::new();
+    if dst.size == 2 { code.push(0x66); }
+    if let Some(rex) = opmod::rex(dst.size, 0, dst.id) {
+        code.push(rex);
+    }
+    let byte = dst.size == 1;
+    code.push(match (imm.is_some(), byte) {
+        (true, true) => 0xC0,
+        (true, false) => 0xC1,
+        (false, true) => 0xD2,
+        (false, false) => 0xD3,
+    });
+    code.push(opmod::encode(opmod::REG, opbits, dst.id));
+    if let Some(imm) = imm {
+        code.push(imm);
+    }
+    code
+}
+
+macro_rules! shift_instr {
+    ($trait:ident, $name:ident, $opbits:expr) => {
+        impl $trait for (GPR, isize) {
+            fn $name(self) -> Vec<u8> {
+                let (dst, count) = self;
+                group2(dst, $opbits, Some(count as u8))
+            }
+        }
+
+        /// The count register can only be `cl`.
+        impl $trait for (GPR, GPR) {
+            fn $name(self) -> Vec<u8> {
+                let (dst, count) = self;
+                if count != CL {
+                    panic!("Error: shift count must be in cl");
+                }
+                group2(dst, $opbits, None)
+            }
+        }
+    };
+}
+
+shift_instr!(Shl, shl, 0b100);
+shift_instr!(Shr, shr, 0b101);
+shift_instr!(Sar, sar, 0b111);
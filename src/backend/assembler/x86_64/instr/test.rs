@@ -0,0 +1,10 @@
+use super::instructions::{*, enc::opmod::push_rex};
+use super::common;
+
+pub trait Test {
+    fn test(self) -> Vec<u8>;
+}
+
+impl Test for (GPR, GPR) {
+    common::tb1_gprgpr_instr!(test, [0x84, 0x85, 0x85, 0x85]);
+}
@@ -0,0 +1,10 @@
+use super::instructions::{*, enc::opmod::push_rex};
+use super::common;
+
+pub trait Xor {
+    fn xor(self) -> Vec<u8>;
+}
+
+impl Xor for (GPR, GPR) {
+    common::tb1_gprgpr_instr!(xor, [0x30, 0x31, 0x31, 0x31]);
+}
@@ -11,6 +11,26 @@ impl GPR {
         }
     }
 }
+impl std::fmt::Display for GPR {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        const LEGACY: [&str; 8] = ["ax", "cx", "dx", "bx", "sp", "bp", "si", "di"];
+        if self.id == 16 {
+            return write!(f, "rip");
+        }
+        if self.id >= 8 {
+            let suffix = match self.size { 1 => "b", 2 => "w", 4 => "d", _ => "" };
+            return write!(f, "r{}{}", self.id, suffix);
+        }
+        let name = LEGACY[self.id as usize];
+        match self.size {
+            8 => write!(f, "r{}", name),
+            4 => write!(f, "e{}", name),
+            2 => write!(f, "{}", name),
+            _ if self.id < 4 => write!(f, "{}l", &name[..1]),
+            _ => write!(f, "{}l", name),
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SEG {
@@ -0,0 +1,180 @@
+//! System V AMD64 calling convention.
+//!
+//! Values are split in eightbytes, each classified as INTEGER or SSE, and
+//! passed in the matching register file while enough registers are left.
+//! Anything larger than two eightbytes, or that does not fit anymore, goes
+//! on the stack in MEMORY class.
+
+use crate::backend::assembler::x86_64::x86::{self, GPR};
+use crate::backend::ir;
+
+pub const INT_ARGS: [GPR; 6] = [x86::RDI, x86::RSI, x86::RDX, x86::RCX, x86::R8, x86::R9];
+pub const INT_RETS: [GPR; 2] = [x86::RAX, x86::RDX];
+/// xmm0 to xmm7
+pub const SSE_ARGS: u8 = 8;
+/// xmm0 and xmm1
+pub const SSE_RETS: u8 = 2;
+
+/// Clobbered by any call.
+pub const CALLER_SAVED: [GPR; 9] = [x86::RAX, x86::RCX, x86::RDX, x86::RSI, x86::RDI, x86::R8, x86::R9, x86::R10, x86::R11];
+/// Must hold the same value when returning to the caller.
+pub const CALLEE_SAVED: [GPR; 6] = [x86::RBX, x86::RBP, x86::R12, x86::R13, x86::R14, x86::R15];
+
+/// rsp must be a multiple of this at every call instruction.
+pub const STACK_ALIGN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Class {
+    /// Padding only.
+    NoClass,
+    Integer,
+    Sse,
+    Memory,
+}
+
+impl Class {
+    fn merge(self, other: Class) -> Class {
+        match (self, other) {
+            (Class::NoClass, c) | (c, Class::NoClass) => c,
+            (Class::Memory, _) | (_, Class::Memory) => Class::Memory,
+            (Class::Integer, _) | (_, Class::Integer) => Class::Integer,
+            _ => Class::Sse,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Slot {
+    Gpr(GPR),
+    /// Index of an xmm register.
+    Xmm(u8),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Location {
+    /// Nothing is passed, the value is void.
+    Empty,
+    /// One register per eightbyte.
+    Regs(Vec<Slot>),
+    /// Offset of the value from rsp at the call instruction.
+    Stack { offset: usize },
+}
+
+fn align_to(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+/// Marks the eightbytes covered by `v_type` placed at `offset`.
+/// Returns `false` if a field is not naturally aligned.
+fn classify_into(v_type: &ir::DataType, offset: usize, classes: &mut [Class]) -> bool {
+    let class = match v_type {
+        ir::DataType::Scalar { .. } | ir::DataType::Pointer { .. } => Class::Integer,
+        ir::DataType::Float { .. } => Class::Sse,
+        ir::DataType::Array { target, capacity } => {
+            let stride = target.size() as usize;
+            return (0..*capacity as usize).all(|i| classify_into(target, offset + i * stride, classes));
+        }
+        ir::DataType::Struct { fields, .. } => {
+            return fields.iter().zip(v_type.field_offsets())
+                .all(|((_, t), field)| classify_into(t, offset + field as usize, classes));
+        }
+    };
+    let size = v_type.size() as usize;
+    if size == 0 {
+        return true;
+    }
+    if !offset.is_multiple_of(v_type.align().max(1) as usize) {
+        return false;
+    }
+    for eightbyte in &mut classes[offset / 8..=(offset + size - 1) / 8] {
+        *eightbyte = eightbyte.merge(class);
+    }
+    true
+}
+
+/// Classifies each eightbyte of `v_type`. Values passed in memory yield a
+/// single `Class::Memory`, void yields nothing.
+pub fn classify(v_type: &ir::DataType) -> Vec<Class> {
+    let size = v_type.size() as usize;
+    if size == 0 {
+        return vec![];
+    }
+    if size > 16 {
+        return vec![Class::Memory];
+    }
+    let mut classes = vec![Class::NoClass; size.div_ceil(8)];
+    if !classify_into(v_type, 0, &mut classes) || classes.contains(&Class::Memory) {
+        return vec![Class::Memory];
+    }
+    // An all padding eightbyte still takes a register
+    classes.iter().map(|c| if *c == Class::NoClass { Class::Sse } else { *c }).collect()
+}
+
+/// Where every argument and the return value of a call live.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallLayout {
+    pub arguments: Vec<Location>,
+    pub ret: Location,
+    /// The return value is written to memory whose address the caller
+    /// passes in rdi, and which the callee returns in rax.
+    pub indirect_ret: bool,
+    /// Bytes of stack arguments, a multiple of 8.
+    pub stack_size: usize,
+    /// Vector registers used, passed in al to variadic functions.
+    pub sse_used: u8,
+}
+
+impl CallLayout {
+    pub fn new(arguments: &[ir::DataType], return_type: &ir::DataType) -> Self {
+        let mut ints = 0;
+        let mut sses = 0;
+
+        let classes = classify(return_type);
+        let indirect_ret = classes.first() == Some(&Class::Memory);
+        let ret = if classes.is_empty() {
+            Location::Empty
+        } else if indirect_ret {
+            ints += 1;
+            Location::Regs(vec![Slot::Gpr(x86::RAX)])
+        } else {
+            let (mut ret_ints, mut ret_sses) = (0, 0);
+            Location::Regs(classes.iter().map(|class| match class {
+                Class::Integer => { ret_ints += 1; Slot::Gpr(INT_RETS[ret_ints - 1].clone()) }
+                _ => { ret_sses += 1; Slot::Xmm(ret_sses - 1) }
+            }).collect())
+        };
+
+        let mut stack_size = 0;
+        let arguments = arguments.iter().map(|v_type| {
+            let classes = classify(v_type);
+            if classes.is_empty() {
+                return Location::Empty;
+            }
+            let need_ints = classes.iter().filter(|c| **c == Class::Integer).count();
+            let need_sses = classes.iter().filter(|c| **c == Class::Sse).count() as u8;
+            if classes[0] != Class::Memory && ints + need_ints <= INT_ARGS.len() && sses + need_sses <= SSE_ARGS {
+                return Location::Regs(classes.iter().map(|class| match class {
+                    Class::Integer => { ints += 1; Slot::Gpr(INT_ARGS[ints - 1].clone()) }
+                    _ => { sses += 1; Slot::Xmm(sses - 1) }
+                }).collect());
+            }
+            // Eightbytes are never split between registers and the stack
+            let offset = align_to(stack_size, (v_type.align() as usize).max(8));
+            stack_size = offset + align_to(v_type.size() as usize, 8);
+            Location::Stack { offset }
+        }).collect();
+
+        CallLayout {
+            arguments,
+            ret,
+            indirect_ret,
+            stack_size,
+            sse_used: sses,
+        }
+    }
+
+    pub fn of(function: &ir::Function) -> Self {
+        let arguments: Vec<ir::DataType> = function.arguments.iter().map(|(_, t)| t.clone()).collect();
+        Self::new(&arguments, &function.return_type)
+    }
+}
@@ -0,0 +1,356 @@
+//! Machine code emission for an allocated `VFunction`.
+
+use super::abi::{self, Location, Slot};
+use super::regalloc::{Allocation, Loc};
+use super::vreg::{AluOp, Inst, ShiftOp, VFunction, VReg};
+use crate::backend::assembler::x86_64::x86::{self, GPR, RegPtr, instructions::*};
+use crate::backend::writer::CodeBuilder;
+
+/// Reload registers for spilled operands, never handed out by the allocator.
+const SCRATCH: GPR = x86::R10;
+const SCRATCH_ALT: GPR = x86::R11;
+
+/// A register or a qword of the frame.
+#[derive(Debug, Clone, PartialEq)]
+enum Place {
+    Reg(GPR),
+    Mem(RegPtr),
+}
+
+pub struct Emitter<'a> {
+    cb: &'a mut CodeBuilder,
+    alloc: &'a Allocation,
+    /// Frame offset right above the callee-saved register slots.
+    saved_base: i32,
+    name: String,
+    /// Block laid out right after the current one, reached without a jump.
+    next: usize,
+}
+
+impl<'a> Emitter<'a> {
+    pub fn new(cb: &'a mut CodeBuilder, func: &VFunction, alloc: &'a Allocation) -> Self {
+        Emitter {
+            cb,
+            alloc,
+            saved_base: alloc.spill_size,
+            name: func.name.clone(),
+            next: 1,
+        }
+    }
+
+    fn place(&self, vreg: VReg) -> Place {
+        match self.alloc.loc(vreg) {
+            Loc::Reg(reg) => Place::Reg(reg.clone()),
+            Loc::Spill(offset) => Place::Mem(x86::ptr(x86::RBP, -offset, 8)),
+        }
+    }
+
+    fn mov(&mut self, dst: &Place, src: &Place) {
+        match (dst, src) {
+            _ if dst == src => {}
+            (Place::Reg(dst), Place::Reg(src)) => self.cb.add_bytes((dst.clone(), src.clone()).mov()),
+            (Place::Reg(dst), Place::Mem(src)) => self.cb.add_bytes((dst.clone(), src.clone()).mov()),
+            (Place::Mem(dst), Place::Reg(src)) => self.cb.add_bytes((dst.clone(), src.clone()).mov()),
+            (Place::Mem(dst), Place::Mem(src)) => {
+                self.cb.add_bytes((SCRATCH, src.clone()).mov());
+                self.cb.add_bytes((dst.clone(), SCRATCH).mov());
+            }
+        }
+    }
+
+    /// Performs all `(dst, src)` moves as if simultaneously.
+    fn parallel_move(&mut self, mut moves: Vec<(Place, Place)>) {
+        moves.retain(|(dst, src)| dst != src);
+        while !moves.is_empty() {
+            let ready = moves.iter().position(|(dst, _)| !moves.iter().any(|(_, src)| src == dst));
+            match ready {
+                Some(i) => {
+                    let (dst, src) = moves.remove(i);
+                    self.mov(&dst, &src);
+                }
+                None => {
+                    // Every destination is still to be read: break the cycle
+                    // by moving one of them out of the way
+                    let blocked = moves[0].0.clone();
+                    let temp = Place::Reg(SCRATCH_ALT);
+                    self.mov(&temp, &blocked);
+                    for (_, src) in moves.iter_mut().filter(|(_, src)| *src == blocked) {
+                        *src = temp.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Register holding `vreg`, reloaded into `scratch` if spilled.
+    fn use_reg(&mut self, vreg: VReg, scratch: GPR) -> GPR {
+        match self.place(vreg) {
+            Place::Reg(reg) => reg,
+            mem => {
+                self.mov(&Place::Reg(scratch.clone()), &mem);
+                scratch
+            }
+        }
+    }
+
+    /// Register to compute `vreg` into, to be passed to `def_done`.
+    fn def_reg(&self, vreg: VReg, scratch: GPR) -> GPR {
+        match self.place(vreg) {
+            Place::Reg(reg) => reg,
+            Place::Mem(_) => scratch,
+        }
+    }
+
+    fn def_done(&mut self, vreg: VReg, reg: GPR) {
+        let place = self.place(vreg);
+        self.mov(&place, &Place::Reg(reg));
+    }
+
+    fn mov_imm(&mut self, dst: &GPR, value: i64) {
+        if (0..=u32::MAX as i64).contains(&value) {
+            // 32-bit moves zero the upper half
+            self.cb.add_bytes((GPR::new(dst.id, 4), value as isize).mov());
+        } else {
+            self.cb.add_bytes((GPR::new(dst.id, 8), value as isize).mov());
+        }
+    }
+
+    /// Extends the low `size` bytes of `reg` over the whole register.
+    fn extend(&mut self, reg: &GPR, size: u8, signed: bool) {
+        let low = GPR::new(reg.id, size);
+        match (size, signed) {
+            (8, _) => {}
+            (4, true) => self.cb.add_bytes((reg.clone(), low).movsx()),
+            (4, false) => self.cb.add_bytes((low.clone(), low).mov()),
+            (_, true) => self.cb.add_bytes((reg.clone(), low).movsx()),
+            (_, false) => self.cb.add_bytes((GPR::new(reg.id, 4), low).movzx()),
+        }
+    }
+
+    fn block_label(&self, block: usize) -> String {
+        match block {
+            0 => self.name.clone(),
+            _ => format!(".{}.b{}", self.name, block),
+        }
+    }
+
+    fn jump(&mut self, target: usize) {
+        if target != self.next {
+            self.cb.symbol_jmp(self.block_label(target));
+        }
+    }
+
+    fn epilogue(&mut self) {
+        for (i, reg) in self.alloc.callee_saved.clone().iter().enumerate() {
+            let slot = x86::ptr(x86::RBP, -(self.saved_base + 8 * (i as i32 + 1)), 8);
+            self.cb.add_bytes((reg.clone(), slot).mov());
+        }
+        self.cb.add_bytes(misc::leave());
+        self.cb.add_bytes(misc::ret());
+    }
+
+    fn prologue(&mut self) {
+        self.cb.add_bytes(x86::RBP.push());
+        self.cb.add_bytes((x86::RBP, x86::RSP).mov());
+        let saved = 8 * self.alloc.callee_saved.len() as i32;
+        let frame = (self.saved_base + saved + 15) / 16 * 16;
+        if frame > 0 {
+            self.cb.add_bytes((x86::RSP, frame as isize).sub());
+        }
+        for (i, reg) in self.alloc.callee_saved.clone().iter().enumerate() {
+            let slot = x86::ptr(x86::RBP, -(self.saved_base + 8 * (i as i32 + 1)), 8);
+            self.cb.add_bytes((slot, reg.clone()).mov());
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[VReg], layout: &abi::CallLayout, ret: &Option<(VReg, u8, bool)>, variadic: bool) {
+        // Frames are 16-byte aligned, only the stack arguments can misalign rsp
+        let stack_slots = layout.stack_size / 8;
+        let pad = stack_slots % (abi::STACK_ALIGN / 8);
+        if pad != 0 {
+            self.cb.add_bytes((x86::RSP, 8).sub());
+        }
+
+        let mut moves = Vec::new();
+        for (arg, location) in args.iter().zip(layout.arguments.iter()).rev() {
+            match location {
+                Location::Stack { .. } => {
+                    let reg = self.use_reg(*arg, SCRATCH);
+                    self.cb.add_bytes(reg.push());
+                }
+                Location::Regs(slots) => {
+                    if let [Slot::Gpr(reg)] = slots.as_slice() {
+                        moves.push((Place::Reg(reg.clone()), self.place(*arg)));
+                    }
+                }
+                Location::Empty => {}
+            }
+        }
+        self.parallel_move(moves);
+
+        if variadic {
+            self.mov_imm(&x86::RAX, layout.sse_used as i64);
+        }
+        self.cb.symbol_call(name.to_string());
+
+        let cleanup = stack_slots + pad;
+        if cleanup > 0 {
+            self.cb.add_bytes((x86::RSP, 8 * cleanup as isize).add());
+        }
+        if let Some((dst, size, signed)) = ret {
+            // The upper bits of a narrow return value are undefined
+            self.extend(&x86::RAX, *size, *signed);
+            self.def_done(*dst, x86::RAX);
+        }
+    }
+
+    fn inst(&mut self, inst: &Inst) {
+        match inst {
+            Inst::Entry { args } => {
+                let moves = args.iter().filter_map(|(vreg, location)| {
+                    let src = match location {
+                        Location::Regs(slots) => match slots.as_slice() {
+                            [Slot::Gpr(reg)] => Place::Reg(reg.clone()),
+                            _ => return None,
+                        },
+                        // Above the saved rbp and the return address
+                        Location::Stack { offset } => Place::Mem(x86::ptr(x86::RBP, 16 + *offset as i32, 8)),
+                        Location::Empty => return None,
+                    };
+                    Some((self.place(*vreg), src))
+                }).collect();
+                self.parallel_move(moves);
+            }
+            Inst::Imm { dst, value } => {
+                let reg = self.def_reg(*dst, SCRATCH);
+                self.mov_imm(&reg, *value);
+                self.def_done(*dst, reg);
+            }
+            Inst::Symbol { dst, name } => {
+                let reg = self.def_reg(*dst, SCRATCH);
+                self.cb.symbol_lea(reg.clone(), name.clone());
+                self.def_done(*dst, reg);
+            }
+            Inst::Copy { dst, src } => {
+                let (dst, src) = (self.place(*dst), self.place(*src));
+                self.mov(&dst, &src);
+            }
+            Inst::Alu { op, dst, src } => {
+                let d = self.use_reg(*dst, SCRATCH);
+                let s = self.use_reg(*src, SCRATCH_ALT);
+                self.cb.add_bytes(match op {
+                    AluOp::Add => (d.clone(), s).add(),
+                    AluOp::Sub => (d.clone(), s).sub(),
+                    AluOp::Mul => (d.clone(), s).imul(),
+                    AluOp::And => (d.clone(), s).and(),
+                    AluOp::Or => (d.clone(), s).or(),
+                    AluOp::Xor => (d.clone(), s).xor(),
+                });
+                self.def_done(*dst, d);
+            }
+            Inst::Div { dst, src, signed, remainder } => {
+                let place = self.place(*dst);
+                self.mov(&Place::Reg(x86::RAX), &place);
+                let s = self.use_reg(*src, SCRATCH_ALT);
+                if *signed {
+                    self.cb.add_bytes(misc::cqo());
+                    self.cb.add_bytes(s.idiv());
+                } else {
+                    self.cb.add_bytes((x86::EDX, x86::EDX).xor());
+                    self.cb.add_bytes(s.div());
+                }
+                // The quotient is left in `rax`, the remainder in `rdx`
+                self.def_done(*dst, if *remainder { x86::RDX } else { x86::RAX });
+            }
+            Inst::Shift { op, dst, src } => {
+                let place = self.place(*src);
+                self.mov(&Place::Reg(x86::RCX), &place);
+                let d = self.use_reg(*dst, SCRATCH);
+                self.cb.add_bytes(match op {
+                    ShiftOp::Shl => (d.clone(), x86::CL).shl(),
+                    ShiftOp::Shr => (d.clone(), x86::CL).shr(),
+                    ShiftOp::Sar => (d.clone(), x86::CL).sar(),
+                });
+                self.def_done(*dst, d);
+            }
+            Inst::MulHigh { dst, magic, shift } => {
+                let place = self.place(*dst);
+                self.mov(&Place::Reg(x86::RAX), &place);
+                self.mov_imm(&x86::RCX, *magic as i64);
+                self.cb.add_bytes(x86::RCX.mul());
+                if *shift >= 64 {
+                    self.cb.add_bytes((x86::RAX, x86::RDX).mov());
+                    if *shift > 64 {
+                        self.cb.add_bytes((x86::RAX, (*shift - 64) as isize).shr());
+                    }
+                } else {
+                    self.cb.add_bytes((x86::RDX, (64 - *shift) as isize).shl());
+                    self.cb.add_bytes((x86::RAX, *shift as isize).shr());
+                    self.cb.add_bytes((x86::RAX, x86::RDX).or());
+                }
+                self.def_done(*dst, x86::RAX);
+            }
+            Inst::Extend { dst, size, signed } => {
+                let d = self.use_reg(*dst, SCRATCH);
+                self.extend(&d, *size, *signed);
+                self.def_done(*dst, d);
+            }
+            Inst::Cmp { cond, dst, left, right } => {
+                let l = self.use_reg(*left, SCRATCH);
+                let r = self.use_reg(*right, SCRATCH_ALT);
+                self.cb.add_bytes((l, r).cmp());
+                // dst may share a register with an operand, the flags are set by now
+                let d = self.def_reg(*dst, SCRATCH);
+                self.cb.add_bytes(GPR::new(d.id, 1).setcc(*cond));
+                self.cb.add_bytes((GPR::new(d.id, 4), GPR::new(d.id, 1)).movzx());
+                self.def_done(*dst, d);
+            }
+            Inst::Branch { cond, left, right, then, otherwise } => {
+                let l = self.use_reg(*left, SCRATCH);
+                match right {
+                    Some(right) => {
+                        let r = self.use_reg(*right, SCRATCH_ALT);
+                        self.cb.add_bytes((l, r).cmp());
+                    }
+                    None => self.cb.add_bytes((l.clone(), l).test()),
+                }
+                if *then == self.next {
+                    self.cb.symbol_jcc(cond.negate(), self.block_label(*otherwise));
+                } else {
+                    self.cb.symbol_jcc(*cond, self.block_label(*then));
+                    self.jump(*otherwise);
+                }
+            }
+            Inst::Jump { target } => self.jump(*target),
+            Inst::Call { name, args, layout, ret, variadic } => {
+                self.call(name, args, layout, ret, *variadic);
+            }
+            Inst::Ret { value } => {
+                if let Some(value) = value {
+                    let place = self.place(*value);
+                    self.mov(&Place::Reg(x86::RAX), &place);
+                }
+                self.epilogue();
+            }
+        }
+    }
+
+    pub fn emit(&mut self, func: &VFunction) {
+        let label = self.cb.add_label(func.name.clone());
+        self.cb.bind_label(label);
+        if func.global {
+            self.cb.set_global();
+        }
+        self.prologue();
+        for (i, block) in func.blocks.iter().enumerate() {
+            if i > 0 {
+                let label = self.cb.add_label(self.block_label(i));
+                self.cb.bind_label(label);
+            }
+            self.next = i + 1;
+            for inst in block.insts.iter() {
+                self.inst(inst);
+            }
+        }
+    }
+}
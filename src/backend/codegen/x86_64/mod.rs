@@ -0,0 +1,76 @@
+use object::SectionKind;
+
+use crate::backend::ir;
+use crate::backend::writer::CodeBuilder;
+use crate::reporter::asm::AssemblerError;
+
+pub mod abi;
+pub mod vreg;
+pub mod select;
+pub mod regalloc;
+pub mod emit;
+
+/// Generates x86-64 code for a whole `ir::Module` into a `CodeBuilder`.
+///
+/// Each function goes through instruction selection into virtual
+/// registers, linear scan register allocation, and emission.
+pub struct Codegen<'a> {
+    cb: &'a mut CodeBuilder,
+    strings: Vec<String>,
+    dumps: Vec<String>,
+}
+
+impl<'a> Codegen<'a> {
+    pub fn new(cb: &'a mut CodeBuilder) -> Self {
+        Codegen {
+            cb,
+            strings: Vec::new(),
+            dumps: Vec::new(),
+        }
+    }
+
+    fn gen_function(&mut self, function: &ir::Function) -> Result<(), AssemblerError> {
+        if function.flags & ir::flags::FunctionModifer::Extern as u16 != 0 {
+            self.cb.add_extern(function.mangled.clone());
+            return Ok(());
+        }
+
+        let func = select::Selector::new(function, &mut self.strings).select(function)?;
+        let alloc = regalloc::allocate(&func);
+        self.dumps.push(alloc.dump(&func));
+        emit::Emitter::new(self.cb, &func, &alloc).emit(&func);
+        Ok(())
+    }
+
+    pub fn generate(&mut self, module: &ir::Module) -> Result<(), AssemblerError> {
+        let text = self.cb.add_section(".text".to_string());
+        self.cb.bind_section(text);
+
+        // HashMap order is not stable, keep the output reproducible
+        let mut functions: Vec<&ir::Function> = module.functions.values().collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        for function in functions {
+            self.gen_function(function)?;
+        }
+
+        if !self.strings.is_empty() {
+            let rodata = self.cb.add_section(".rodata".to_string());
+            self.cb.bind_section(rodata);
+            self.cb.set_kind(SectionKind::ReadOnlyData);
+            for (i, string) in self.strings.iter().enumerate() {
+                let label = self.cb.add_label(format!(".str{}", i));
+                self.cb.bind_label(label);
+                self.cb.add_bytes(string.as_bytes().to_vec());
+                self.cb.add_byte(0);
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the register assignments of every generated function.
+    pub fn dump_allocations(&self) {
+        for dump in self.dumps.iter() {
+            println!("{}", dump);
+        }
+    }
+}
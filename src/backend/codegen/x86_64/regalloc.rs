@@ -0,0 +1,248 @@
+//! Linear scan register allocation (Poletto & Sarkar).
+//!
+//! Liveness is computed per block until a fixed point, then every virtual
+//! register gets a single lifetime interval over the linear order of the
+//! instructions. Intervals are scanned by start point; when no register is
+//! left, the interval ending last is spilled to a frame slot.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use super::abi::{self, Location, Slot};
+use super::vreg::{Inst, VFunction, VReg};
+use crate::backend::assembler::x86_64::x86::{self, GPR};
+
+/// Registers handed out to virtual registers, caller-saved ones first.
+/// rax, rcx and rdx are kept for instructions with fixed operands, r10 and
+/// r11 for reloading spilled values.
+pub const ALLOCATABLE: [GPR; 9] = [
+    x86::RSI, x86::RDI, x86::R8, x86::R9,
+    x86::RBX, x86::R12, x86::R13, x86::R14, x86::R15,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Loc {
+    Reg(GPR),
+    /// Frame slot at `[rbp - offset]`.
+    Spill(i32),
+}
+
+impl fmt::Display for Loc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Loc::Reg(reg) => write!(f, "{}", reg),
+            Loc::Spill(offset) => write!(f, "[rbp-{}]", offset),
+        }
+    }
+}
+
+/// Lifetime of a virtual register. Instruction `i` uses its operands at
+/// `2 * i` and defines its results at `2 * i + 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub vreg: VReg,
+    pub start: usize,
+    pub end: usize,
+    /// Live across a call, so it cannot stay in a caller-saved register.
+    pub crosses_call: bool,
+    /// Register the value arrives in or is passed in, saving a move.
+    pub hint: Option<GPR>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Allocation {
+    pub locs: HashMap<VReg, Loc>,
+    /// Callee-saved registers handed out, to be preserved by the function.
+    pub callee_saved: Vec<GPR>,
+    /// Bytes of frame taken by spill slots.
+    pub spill_size: i32,
+}
+
+impl Allocation {
+    pub fn loc(&self, vreg: VReg) -> &Loc {
+        &self.locs[&vreg]
+    }
+
+    /// Listing of `func` annotated with the location of every register.
+    pub fn dump(&self, func: &VFunction) -> String {
+        let mut out = format!("{}:\n", func.name);
+        for (i, block) in func.blocks.iter().enumerate() {
+            out.push_str(&format!("  .b{}:\n", i));
+            for inst in block.insts.iter() {
+                let mut vregs: Vec<VReg> = inst.defs().into_iter().chain(inst.uses()).collect();
+                vregs.sort();
+                vregs.dedup();
+                let locs: Vec<String> = vregs.iter().map(|v| format!("{} -> {}", v, self.loc(*v))).collect();
+                out.push_str(&format!("    {:<32} ; {}\n", inst.to_string(), locs.join(", ")));
+            }
+        }
+        out
+    }
+}
+
+/// Live-in set of every block.
+pub fn liveness(func: &VFunction) -> Vec<BTreeSet<VReg>> {
+    // Registers read before being written, and written, by each block
+    let (gen_, kill): (Vec<BTreeSet<VReg>>, Vec<BTreeSet<VReg>>) = func.blocks.iter().map(|block| {
+        let mut gen_ = BTreeSet::new();
+        let mut kill = BTreeSet::new();
+        for inst in block.insts.iter() {
+            gen_.extend(inst.uses().into_iter().filter(|v| !kill.contains(v)));
+            kill.extend(inst.defs());
+        }
+        (gen_, kill)
+    }).unzip();
+
+    let mut live_in = vec![BTreeSet::new(); func.blocks.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, block) in func.blocks.iter().enumerate().rev() {
+            let live_out: BTreeSet<VReg> = block.succs.iter().flat_map(|s| live_in[*s].iter().copied()).collect();
+            let new_in: BTreeSet<VReg> = gen_[i].iter().copied()
+                .chain(live_out.difference(&kill[i]).copied())
+                .collect();
+            if new_in != live_in[i] {
+                live_in[i] = new_in;
+                changed = true;
+            }
+        }
+    }
+    live_in
+}
+
+/// Lifetime intervals, sorted by start point.
+pub fn intervals(func: &VFunction) -> Vec<Interval> {
+    let live_in = liveness(func);
+    let mut ranges: HashMap<VReg, (usize, usize)> = HashMap::new();
+    let mut extend = |vreg: VReg, pos: usize| {
+        let range = ranges.entry(vreg).or_insert((pos, pos));
+        range.0 = range.0.min(pos);
+        range.1 = range.1.max(pos);
+    };
+
+    let mut calls = Vec::new();
+    let mut hints: HashMap<VReg, GPR> = HashMap::new();
+    let mut hint = |vreg: VReg, location: &Location| {
+        if let Location::Regs(slots) = location
+            && let [Slot::Gpr(reg)] = slots.as_slice() {
+            hints.entry(vreg).or_insert(reg.clone());
+        }
+    };
+    let mut pos = 0;
+    for (i, block) in func.blocks.iter().enumerate() {
+        let start = pos;
+        for vreg in live_in[i].iter() {
+            extend(*vreg, start);
+        }
+        for inst in block.insts.iter() {
+            for vreg in inst.uses() {
+                extend(vreg, pos);
+            }
+            for vreg in inst.defs() {
+                extend(vreg, pos + 1);
+            }
+            match inst {
+                Inst::Entry { args } => {
+                    for (vreg, location) in args.iter() {
+                        hint(*vreg, location);
+                    }
+                }
+                Inst::Call { args, layout, .. } => {
+                    for (vreg, location) in args.iter().zip(layout.arguments.iter()) {
+                        hint(*vreg, location);
+                    }
+                    calls.push(pos);
+                }
+                _ => {}
+            }
+            pos += 2;
+        }
+        let end = pos.saturating_sub(1).max(start);
+        for vreg in block.succs.iter().flat_map(|s| live_in[*s].iter()) {
+            extend(*vreg, end);
+        }
+    }
+
+    let mut intervals: Vec<Interval> = ranges.into_iter().map(|(vreg, (start, end))| Interval {
+        vreg,
+        start,
+        end,
+        crosses_call: calls.iter().any(|call| start < *call && end > call + 1),
+        hint: hints.get(&vreg).cloned(),
+    }).collect();
+    intervals.sort_by_key(|interval| (interval.start, interval.vreg));
+    intervals
+}
+
+pub fn allocate(func: &VFunction) -> Allocation {
+    allocate_with(func, &ALLOCATABLE)
+}
+
+/// Allocates out of `registers` only, which is mostly useful to exercise spilling.
+pub fn allocate_with(func: &VFunction, registers: &[GPR]) -> Allocation {
+    let is_callee_saved = |reg: &GPR| abi::CALLEE_SAVED.contains(reg);
+
+    let mut locs = HashMap::new();
+    let mut free: Vec<GPR> = registers.to_vec();
+    let mut active: Vec<(Interval, GPR)> = Vec::new();
+    let mut callee_saved: Vec<GPR> = Vec::new();
+    // Locals all live in registers, so spill slots start right below the frame base
+    let mut spill_size = 0;
+    let mut spill = |locs: &mut HashMap<VReg, Loc>, vreg: VReg| {
+        spill_size += 8;
+        locs.insert(vreg, Loc::Spill(spill_size));
+    };
+
+    for current in intervals(func) {
+        // Expire intervals that ended before this one starts
+        active.retain(|(interval, reg)| {
+            if interval.end < current.start {
+                free.push(reg.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        let usable = |reg: &GPR| !current.crosses_call || is_callee_saved(reg);
+        // Keep callee-saved registers for values that need them
+        let pick = free.iter().position(|r| usable(r) && Some(r) == current.hint.as_ref())
+            .or_else(|| free.iter().position(|r| usable(r) && !is_callee_saved(r)))
+            .or_else(|| free.iter().position(&usable));
+
+        let reg = match pick {
+            Some(i) => free.remove(i),
+            None => {
+                // Steal the register of the active interval ending last
+                let victim = active.iter().enumerate()
+                    .filter(|(_, (_, reg))| usable(reg))
+                    .max_by_key(|(_, (interval, _))| interval.end)
+                    .map(|(i, _)| i);
+                match victim {
+                    Some(i) if active[i].0.end > current.end => {
+                        let (interval, reg) = active.remove(i);
+                        spill(&mut locs, interval.vreg);
+                        reg
+                    }
+                    _ => {
+                        spill(&mut locs, current.vreg);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        if is_callee_saved(&reg) && !callee_saved.contains(&reg) {
+            callee_saved.push(reg.clone());
+        }
+        locs.insert(current.vreg, Loc::Reg(reg.clone()));
+        active.push((current, reg));
+    }
+
+    Allocation {
+        locs,
+        callee_saved,
+        spill_size,
+    }
+}
@@ -0,0 +1,432 @@
+//! Instruction selection from `ir::Function` into the virtual register form.
+
+use std::collections::HashMap;
+
+use super::abi::{self, CallLayout};
+use super::vreg::{AluOp, Block, Inst, ShiftOp, VFunction, VReg};
+use crate::backend::assembler::x86_64::x86::instructions::Cond;
+use crate::backend::ir;
+use crate::reporter::asm::AssemblerError;
+
+/// Size in bytes and signedness of a value that fits in a register.
+pub fn scalar(v_type: &ir::DataType) -> Result<(u8, bool), AssemblerError> {
+    match v_type {
+        ir::DataType::Scalar { size: size @ (1 | 2 | 4 | 8), signed } => Ok((*size as u8, *signed)),
+        ir::DataType::Pointer { .. } => Ok((8, false)),
+        _ => Err(AssemblerError::Unsupported { what: format!("values of type `{}`", v_type) }),
+    }
+}
+
+/// Only values held in a single integer register can be passed around yet.
+fn check_location(location: &abi::Location) -> Result<(), AssemblerError> {
+    match location {
+        abi::Location::Regs(slots) if !matches!(slots.as_slice(), [abi::Slot::Gpr(_)]) => {
+            Err(AssemblerError::Unsupported { what: "values passed in vector registers".to_string() })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn condition_code(op: &ir::BinaryOp, signed: bool) -> Cond {
+    match (op, signed) {
+        (ir::BinaryOp::Eq, _) => Cond::E,
+        (ir::BinaryOp::Ne, _) => Cond::NE,
+        (ir::BinaryOp::Lt, true) => Cond::L,
+        (ir::BinaryOp::Le, true) => Cond::LE,
+        (ir::BinaryOp::Gt, true) => Cond::G,
+        (ir::BinaryOp::Ge, true) => Cond::GE,
+        (ir::BinaryOp::Lt, false) => Cond::B,
+        (ir::BinaryOp::Le, false) => Cond::BE,
+        (ir::BinaryOp::Gt, false) => Cond::A,
+        (ir::BinaryOp::Ge, false) => Cond::AE,
+        _ => unreachable!("not a comparison"),
+    }
+}
+
+/// Blocks to patch once the end of a loop is known.
+struct Loop {
+    head: usize,
+    breaks: Vec<usize>,
+}
+
+pub struct Selector<'a> {
+    func: VFunction,
+    block: usize,
+    strings: &'a mut Vec<String>,
+    arguments: Vec<VReg>,
+    /// Virtual register holding each local, by the offset lowering gave it.
+    promoted: HashMap<i16, VReg>,
+    loops: Vec<Loop>,
+}
+
+impl<'a> Selector<'a> {
+    pub fn new(function: &ir::Function, strings: &'a mut Vec<String>) -> Self {
+        let global = function.flags & ir::flags::FunctionModifer::NoMangle as u16 != 0;
+        Selector {
+            func: VFunction::new(function.mangled.clone(), global),
+            block: 0,
+            strings,
+            arguments: Vec::new(),
+            promoted: HashMap::new(),
+            loops: Vec::new(),
+        }
+    }
+
+    fn emit(&mut self, inst: Inst) {
+        self.func.blocks[self.block].insts.push(inst);
+    }
+
+    fn new_block(&mut self) -> usize {
+        self.func.blocks.push(Block::default());
+        self.func.blocks.len() - 1
+    }
+
+    /// Ends `block` with a jump and records where control goes.
+    fn terminate(&mut self, block: usize, inst: Inst) {
+        let succs = match &inst {
+            Inst::Branch { then, otherwise, .. } => vec![*then, *otherwise],
+            Inst::Jump { target } => vec![*target],
+            _ => vec![],
+        };
+        self.func.blocks[block].insts.push(inst);
+        self.func.blocks[block].succs = succs;
+    }
+
+    fn extend(&mut self, dst: VReg, size: u8, signed: bool) {
+        if size < 8 {
+            self.emit(Inst::Extend { dst, size, signed });
+        }
+    }
+
+    fn literal(&mut self, literal: &ir::Literal, v_type: &ir::DataType) -> Result<VReg, AssemblerError> {
+        let dst = self.func.new_vreg();
+        match literal {
+            ir::Literal::Int(value) => {
+                scalar(v_type)?;
+                self.emit(Inst::Imm { dst, value: v_type.canonical(*value) as i64 });
+            }
+            ir::Literal::Bool(value) => self.emit(Inst::Imm { dst, value: *value as i64 }),
+            ir::Literal::Str(value) => {
+                let name = format!(".str{}", self.strings.len());
+                self.strings.push(value.clone());
+                self.emit(Inst::Symbol { dst, name });
+            }
+            ir::Literal::Float(_) => {
+                return Err(AssemblerError::Unsupported { what: "floating point literals".to_string() });
+            }
+        }
+        Ok(dst)
+    }
+
+    fn compare(&mut self, op: &ir::BinaryOp, left: &ir::Expr, right: &ir::Expr) -> Result<(Cond, VReg, VReg), AssemblerError> {
+        let v_type = ir::Expr::operand_type(left, right);
+        let (_, signed) = scalar(&v_type)?;
+        let left = self.expr(left, &v_type)?;
+        let right = self.expr(right, &v_type)?;
+        Ok((condition_code(op, signed), left, right))
+    }
+
+    /// Evaluates `cond` for a branch, comparisons set the flags directly.
+    fn condition(&mut self, cond: &ir::Expr) -> Result<(Cond, VReg, Option<VReg>), AssemblerError> {
+        if let ir::Expr::Binary { op, left, right } = cond
+            && op.is_comparison() {
+            let (cond, left, right) = self.compare(op, left, right)?;
+            return Ok((cond, left, Some(right)));
+        }
+        let value = self.expr(cond, &cond.infer_type(None))?;
+        Ok((Cond::NE, value, None))
+    }
+
+    /// `left && right` or `left || right`, only evaluating `right` when
+    /// `left` does not decide the result already.
+    fn logical(&mut self, op: &ir::BinaryOp, left: &ir::Expr, right: &ir::Expr) -> Result<VReg, AssemblerError> {
+        let and = *op == ir::BinaryOp::LogicalAnd;
+        let dst = self.func.new_vreg();
+        self.emit(Inst::Imm { dst, value: !and as i64 });
+        let (cond, l, r) = self.condition(left)?;
+        let head = self.block;
+
+        let rhs = self.new_block();
+        self.block = rhs;
+        let zero = ir::Expr::Literal(ir::Literal::Int(0));
+        let (cond_r, l_r, r_r) = self.compare(&ir::BinaryOp::Ne, right, &zero)?;
+        let src = self.func.new_vreg();
+        self.emit(Inst::Cmp { cond: cond_r, dst: src, left: l_r, right: r_r });
+        self.emit(Inst::Copy { dst, src });
+
+        let end = self.new_block();
+        let (then, otherwise) = if and { (rhs, end) } else { (end, rhs) };
+        self.terminate(head, Inst::Branch { cond, left: l, right: r, then, otherwise });
+        self.terminate(self.block, Inst::Jump { target: end });
+        self.block = end;
+        Ok(dst)
+    }
+
+    fn binary(&mut self, op: &ir::BinaryOp, left: &ir::Expr, right: &ir::Expr, v_type: &ir::DataType) -> Result<VReg, AssemblerError> {
+        if op.is_logical() {
+            return self.logical(op, left, right);
+        }
+        if op.is_comparison() {
+            let (cond, left, right) = self.compare(op, left, right)?;
+            let dst = self.func.new_vreg();
+            self.emit(Inst::Cmp { cond, dst, left, right });
+            return Ok(dst);
+        }
+        let (size, signed) = scalar(v_type)?;
+
+        // Division by a constant after strength reduction, the shift reaching
+        // past the operand width means the high half of the product is wanted
+        if let (ir::BinaryOp::Shr, ir::Expr::Binary { op: ir::BinaryOp::Mul, left: value, right: magic }, ir::Expr::Literal(ir::Literal::Int(shift)))
+            = (op, left, right)
+            && let ir::Expr::Literal(ir::Literal::Int(magic)) = **magic
+            && !signed && *shift >= size as u64 * 8 {
+            let value = self.expr(value, v_type)?;
+            let dst = self.func.new_vreg();
+            self.emit(Inst::Copy { dst, src: value });
+            self.emit(Inst::MulHigh { dst, magic, shift: *shift });
+            return Ok(dst);
+        }
+
+        let left = self.expr(left, v_type)?;
+        let right = self.expr(right, v_type)?;
+        let dst = self.func.new_vreg();
+        self.emit(Inst::Copy { dst, src: left });
+        self.emit(match op {
+            ir::BinaryOp::Add => Inst::Alu { op: AluOp::Add, dst, src: right },
+            ir::BinaryOp::Sub => Inst::Alu { op: AluOp::Sub, dst, src: right },
+            ir::BinaryOp::Mul => Inst::Alu { op: AluOp::Mul, dst, src: right },
+            ir::BinaryOp::And => Inst::Alu { op: AluOp::And, dst, src: right },
+            ir::BinaryOp::Or => Inst::Alu { op: AluOp::Or, dst, src: right },
+            ir::BinaryOp::Xor => Inst::Alu { op: AluOp::Xor, dst, src: right },
+            ir::BinaryOp::Div => Inst::Div { dst, src: right, signed, remainder: false },
+            ir::BinaryOp::Rem => Inst::Div { dst, src: right, signed, remainder: true },
+            ir::BinaryOp::Shl => Inst::Shift { op: ShiftOp::Shl, dst, src: right },
+            ir::BinaryOp::Shr => Inst::Shift { op: if signed { ShiftOp::Sar } else { ShiftOp::Shr }, dst, src: right },
+            _ => unreachable!("comparisons are handled above"),
+        });
+        self.extend(dst, size, signed);
+        Ok(dst)
+    }
+
+    fn call(&mut self, call: &ir::FunctionCall) -> Result<VReg, AssemblerError> {
+        // Trailing variadic arguments keep their own type
+        let types: Vec<ir::DataType> = call.arguments.iter().enumerate()
+            .map(|(i, arg)| match call.reference.arguments.get(i) {
+                Some((_, v_type)) => v_type.clone(),
+                None => arg.infer_type(None),
+            })
+            .collect();
+        let layout = CallLayout::new(&types, &call.reference.return_type);
+        if layout.indirect_ret {
+            return Err(AssemblerError::Unsupported { what: format!("returning `{}` by value", call.reference.return_type) });
+        }
+
+        let mut args = Vec::new();
+        for ((arg, v_type), location) in call.arguments.iter().zip(types.iter()).zip(layout.arguments.iter()) {
+            check_location(location)?;
+            args.push(self.expr(arg, v_type)?);
+        }
+
+        let dst = self.func.new_vreg();
+        let ret = match call.reference.return_type.size() {
+            0 => None,
+            _ => {
+                let (size, signed) = scalar(&call.reference.return_type)?;
+                Some((dst, size, signed))
+            }
+        };
+        self.emit(Inst::Call {
+            name: call.reference.mangled.clone(),
+            args,
+            layout,
+            ret,
+            variadic: call.reference.flags & ir::flags::FunctionModifer::Variadic as u16 != 0,
+        });
+        Ok(dst)
+    }
+
+    fn expr(&mut self, expr: &ir::Expr, v_type: &ir::DataType) -> Result<VReg, AssemblerError> {
+        match expr {
+            ir::Expr::Literal(literal) => self.literal(literal, v_type),
+            ir::Expr::Reference(ir::Reference::Argument { index, .. }) => Ok(self.arguments[*index]),
+            ir::Expr::Reference(reference @ ir::Reference::Local { .. }) => self.variable(reference),
+            ir::Expr::Binary { op, left, right } => {
+                let v_type = expr.infer_type(Some(v_type.clone()));
+                self.binary(op, left, right, &v_type)
+            }
+            ir::Expr::Call(call) => self.call(call),
+            // A literal is simply computed in the type it is cast to
+            ir::Expr::Cast { value, v_type } if matches!(**value, ir::Expr::Literal(_)) => self.expr(value, v_type),
+            ir::Expr::Cast { value, v_type } => {
+                let src = self.expr(value, &value.infer_type(None))?;
+                let (size, signed) = scalar(v_type)?;
+                let dst = self.func.new_vreg();
+                self.emit(Inst::Copy { dst, src });
+                self.extend(dst, size, signed);
+                Ok(dst)
+            }
+        }
+    }
+
+    /// Register holding the variable `reference`. Scalar locals never have
+    /// their address taken, so they all live in registers.
+    fn variable(&mut self, reference: &ir::Reference) -> Result<VReg, AssemblerError> {
+        scalar(reference.v_type())?;
+        Ok(match reference {
+            ir::Reference::Argument { index, .. } => self.arguments[*index],
+            ir::Reference::Local { offset, .. } => match self.promoted.get(offset) {
+                Some(vreg) => *vreg,
+                None => {
+                    let vreg = self.func.new_vreg();
+                    self.promoted.insert(*offset, vreg);
+                    vreg
+                }
+            },
+        })
+    }
+
+    /// Returns `false` once control cannot reach past `stat`.
+    fn stat(&mut self, stat: &ir::Statement, return_type: &ir::DataType) -> Result<bool, AssemblerError> {
+        match stat {
+            ir::Statement::Expr(expr) => {
+                self.expr(expr, &expr.infer_type(None))?;
+                Ok(true)
+            }
+            ir::Statement::Return(expr) => {
+                let value = self.expr(expr, return_type)?;
+                self.emit(Inst::Ret { value: Some(value) });
+                Ok(false)
+            }
+            ir::Statement::Block(body) => self.body(body, return_type),
+            ir::Statement::Let { reference, value } => {
+                let dst = self.variable(reference)?;
+                match value {
+                    Some(value) => {
+                        let src = self.expr(value, reference.v_type())?;
+                        self.emit(Inst::Copy { dst, src });
+                    }
+                    None => self.emit(Inst::Imm { dst, value: 0 }),
+                }
+                Ok(true)
+            }
+            ir::Statement::Assign { target, value } => {
+                let dst = self.variable(target)?;
+                let src = self.expr(value, target.v_type())?;
+                self.emit(Inst::Copy { dst, src });
+                Ok(true)
+            }
+            ir::Statement::CompoundAssign { op, target, value } => {
+                let dst = self.variable(target)?;
+                let src = self.binary(op, &ir::Expr::Reference(target.clone()), value, target.v_type())?;
+                self.emit(Inst::Copy { dst, src });
+                Ok(true)
+            }
+            ir::Statement::If { cond, then, otherwise } => {
+                let (cond, left, right) = self.condition(cond)?;
+                let head = self.block;
+
+                let then_block = self.new_block();
+                self.block = then_block;
+                let then_end = self.body(then, return_type)?.then_some(self.block);
+                let (else_block, else_end) = if otherwise.is_empty() {
+                    (None, None)
+                } else {
+                    let else_block = self.new_block();
+                    self.block = else_block;
+                    (Some(else_block), self.body(otherwise, return_type)?.then_some(self.block))
+                };
+
+                let join = match (else_block, then_end, else_end) {
+                    (Some(else_block), None, None) => {
+                        self.terminate(head, Inst::Branch { cond, left, right, then: then_block, otherwise: else_block });
+                        return Ok(false);
+                    }
+                    _ => self.new_block(),
+                };
+                self.terminate(head, Inst::Branch { cond, left, right, then: then_block, otherwise: else_block.unwrap_or(join) });
+                for end in [then_end, else_end].into_iter().flatten() {
+                    self.terminate(end, Inst::Jump { target: join });
+                }
+                self.block = join;
+                Ok(true)
+            }
+            ir::Statement::While { cond, body } => {
+                let head = self.new_block();
+                self.terminate(self.block, Inst::Jump { target: head });
+                self.block = head;
+                let (cond, left, right) = self.condition(cond)?;
+
+                let body_block = self.new_block();
+                self.block = body_block;
+                self.loops.push(Loop { head, breaks: Vec::new() });
+                let body_end = self.body(body, return_type)?.then_some(self.block);
+                let Loop { breaks, .. } = self.loops.pop().expect("loop stack is balanced");
+                if let Some(end) = body_end {
+                    self.terminate(end, Inst::Jump { target: head });
+                }
+
+                let exit = self.new_block();
+                self.terminate(head, Inst::Branch { cond, left, right, then: body_block, otherwise: exit });
+                for block in breaks {
+                    self.terminate(block, Inst::Jump { target: exit });
+                }
+                self.block = exit;
+                Ok(true)
+            }
+            ir::Statement::Break => {
+                let block = self.block;
+                let Some(innermost) = self.loops.last_mut() else {
+                    return Err(AssemblerError::Unsupported { what: "`break` outside of a loop".to_string() });
+                };
+                innermost.breaks.push(block);
+                Ok(false)
+            }
+            ir::Statement::Continue => {
+                let Some(innermost) = self.loops.last() else {
+                    return Err(AssemblerError::Unsupported { what: "`continue` outside of a loop".to_string() });
+                };
+                let head = innermost.head;
+                self.terminate(self.block, Inst::Jump { target: head });
+                Ok(false)
+            }
+        }
+    }
+
+    /// Selects `body` until control cannot go any further.
+    fn body(&mut self, body: &[ir::Statement], return_type: &ir::DataType) -> Result<bool, AssemblerError> {
+        for stat in body.iter() {
+            if !self.stat(stat, return_type)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn select(mut self, function: &ir::Function) -> Result<VFunction, AssemblerError> {
+        let layout = CallLayout::of(function);
+        if layout.indirect_ret {
+            return Err(AssemblerError::Unsupported { what: format!("returning `{}` by value", function.return_type) });
+        }
+
+        let mut args = Vec::new();
+        let mut extends = Vec::new();
+        for ((_, v_type), location) in function.arguments.iter().zip(layout.arguments) {
+            check_location(&location)?;
+            let (size, signed) = scalar(v_type)?;
+            let vreg = self.func.new_vreg();
+            self.arguments.push(vreg);
+            args.push((vreg, location));
+            // The upper bits of narrow arguments are undefined
+            extends.push((vreg, size, signed));
+        }
+        self.emit(Inst::Entry { args });
+        for (vreg, size, signed) in extends {
+            self.extend(vreg, size, signed);
+        }
+
+        if self.body(&function.body, &function.return_type)? {
+            self.emit(Inst::Ret { value: None });
+        }
+        Ok(self.func)
+    }
+}
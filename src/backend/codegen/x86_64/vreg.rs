@@ -0,0 +1,177 @@
+//! Machine level form of a function over an unbounded set of virtual
+//! registers, the input of the register allocator.
+//!
+//! Every virtual register holds a 64-bit value; narrower values are kept
+//! sign or zero extended according to their type.
+
+use std::fmt;
+
+use super::abi::{CallLayout, Location};
+use crate::backend::assembler::x86_64::x86::instructions::Cond;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VReg(pub u32);
+
+impl fmt::Display for VReg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AluOp {
+    Add,
+    Sub,
+    Mul,
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShiftOp {
+    Shl,
+    Shr,
+    Sar,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inst {
+    /// Defines the incoming arguments at function entry.
+    Entry { args: Vec<(VReg, Location)> },
+    Imm { dst: VReg, value: i64 },
+    /// Address of a symbol.
+    Symbol { dst: VReg, name: String },
+    Copy { dst: VReg, src: VReg },
+    /// `dst = dst op src`
+    Alu { op: AluOp, dst: VReg, src: VReg },
+    /// `dst = dst / src`
+    Div { dst: VReg, src: VReg, signed: bool, remainder: bool },
+    /// `dst = dst op src`
+    Shift { op: ShiftOp, dst: VReg, src: VReg },
+    /// `dst = (dst * magic) >> shift` over the full 128-bit product.
+    MulHigh { dst: VReg, magic: u64, shift: u64 },
+    /// Re-extends the low `size` bytes of `dst`.
+    Extend { dst: VReg, size: u8, signed: bool },
+    /// `dst = left cond right`, either 0 or 1.
+    Cmp { cond: Cond, dst: VReg, left: VReg, right: VReg },
+    Call {
+        name: String,
+        args: Vec<VReg>,
+        layout: CallLayout,
+        ret: Option<(VReg, u8, bool)>,
+        variadic: bool,
+    },
+    Ret { value: Option<VReg> },
+    /// Goes to block `then` if `left cond right` holds, to `otherwise` if
+    /// not. Without `right`, `left` is compared against zero.
+    Branch { cond: Cond, left: VReg, right: Option<VReg>, then: usize, otherwise: usize },
+    Jump { target: usize },
+}
+
+impl Inst {
+    pub fn uses(&self) -> Vec<VReg> {
+        match self {
+            Inst::Copy { src, .. } => vec![*src],
+            Inst::Alu { dst, src, .. } | Inst::Div { dst, src, .. } | Inst::Shift { dst, src, .. } => vec![*dst, *src],
+            Inst::MulHigh { dst, .. } | Inst::Extend { dst, .. } => vec![*dst],
+            Inst::Cmp { left, right, .. } => vec![*left, *right],
+            Inst::Call { args, .. } => args.clone(),
+            Inst::Ret { value } => value.iter().copied().collect(),
+            Inst::Branch { left, right, .. } => std::iter::once(*left).chain(*right).collect(),
+            Inst::Entry { .. } | Inst::Imm { .. } | Inst::Symbol { .. } | Inst::Jump { .. } => vec![],
+        }
+    }
+
+    pub fn defs(&self) -> Vec<VReg> {
+        match self {
+            Inst::Entry { args } => args.iter().map(|(v, _)| *v).collect(),
+            Inst::Imm { dst, .. } | Inst::Symbol { dst, .. } | Inst::Copy { dst, .. }
+            | Inst::Alu { dst, .. } | Inst::Div { dst, .. } | Inst::Shift { dst, .. }
+            | Inst::MulHigh { dst, .. } | Inst::Extend { dst, .. } | Inst::Cmp { dst, .. } => vec![*dst],
+            Inst::Call { ret, .. } => ret.iter().map(|(v, _, _)| *v).collect(),
+            Inst::Ret { .. } | Inst::Branch { .. } | Inst::Jump { .. } => vec![],
+        }
+    }
+}
+
+impl fmt::Display for Inst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Inst::Entry { args } => {
+                let args: Vec<String> = args.iter().map(|(v, _)| v.to_string()).collect();
+                write!(f, "entry {}", args.join(", "))
+            }
+            Inst::Imm { dst, value } => write!(f, "{} = {}", dst, value),
+            Inst::Symbol { dst, name } => write!(f, "{} = &{}", dst, name),
+            Inst::Copy { dst, src } => write!(f, "{} = {}", dst, src),
+            Inst::Alu { op, dst, src } => write!(f, "{} = {:?} {}, {}", dst, op, dst, src),
+            Inst::Div { dst, src, signed, remainder } => {
+                let op = match (signed, remainder) {
+                    (true, false) => "IDiv",
+                    (false, false) => "Div",
+                    (true, true) => "IRem",
+                    (false, true) => "Rem",
+                };
+                write!(f, "{} = {} {}, {}", dst, op, dst, src)
+            }
+            Inst::Shift { op, dst, src } => write!(f, "{} = {:?} {}, {}", dst, op, dst, src),
+            Inst::MulHigh { dst, magic, shift } => write!(f, "{} = ({} * {}) >> {}", dst, dst, magic, shift),
+            Inst::Extend { dst, size, signed } => write!(f, "{} = {}ext{} {}", dst, if *signed { "s" } else { "z" }, size * 8, dst),
+            Inst::Cmp { cond, dst, left, right } => write!(f, "{} = cmp.{:?} {}, {}", dst, cond, left, right),
+            Inst::Call { name, args, ret, .. } => {
+                let args: Vec<String> = args.iter().map(|v| v.to_string()).collect();
+                if let Some((ret, _, _)) = ret {
+                    write!(f, "{} = ", ret)?;
+                }
+                write!(f, "call {}({})", name, args.join(", "))
+            }
+            Inst::Ret { value: Some(value) } => write!(f, "ret {}", value),
+            Inst::Ret { value: None } => write!(f, "ret"),
+            Inst::Branch { cond, left, right, then, otherwise } => {
+                match right {
+                    Some(right) => write!(f, "br.{:?} {}, {}", cond, left, right)?,
+                    None => write!(f, "br.{:?} {}, 0", cond, left)?,
+                }
+                write!(f, " .b{}, .b{}", then, otherwise)
+            }
+            Inst::Jump { target } => write!(f, "jmp .b{}", target),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Block {
+    pub insts: Vec<Inst>,
+    /// Blocks control may flow to once this one is done.
+    pub succs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VFunction {
+    pub name: String,
+    pub global: bool,
+    pub blocks: Vec<Block>,
+    pub vregs: u32,
+}
+
+impl VFunction {
+    pub fn new(name: String, global: bool) -> Self {
+        VFunction {
+            name,
+            global,
+            blocks: vec![Block::default()],
+            vregs: 0,
+        }
+    }
+
+    pub fn new_vreg(&mut self) -> VReg {
+        self.vregs += 1;
+        VReg(self.vregs - 1)
+    }
+
+    /// Instructions in layout order.
+    pub fn insts(&self) -> impl Iterator<Item = &Inst> {
+        self.blocks.iter().flat_map(|block| block.insts.iter())
+    }
+}
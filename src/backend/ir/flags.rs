@@ -1,11 +1,18 @@
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FunctionModifer {
     NoMangle = 1 << 0,
+    /// Declared here, defined elsewhere (e.g. libc). Never mangled.
+    Extern = 1 << 1,
+    /// Accepts extra arguments after the declared ones, C style.
+    Variadic = 1 << 2,
 }
 impl FunctionModifer {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<FunctionModifer> {
         match s {
             "no_mangle" => Some(FunctionModifer::NoMangle),
+            "extern" => Some(FunctionModifer::Extern),
+            "variadic" => Some(FunctionModifer::Variadic),
             _ => None,
         }
     }
@@ -17,4 +17,14 @@ pub mod mangling {
     pub fn hash(metadata: String) -> String {
         format!("func_{:x}", xxh3_64(metadata.as_bytes()) as u64)
     }
-}
\ No newline at end of file
+}
+pub mod frame {
+    use crate::backend::ir::DataType;
+
+    /// Reserves a slot for `v_type` below `top` and returns its offset from the frame base.
+    pub fn alloc(top: i16, v_type: &DataType) -> i16 {
+        let align = v_type.align().max(1);
+        let end = top + v_type.size();
+        (end + align - 1) / align * align
+    }
+}
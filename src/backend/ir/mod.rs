@@ -11,6 +11,9 @@ pub enum DataType {
         size: i16,
         signed: bool,
     },
+    Float {
+        size: i16,
+    },
     Pointer {
         target: Box<DataType>,
     },
@@ -32,6 +35,9 @@ impl std::fmt::Display for DataType {
                 }
                 write!(f, "{}{}", if *signed { "i" } else { "u" }, size*8)
             }
+            DataType::Float { size } => {
+                write!(f, "f{}", size*8)
+            }
             DataType::Pointer { target } => {
                 write!(f, "*{}", target)
             }
@@ -39,9 +45,9 @@ impl std::fmt::Display for DataType {
                 write!(f, "[{}; {}]", target, capacity)
             }
             DataType::Struct { path, fields } => {
-                write!(f, "struct {} {{\n", path)?;
+                writeln!(f, "struct {} {{", path)?;
                 for (name, t) in fields {
-                    write!(f, "    {}: {},\n", name, t)?;
+                    writeln!(f, "    {}: {},", name, t)?;
                 }
                 write!(f, "}}")
             }
@@ -49,6 +55,64 @@ impl std::fmt::Display for DataType {
     }
 }
 
+impl DataType {
+    /// Size in bytes, including the padding of struct fields.
+    pub fn size(&self) -> i16 {
+        match self {
+            DataType::Scalar { size, .. } | DataType::Float { size } => *size,
+            DataType::Pointer { .. } => 8,
+            DataType::Array { target, capacity } => target.size() * capacity,
+            DataType::Struct { fields, .. } => {
+                let end = match (fields.last(), self.field_offsets().last()) {
+                    (Some((_, t)), Some(offset)) => offset + t.size(),
+                    _ => 0,
+                };
+                let align = self.align().max(1);
+                (end + align - 1) / align * align
+            }
+        }
+    }
+
+    /// Offsets of the fields of a struct, each aligned to its natural alignment.
+    pub fn field_offsets(&self) -> Vec<i16> {
+        let DataType::Struct { fields, .. } = self else {
+            return vec![];
+        };
+        let mut end = 0;
+        fields.iter().map(|(_, t)| {
+            let align = t.align().max(1);
+            let offset = (end + align - 1) / align * align;
+            end = offset + t.size();
+            offset
+        }).collect()
+    }
+
+    /// `value` truncated to the width of a scalar and extended back to 64 bits
+    /// by its signedness, the way registers hold it.
+    pub fn canonical(&self, value: u64) -> u64 {
+        let DataType::Scalar { size: size @ 1..8, signed } = self else {
+            return value;
+        };
+        let shift = 64 - *size as u32 * 8;
+        if *signed {
+            (((value << shift) as i64) >> shift) as u64
+        } else {
+            (value << shift) >> shift
+        }
+    }
+
+    pub fn align(&self) -> i16 {
+        match self {
+            DataType::Scalar { size, .. } | DataType::Float { size } => *size,
+            DataType::Pointer { .. } => 8,
+            DataType::Array { target, .. } => target.align(),
+            DataType::Struct { fields, .. } => {
+                fields.iter().map(|(_, t)| t.align()).max().unwrap_or(1)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Int(u64),
@@ -63,16 +127,53 @@ pub enum BinaryOp {
     Sub,
     Mul,
     Div,
+    Rem,
     Shl,
     Shr,
-    And
+    And,
+    Or,
+    Xor,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `&&`, evaluating the right side only when the left one is not zero.
+    LogicalAnd,
+    /// `||`, evaluating the right side only when the left one is zero.
+    LogicalOr,
+}
+impl BinaryOp {
+    /// Comparisons yield a `bool` whatever the type of their operands.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge)
+    }
+
+    /// Logical operators test each operand against zero in its own type.
+    pub fn is_logical(&self) -> bool {
+        matches!(self, BinaryOp::LogicalAnd | BinaryOp::LogicalOr)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Reference {
+    /// Frame slot at `offset` bytes below the frame base.
     Local {
         offset: i16,
         v_type: DataType,
+    },
+    /// The `index`th argument of the function.
+    Argument {
+        index: usize,
+        v_type: DataType,
+    },
+}
+impl Reference {
+    pub fn v_type(&self) -> &DataType {
+        match self {
+            Reference::Local { v_type, .. } | Reference::Argument { v_type, .. } => v_type,
+        }
     }
 }
 
@@ -85,24 +186,33 @@ pub struct Function {
     pub return_type: DataType,
     pub flags: u16,
     pub body: Vec<Statement>,
+    /// Bytes of frame taken by the locals.
+    pub stack_size: i16,
 }
 impl Function {
     pub fn new(name: String, arguments: Vec<(String, DataType)>, return_type: DataType, flags: u16, body: Vec<Statement>) -> Function {
         let metadata = helper::mangling::mangle(name.clone(), arguments.clone(), return_type.clone());
         Function {
-            mangled: if (flags & flags::FunctionModifer::NoMangle as u16) != 0 { name.clone() } else { helper::mangling::hash(metadata.clone()) },
+            mangled: if (flags & (flags::FunctionModifer::NoMangle as u16 | flags::FunctionModifer::Extern as u16)) != 0 { name.clone() } else { helper::mangling::hash(metadata.clone()) },
             metadata,
             name,
             arguments,
             return_type,
             flags,
             body,
+            stack_size: 0,
         }
     }
 
     pub fn push(&mut self, statement: Statement) {
         self.body.push(statement);
     }
+
+    /// Reserves a frame slot for a local of type `v_type`, aligned to its type.
+    pub fn alloc_local(&mut self, v_type: DataType) -> Reference {
+        self.stack_size = helper::frame::alloc(self.stack_size, &v_type);
+        Reference::Local { offset: self.stack_size, v_type }
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -128,10 +238,35 @@ pub enum Expr {
         left: Box<Expr>,
         right: Box<Expr>,
     },
+    /// `value` converted to `v_type`, e.g. a literal with a type suffix.
+    Cast {
+        value: Box<Expr>,
+        v_type: DataType,
+    },
 }
 
 impl Expr {
-    pub fn infer_type(&mut self, enforced_cast: Option<DataType>) -> DataType {
+    /// Type both operands of a binary expression are computed in, taken from
+    /// the side that is not an untyped literal.
+    pub fn operand_type(left: &Expr, right: &Expr) -> DataType {
+        match left {
+            Expr::Literal(_) => right.infer_type(None),
+            _ => left.infer_type(None),
+        }
+    }
+
+    /// Whether evaluating the expression calls a function, so it can neither
+    /// be dropped nor evaluated twice.
+    pub fn has_call(&self) -> bool {
+        match self {
+            Expr::Literal(_) | Expr::Reference(_) => false,
+            Expr::Call(_) => true,
+            Expr::Binary { left, right, .. } => left.has_call() || right.has_call(),
+            Expr::Cast { value, .. } => value.has_call(),
+        }
+    }
+
+    pub fn infer_type(&self, enforced_cast: Option<DataType>) -> DataType {
         match self {
             Expr::Literal(literal) => {
                 if let Some(enforced_cast) = enforced_cast {
@@ -139,19 +274,24 @@ impl Expr {
                 }
                 match literal {
                     Literal::Int(_) => DataType::Scalar { size: 4, signed: true },
-                    Literal::Float(_) => DataType::Scalar { size: 8, signed: true },
+                    Literal::Float(_) => DataType::Float { size: 8 },
                     Literal::Bool(_) => DataType::Scalar { size: 1, signed: false },
                     Literal::Str(_) => DataType::Pointer { target: Box::new(DataType::Scalar { size: 1, signed: false }) },
                 }
             }
-            Expr::Reference(reference) => {
-                match reference {
-                    Reference::Local { v_type, .. } => v_type.clone(),
-                }
-            }
+            Expr::Reference(reference) => reference.v_type().clone(),
+            Expr::Binary { op, .. } if op.is_comparison() || op.is_logical() => DataType::Scalar { size: 1, signed: false },
             Expr::Binary { left, right, .. } => {
-                let left = left.infer_type(None);
-                let right = right.infer_type(None);
+                // Literals are untyped and take the type of the other operand
+                if let Expr::Literal(_) = **left {
+                    return right.infer_type(enforced_cast);
+                }
+                let left = left.infer_type(enforced_cast);
+                if let Expr::Literal(_) = **right {
+                    return left;
+                }
+                // A literal-only right side takes the type of the left one
+                let right = right.infer_type(Some(left.clone()));
                 if left != right {
                     todo!("Type mismatch");
                 }
@@ -160,6 +300,7 @@ impl Expr {
             Expr::Call(call) => {
                 call.reference.return_type.clone()
             }
+            Expr::Cast { v_type, .. } => v_type.clone(),
         }
     }
 }
@@ -169,6 +310,37 @@ impl Expr {
 pub enum Statement {
     Expr(Expr),
     Return(Expr),
+    /// Nested scope.
+    Block(Vec<Statement>),
+    /// Conditions hold when they are not zero.
+    If {
+        cond: Expr,
+        then: Vec<Statement>,
+        otherwise: Vec<Statement>,
+    },
+    While {
+        cond: Expr,
+        body: Vec<Statement>,
+    },
+    /// Leaves the innermost loop.
+    Break,
+    /// Goes back to the condition of the innermost loop.
+    Continue,
+    /// Declares a local, left zeroed without a value.
+    Let {
+        reference: Reference,
+        value: Option<Expr>,
+    },
+    Assign {
+        target: Reference,
+        value: Expr,
+    },
+    /// `target = target op value`
+    CompoundAssign {
+        op: BinaryOp,
+        target: Reference,
+        value: Expr,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -176,6 +348,11 @@ pub struct Module {
     pub types_def: std::collections::HashMap<String, DataType>, // <Plain Name, Type>
     pub functions: std::collections::HashMap<String, Function> // <Plain Name, Function>
 }
+impl Default for Module {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl Module {
     pub fn new() -> Module {
         Module {
@@ -189,20 +366,26 @@ impl Module {
                 ("u32".to_string(), DataType::Scalar { size: 4, signed: false }),
                 ("i64".to_string(), DataType::Scalar { size: 8, signed: true }),
                 ("u64".to_string(), DataType::Scalar { size: 8, signed: false }),
+                ("f32".to_string(), DataType::Float { size: 4 }),
+                ("f64".to_string(), DataType::Float { size: 8 }),
             ].into_iter().collect::<std::collections::HashMap<_, _>>(),
             functions: std::collections::HashMap::new()
         }
     }
     pub fn push<T: Into<Function>>(&mut self, function: T) -> Result<(), comp::CompilerError> {
         let function: Function = function.into();
-        if self.functions.contains_key(&function.metadata) {
+        if self.functions.contains_key(&function.name) {
             return Err(comp::CompilerError::AlreadyDefinedFunction{name: function.metadata});
         }
         self.functions.insert(function.name.clone(), function);
         Ok(())
     }
 
+    /// Type named `name`, where `*name` points to one.
     pub fn resolve_type(&self, name: String) -> Result<DataType, comp::CompilerError> {
+        if let Some(target) = name.strip_prefix('*') {
+            return Ok(DataType::Pointer { target: Box::new(self.resolve_type(target.to_string())?) });
+        }
         match self.types_def.get(&name) {
             Some(t) => Ok(t.clone()),
             None => Err(comp::CompilerError::TypeNotFound{name})
@@ -212,12 +395,14 @@ impl Module {
     pub fn resolve_call(&self, name: String, args: Vec<Expr>) -> Result<Expr, comp::CompilerError> {
         for (_, function) in self.functions.iter() {
             if function.name == name {
-                if function.arguments.len() != args.len() {
+                // Variadic functions take any number of trailing arguments
+                let variadic = function.flags & flags::FunctionModifer::Variadic as u16 != 0;
+                if function.arguments.len() != args.len() && !(variadic && args.len() > function.arguments.len()) {
                     continue;
                 }
                 let mut valid = true;
-                for (i, arg) in args.iter().enumerate() {
-                    if function.arguments[i].1 != arg.clone().infer_type(Some(function.arguments[i].1.clone())) {
+                for (i, arg) in args.iter().enumerate().take(function.arguments.len()) {
+                    if function.arguments[i].1 != arg.infer_type(Some(function.arguments[i].1.clone())) {
                         valid = false;
                         break;
                     }
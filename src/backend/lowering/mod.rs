@@ -0,0 +1,362 @@
+use std::collections::{HashMap, LinkedList};
+
+use crate::backend::ir;
+use crate::frontend::ast;
+use crate::reporter::comp::CompilerError;
+
+/// Lowers the parsed AST into an `ir::Module`.
+///
+/// Functions are declared first so that calls can be resolved regardless of
+/// the order in which they appear in the source.
+pub struct Lowering {
+    module: ir::Module,
+    errors: Vec<CompilerError>,
+    /// Variables in scope, and whether they can be assigned to.
+    scope: HashMap<String, (ir::Reference, bool)>,
+    /// Function being lowered, owning the frame slots of its locals.
+    current: Option<ir::Function>,
+    /// Number of loops around the statement being lowered.
+    loops: usize,
+}
+
+impl Lowering {
+    pub fn new(module: ir::Module) -> Self {
+        Lowering {
+            module,
+            errors: Vec::new(),
+            scope: HashMap::new(),
+            current: None,
+            loops: 0,
+        }
+    }
+
+    fn resolve_op(op: &str) -> Result<ir::BinaryOp, CompilerError> {
+        match op {
+            "+" => Ok(ir::BinaryOp::Add),
+            "-" => Ok(ir::BinaryOp::Sub),
+            "*" => Ok(ir::BinaryOp::Mul),
+            "/" => Ok(ir::BinaryOp::Div),
+            "%" => Ok(ir::BinaryOp::Rem),
+            "<<" => Ok(ir::BinaryOp::Shl),
+            ">>" => Ok(ir::BinaryOp::Shr),
+            "&" => Ok(ir::BinaryOp::And),
+            "|" => Ok(ir::BinaryOp::Or),
+            "^" => Ok(ir::BinaryOp::Xor),
+            "==" => Ok(ir::BinaryOp::Eq),
+            "!=" => Ok(ir::BinaryOp::Ne),
+            "<" => Ok(ir::BinaryOp::Lt),
+            "<=" => Ok(ir::BinaryOp::Le),
+            ">" => Ok(ir::BinaryOp::Gt),
+            ">=" => Ok(ir::BinaryOp::Ge),
+            "&&" => Ok(ir::BinaryOp::LogicalAnd),
+            "||" => Ok(ir::BinaryOp::LogicalOr),
+            _ => Err(CompilerError::UnsupportedOperator { op: op.to_string() }),
+        }
+    }
+
+    fn declare(&mut self, function: &ast::Function) -> Result<(), CompilerError> {
+        let mut arguments = Vec::new();
+        for (name, t) in function.arguments.iter() {
+            arguments.push((name.clone(), self.module.resolve_type(t.clone())?));
+        }
+        let return_type = self.module.resolve_type(function.return_type.clone())?;
+        // The C runtime looks `main` up by its name
+        let mut flags = function.flags;
+        if function.name == "main" {
+            flags |= ir::flags::FunctionModifer::NoMangle as u16;
+        }
+        self.module.push(ir::Function::new(function.name.clone(), arguments, return_type, flags, vec![]))
+    }
+
+    fn lower_literal(literal: &ast::Literal, expected: Option<&ir::DataType>) -> (ir::Expr, ir::DataType) {
+        let expr = ir::Expr::Literal(match literal {
+            ast::Literal::INTEGER(v) => ir::Literal::Int(*v as u64),
+            ast::Literal::FLOAT(v) => ir::Literal::Float(*v),
+            ast::Literal::BOOL(v) => ir::Literal::Bool(*v),
+            ast::Literal::STRING(v) => ir::Literal::Str(v.clone()),
+            ast::Literal::TYPED { literal, .. } => return Self::lower_literal(literal, expected),
+        });
+        // Numeric literals adapt to the scalar they are combined with.
+        let cast = match (literal, expected) {
+            (ast::Literal::INTEGER(_), Some(t @ ir::DataType::Scalar { size, .. })) if *size > 0 => Some(t.clone()),
+            (ast::Literal::FLOAT(_), Some(t @ ir::DataType::Float { .. })) => Some(t.clone()),
+            _ => None,
+        };
+        let v_type = expr.infer_type(cast);
+        (expr, v_type)
+    }
+
+    /// Integer literals must fit the type they get, a negated signed one may
+    /// also be the magnitude of its minimum.
+    fn check_range(literal: &ast::Literal, v_type: &ir::DataType, negated: bool) -> Result<(), CompilerError> {
+        let (ast::Literal::INTEGER(value), ir::DataType::Scalar { size: size @ 1..=8, signed }) = (literal, v_type) else {
+            return Ok(());
+        };
+        let bits = *size as u32 * 8 - *signed as u32;
+        let max = u64::MAX >> (64 - bits);
+        if *value as u64 > max + (*signed && negated) as u64 {
+            return Err(CompilerError::LiteralOutOfRange { literal: (*value as u64).to_string(), v_type: v_type.to_string() });
+        }
+        Ok(())
+    }
+
+    fn lower_literal_expr(&mut self, literal: &ast::Literal, expected: Option<&ir::DataType>, negated: bool) -> Result<(ir::Expr, ir::DataType), CompilerError> {
+        // A suffix overrides the type expected from the context
+        if let ast::Literal::TYPED { literal, data_type } = literal {
+            let v_type = self.module.resolve_type(data_type.clone())?;
+            Self::check_range(literal, &v_type, negated)?;
+            let (expr, v_type) = Self::lower_literal(literal, Some(&v_type));
+            // Literals are untyped in the IR, so the suffix is kept in a cast
+            // unless the context already gives them that type
+            if expected == Some(&v_type) {
+                return Ok((expr, v_type));
+            }
+            return Ok((ir::Expr::Cast { value: Box::new(expr), v_type: v_type.clone() }, v_type));
+        }
+        let (expr, v_type) = Self::lower_literal(literal, expected);
+        Self::check_range(literal, &v_type, negated)?;
+        Ok((expr, v_type))
+    }
+
+    fn lower_expr(&mut self, expr: &ast::Expression, expected: Option<&ir::DataType>) -> Result<(ir::Expr, ir::DataType), CompilerError> {
+        match expr {
+            ast::Expression::LITERAL(literal) => self.lower_literal_expr(literal, expected, false),
+            ast::Expression::VARIABLE(variable) => {
+                let (reference, _) = self.scope.get(&variable.name).cloned().ok_or(
+                    CompilerError::VariableNotFound { name: variable.name.clone() }
+                )?;
+                let expr = ir::Expr::Reference(reference);
+                let v_type = expr.infer_type(None);
+                Ok((expr, v_type))
+            }
+            ast::Expression::BINARY { left, op, right } => {
+                let op = Self::resolve_op(op)?;
+                // Each side of `&&` and `||` is a condition of its own
+                if op.is_logical() {
+                    let expr = ir::Expr::Binary { op, left: Box::new(self.lower_cond(left)?), right: Box::new(self.lower_cond(right)?) };
+                    let v_type = expr.infer_type(None);
+                    return Ok((expr, v_type));
+                }
+                // The operands of a comparison do not have to be booleans
+                let comparison = op.is_comparison();
+                let expected = if comparison { None } else { expected };
+                // Lower the typed side first so a literal operand can take its type.
+                let ((left, l_type), (right, r_type)) = if matches!(&**left, ast::Expression::LITERAL(literal) if !matches!(literal, ast::Literal::TYPED { .. })) {
+                    let right = self.lower_expr(right, expected)?;
+                    (self.lower_expr(left, Some(&right.1))?, right)
+                } else {
+                    let left = self.lower_expr(left, expected)?;
+                    let right = self.lower_expr(right, Some(&left.1))?;
+                    (left, right)
+                };
+                if l_type != r_type {
+                    return Err(CompilerError::TypeMismatch { expected: l_type.to_string(), found: r_type.to_string() });
+                }
+                let expr = ir::Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+                let v_type = if comparison { expr.infer_type(None) } else { l_type };
+                Ok((expr, v_type))
+            }
+            ast::Expression::UNARY { op, operand } => {
+                let zero = Box::new(ir::Expr::Literal(ir::Literal::Int(0)));
+                match op.as_str() {
+                    // `-x` is `0 - x`
+                    "-" => {
+                        let (operand, v_type) = match &**operand {
+                            ast::Expression::LITERAL(literal) => self.lower_literal_expr(literal, expected, true)?,
+                            operand => self.lower_expr(operand, expected)?,
+                        };
+                        if !matches!(v_type, ir::DataType::Scalar { size, .. } if size > 0) {
+                            return Err(CompilerError::TypeMismatch { expected: "integer".to_string(), found: v_type.to_string() });
+                        }
+                        Ok((ir::Expr::Binary { op: ir::BinaryOp::Sub, left: zero, right: Box::new(operand) }, v_type))
+                    }
+                    // `~x` is `x ^ !0`
+                    "~" => {
+                        let (operand, v_type) = self.lower_expr(operand, expected)?;
+                        if !matches!(v_type, ir::DataType::Scalar { size, .. } if size > 0) {
+                            return Err(CompilerError::TypeMismatch { expected: "integer".to_string(), found: v_type.to_string() });
+                        }
+                        let ones = Box::new(ir::Expr::Literal(ir::Literal::Int(u64::MAX)));
+                        Ok((ir::Expr::Binary { op: ir::BinaryOp::Xor, left: Box::new(operand), right: ones }, v_type))
+                    }
+                    // `!x` is `x == 0`
+                    "!" => {
+                        let operand = self.lower_cond(operand)?;
+                        let expr = ir::Expr::Binary { op: ir::BinaryOp::Eq, left: Box::new(operand), right: zero };
+                        let v_type = expr.infer_type(None);
+                        Ok((expr, v_type))
+                    }
+                    _ => Err(CompilerError::UnsupportedOperator { op: op.clone() }),
+                }
+            }
+            ast::Expression::CALL { name, arguments } => {
+                let mut args = Vec::new();
+                for arg in arguments.iter() {
+                    args.push(self.lower_expr(arg, None)?.0);
+                }
+                let call = self.module.resolve_call(name.clone(), args)?;
+                let v_type = call.infer_type(None);
+                Ok((call, v_type))
+            }
+        }
+    }
+
+    /// Conditions must be integers or pointers, tested against zero.
+    fn lower_cond(&mut self, cond: &ast::Expression) -> Result<ir::Expr, CompilerError> {
+        let (cond, v_type) = self.lower_expr(cond, None)?;
+        match v_type {
+            ir::DataType::Scalar { size, .. } if size > 0 => Ok(cond),
+            ir::DataType::Pointer { .. } => Ok(cond),
+            _ => Err(CompilerError::TypeMismatch { expected: "bool".to_string(), found: v_type.to_string() }),
+        }
+    }
+
+    /// Lowers a nested scope, whose names are dropped at its end.
+    fn lower_block(&mut self, stats: &[ast::Statement], return_type: &ir::DataType) -> Vec<ir::Statement> {
+        let scope = self.scope.clone();
+        let mut body = Vec::new();
+        for stat in stats.iter() {
+            match self.lower_stat(stat, return_type) {
+                Ok(stat) => body.push(stat),
+                Err(e) => self.errors.push(e),
+            }
+        }
+        self.scope = scope;
+        body
+    }
+
+    fn lower_stat(&mut self, stat: &ast::Statement, return_type: &ir::DataType) -> Result<ir::Statement, CompilerError> {
+        match stat {
+            ast::Statement::EXPRESSION(expr) => Ok(ir::Statement::Expr(self.lower_expr(expr, None)?.0)),
+            ast::Statement::RETURN(expr) => {
+                let (value, v_type) = self.lower_expr(expr, Some(return_type))?;
+                if v_type != *return_type {
+                    return Err(CompilerError::TypeMismatch { expected: return_type.to_string(), found: v_type.to_string() });
+                }
+                Ok(ir::Statement::Return(value))
+            }
+            ast::Statement::BLOCK(stats) => Ok(ir::Statement::Block(self.lower_block(stats, return_type))),
+            ast::Statement::IF { condition, then, otherwise } => {
+                let cond = self.lower_cond(condition)?;
+                Ok(ir::Statement::If {
+                    cond,
+                    then: self.lower_block(then, return_type),
+                    otherwise: self.lower_block(otherwise, return_type),
+                })
+            }
+            ast::Statement::WHILE { condition, body } => {
+                let cond = self.lower_cond(condition)?;
+                self.loops += 1;
+                let body = self.lower_block(body, return_type);
+                self.loops -= 1;
+                Ok(ir::Statement::While { cond, body })
+            }
+            ast::Statement::BREAK | ast::Statement::CONTINUE if self.loops == 0 => {
+                let keyword = if *stat == ast::Statement::BREAK { "break" } else { "continue" };
+                Err(CompilerError::OutsideLoop { keyword: keyword.to_string() })
+            }
+            ast::Statement::BREAK => Ok(ir::Statement::Break),
+            ast::Statement::CONTINUE => Ok(ir::Statement::Continue),
+            ast::Statement::LET { name, data_type, value, constant } => {
+                let declared = match data_type {
+                    Some(t) => Some(self.module.resolve_type(t.clone())?),
+                    None => None,
+                };
+                // The name is not in scope yet, `let x = x` refers to an outer `x`
+                let value = match value {
+                    Some(value) => Some(self.lower_expr(value, declared.as_ref())?),
+                    None if *constant => return Err(CompilerError::UninitializedConst { name: name.clone() }),
+                    None => None,
+                };
+                let v_type = match (declared, &value) {
+                    (Some(declared), Some((_, v_type))) if declared != *v_type => {
+                        return Err(CompilerError::TypeMismatch { expected: declared.to_string(), found: v_type.to_string() });
+                    }
+                    (Some(declared), _) => declared,
+                    (None, Some((_, v_type))) => v_type.clone(),
+                    (None, None) => return Err(CompilerError::UntypedVariable { name: name.clone() }),
+                };
+                let reference = self.current.as_mut().expect("statements are lowered within a function").alloc_local(v_type);
+                self.scope.insert(name.clone(), (reference.clone(), !constant));
+                Ok(ir::Statement::Let { reference, value: value.map(|(value, _)| value) })
+            }
+            ast::Statement::ASSIGN { name, op, value } => {
+                let (target, mutable) = self.scope.get(name).cloned().ok_or(
+                    CompilerError::VariableNotFound { name: name.clone() }
+                )?;
+                if !mutable {
+                    return Err(CompilerError::AssignToConst { name: name.clone() });
+                }
+                let (value, v_type) = self.lower_expr(value, Some(target.v_type()))?;
+                if v_type != *target.v_type() {
+                    return Err(CompilerError::TypeMismatch { expected: target.v_type().to_string(), found: v_type.to_string() });
+                }
+                if op == "=" {
+                    return Ok(ir::Statement::Assign { target, value });
+                }
+                // `a op= b` for any arithmetic `op`
+                let unsupported = || CompilerError::UnsupportedOperator { op: op.clone() };
+                let op = Self::resolve_op(op.strip_suffix('=').ok_or_else(unsupported)?).map_err(|_| unsupported())?;
+                if op.is_comparison() {
+                    return Err(unsupported());
+                }
+                Ok(ir::Statement::CompoundAssign { op, target, value })
+            }
+        }
+    }
+
+    /// Lowers the body of `function`, along with the frame size of its locals.
+    fn lower_function(&mut self, function: &ast::Function) -> (Vec<ir::Statement>, i16) {
+        let Some(declared) = self.module.functions.get(&function.name) else {
+            return (vec![], 0);
+        };
+        let return_type = declared.return_type.clone();
+        self.scope = declared.arguments.iter().enumerate()
+            .map(|(index, (name, v_type))| (name.clone(), (ir::Reference::Argument { index, v_type: v_type.clone() }, true)))
+            .collect();
+        self.current = Some(declared.clone());
+
+        let body = self.lower_block(&function.body, &return_type);
+        self.scope.clear();
+        let stack_size = self.current.take().map_or(0, |current| current.stack_size);
+        (body, stack_size)
+    }
+
+    pub fn lower(mut self, nodes: &LinkedList<ast::ASTNode>) -> Result<ir::Module, Vec<CompilerError>> {
+        let mut functions = Vec::new();
+        for node in nodes.iter() {
+            let unexpected = match node {
+                ast::ASTNode::FUNCTION(function) => {
+                    match self.declare(function) {
+                        Ok(()) => functions.push(function),
+                        Err(e) => self.errors.push(e),
+                    }
+                    continue;
+                }
+                ast::ASTNode::EXPRESSION(_) => "expression".to_string(),
+                ast::ASTNode::VARIABLE(variable) => format!("variable `{}`", variable.name),
+                ast::ASTNode::BODY(_) => "block".to_string(),
+                ast::ASTNode::RETURN(_) => "return statement".to_string(),
+            };
+            self.errors.push(CompilerError::UnexpectedNode { node: unexpected });
+        }
+
+        // Bodies are attached only once every function is lowered, so calls keep
+        // referring to the bare declarations.
+        let bodies: Vec<(String, (Vec<ir::Statement>, i16))> = functions.iter()
+            .map(|function| (function.name.clone(), self.lower_function(function)))
+            .collect();
+        for (name, (body, stack_size)) in bodies {
+            if let Some(function) = self.module.functions.get_mut(&name) {
+                function.body = body;
+                function.stack_size = stack_size;
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(self.module)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod ir;
 pub mod opt;
+pub mod lowering;
 pub mod assembler;
 pub mod writer;
 pub mod codegen;
\ No newline at end of file
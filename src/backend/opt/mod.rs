@@ -22,6 +22,12 @@ pub struct PassManager {
     max_iterations: usize,
 }
 
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PassManager {
     pub fn new() -> Self {
         PassManager {
@@ -11,6 +11,12 @@ pub struct DeadCode {
     state: VisitState,
 }
 
+impl Default for DeadCode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DeadCode {
     pub fn new() -> Self {
         DeadCode {
@@ -25,14 +31,9 @@ impl DeadCode {
     
     fn visit_expr(&mut self, expr: &mut ir::Expr) -> bool {
         match expr {
-            ir::Expr::Binary { op, .. } => {
-                // if it's a binary expression with no side effects, we can remove it
-                match op {
-                    _ => {
-                        // can remove this expression as it has no side effects
-                        return false;
-                    } 
-                }
+            ir::Expr::Binary { .. } => {
+                // Operators have no side effects, calls among the operands do
+                return expr.has_call();
             }
             ir::Expr::Literal(_) => {
                 // literals have no side effects
@@ -43,9 +44,20 @@ impl DeadCode {
         true
     }
 
+    /// Whether `cond` is a constant, and which one.
+    fn constant(cond: &ir::Expr) -> Option<bool> {
+        match cond {
+            ir::Expr::Literal(ir::Literal::Bool(value)) => Some(*value),
+            ir::Expr::Literal(ir::Literal::Int(value)) => Some(*value != 0),
+            _ => None,
+        }
+    }
+
+    /// Returns `false` if the statement can be removed. Sets `has_died` when
+    /// control never gets past it.
     fn visit_stat(&mut self, stat: &mut ir::Statement) -> bool {
         match stat {
-            ir::Statement::Return(_) => {
+            ir::Statement::Return(_) | ir::Statement::Break | ir::Statement::Continue => {
                 if self.state.can_die {
                     self.state.has_died = true;
                 }
@@ -56,12 +68,58 @@ impl DeadCode {
                 self.visit_expr(expr)
             }
 
-            // TODO: Extend to conditional branches when we have them
+            ir::Statement::Let { .. } | ir::Statement::Assign { .. } | ir::Statement::CompoundAssign { .. } => true,
+
+            ir::Statement::Block(body) => {
+                self.visit_body(body);
+                !body.is_empty()
+            }
+
+            ir::Statement::If { cond, then, otherwise } => {
+                if let Some(taken) = Self::constant(cond) {
+                    let body = std::mem::take(if taken { then } else { otherwise });
+                    *stat = ir::Statement::Block(body);
+                    self.changed = true;
+                    return self.visit_stat(stat);
+                }
+                // Dead only if both branches are
+                self.visit_body(then);
+                let then_died = self.state.has_died;
+                self.visit_body(otherwise);
+                self.state.has_died &= then_died;
+                true
+            }
 
-            /* _ => {
+            ir::Statement::While { cond, body } => {
+                if Self::constant(cond) == Some(false) {
+                    return false;
+                }
+                // A `break` may still leave the loop
+                self.visit_body(body);
+                self.state.has_died = false;
                 true
-            } */
+            }
+        }
+    }
+
+    /// Drops the statements of `body` that have no effect or cannot be
+    /// reached. `has_died` tells whether control gets past its end.
+    fn visit_body(&mut self, body: &mut Vec<ir::Statement>) {
+        let len = body.len();
+        let mut new_body = Vec::new();
+        self.state.has_died = false;
+        for (i, mut stat) in std::mem::take(body).into_iter().enumerate() {
+            self.state.has_died = false;
+            if self.visit_stat(&mut stat) {
+                new_body.push(stat);
+            } else { self.changed = true; }
+            if self.state.has_died {
+                // Only unreachable statements count as a change
+                self.changed |= i + 1 < len;
+                break;
+            }
         }
+        *body = new_body;
     }
 }
 
@@ -70,27 +128,14 @@ impl opt::OptimizationPass for DeadCode {
     fn run(&mut self, ir: &mut ir::Module) -> bool {
         self.changed = false;
         for d_fn in ir.functions.iter_mut() {
-            let mut new_body: Vec<ir::Statement> = Vec::new();
             self.state.func = Some(Box::new(d_fn.1.clone()));
             self.state.has_died = false; self.state.can_die = true;
 
-            for stat in d_fn.1.body.iter_mut() {
-                if self.visit_stat(stat) {
-                    new_body.push(stat.clone())
-                } else { self.changed = true; }
-                if self.state.has_died {
-                    self.changed = true;
-                    break;
-                }
-            }
-
-            if self.changed {
-                d_fn.1.body = new_body;
-            }
+            self.visit_body(&mut d_fn.1.body);
     
             self.state.func = None;
         }
         self.changed
     }
     
-}
\ No newline at end of file
+}
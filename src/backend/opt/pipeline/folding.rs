@@ -9,6 +9,12 @@ pub struct ConstantFolding {
     state: VisitState
 }
 
+impl Default for ConstantFolding {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ConstantFolding {
     pub fn new() -> Self {
         ConstantFolding {
@@ -19,27 +25,118 @@ impl ConstantFolding {
         }
     }
 
-    fn visit_expr(&mut self, expr: &mut ir::Expr) {
+    /// Folds `op` over two literals computed in `v_type`, wrapping to its
+    /// width. Division by zero, overflowing division and oversized shifts are
+    /// left for the target to deal with.
+    fn fold(op: &ir::BinaryOp, l: u64, r: u64, v_type: &ir::DataType) -> Option<ir::Literal> {
+        let ir::DataType::Scalar { size: size @ 1..=8, signed } = *v_type else {
+            return None;
+        };
+        let (l, r) = (v_type.canonical(l), v_type.canonical(r));
+        let (sl, sr) = (l as i64, r as i64);
+        let bits = size as u64 * 8;
+        let value = match op {
+            ir::BinaryOp::Add => l.wrapping_add(r),
+            ir::BinaryOp::Sub => l.wrapping_sub(r),
+            ir::BinaryOp::Mul => l.wrapping_mul(r),
+            ir::BinaryOp::Div if signed => sl.checked_div(sr)? as u64,
+            ir::BinaryOp::Div => l.checked_div(r)?,
+            ir::BinaryOp::Rem if signed => sl.checked_rem(sr)? as u64,
+            ir::BinaryOp::Rem => l.checked_rem(r)?,
+            ir::BinaryOp::Shl if r < bits => l << r,
+            ir::BinaryOp::Shr if r < bits && signed => (sl >> r) as u64,
+            ir::BinaryOp::Shr if r < bits => l >> r,
+            ir::BinaryOp::Shl | ir::BinaryOp::Shr => return None,
+            ir::BinaryOp::And => l & r,
+            ir::BinaryOp::Or => l | r,
+            ir::BinaryOp::Xor => l ^ r,
+            ir::BinaryOp::Eq => return Some(ir::Literal::Bool(l == r)),
+            ir::BinaryOp::Ne => return Some(ir::Literal::Bool(l != r)),
+            ir::BinaryOp::Lt if signed => return Some(ir::Literal::Bool(sl < sr)),
+            ir::BinaryOp::Le if signed => return Some(ir::Literal::Bool(sl <= sr)),
+            ir::BinaryOp::Gt if signed => return Some(ir::Literal::Bool(sl > sr)),
+            ir::BinaryOp::Ge if signed => return Some(ir::Literal::Bool(sl >= sr)),
+            ir::BinaryOp::Lt => return Some(ir::Literal::Bool(l < r)),
+            ir::BinaryOp::Le => return Some(ir::Literal::Bool(l <= r)),
+            ir::BinaryOp::Gt => return Some(ir::Literal::Bool(l > r)),
+            ir::BinaryOp::Ge => return Some(ir::Literal::Bool(l >= r)),
+            // Operands are tested in their own types, see `truth`
+            ir::BinaryOp::LogicalAnd | ir::BinaryOp::LogicalOr => return None,
+        };
+        Some(ir::Literal::Int(v_type.canonical(value)))
+    }
+
+    /// Value of an integer literal, and whether a cast gives it its type.
+    fn constant(expr: &ir::Expr) -> Option<(u64, bool)> {
+        match expr {
+            ir::Expr::Literal(ir::Literal::Int(value)) => Some((*value, false)),
+            ir::Expr::Cast { value, .. } => match **value {
+                ir::Expr::Literal(ir::Literal::Int(value)) => Some((value, true)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether a constant condition holds, tested in its own type.
+    fn truth(expr: &ir::Expr) -> Option<bool> {
+        match expr {
+            ir::Expr::Literal(ir::Literal::Bool(value)) => Some(*value),
+            _ => Self::constant(expr).map(|(value, _)| expr.infer_type(None).canonical(value) != 0),
+        }
+    }
+
+    /// `v_type` is the type the context expects, which untyped literals take.
+    fn visit_expr(&mut self, expr: &mut ir::Expr, v_type: &ir::DataType) {
+        if let ir::Expr::Binary { op, left, right } = expr
+            && op.is_logical() {
+            // A constant left side either decides the result or drops out
+            let and = *op == ir::BinaryOp::LogicalAnd;
+            match (Self::truth(left), Self::truth(right)) {
+                (Some(l), _) if l != and => {
+                    self.changed = true;
+                    *expr = ir::Expr::Literal(ir::Literal::Bool(l));
+                }
+                (Some(_), Some(r)) => {
+                    self.changed = true;
+                    *expr = ir::Expr::Literal(ir::Literal::Bool(r));
+                }
+                _ => {
+                    let (l_type, r_type) = (left.infer_type(None), right.infer_type(None));
+                    self.visit_expr(left, &l_type);
+                    self.visit_expr(right, &r_type);
+                }
+            }
+            return;
+        }
+        // Computed in the type instruction selection picks
+        let v_type = match &*expr {
+            ir::Expr::Binary { op, left, right } if op.is_comparison() => ir::Expr::operand_type(left, right),
+            _ => expr.infer_type(Some(v_type.clone())),
+        };
         if let ir::Expr::Binary { op, left, right} = expr {
-            if let (ir::Expr::Literal(ir::Literal::Int(l)), ir::Expr::Literal(ir::Literal::Int(r))) = (&**left, &**right) {
-                let result = match op {
-                    ir::BinaryOp::Add => l + r,
-                    ir::BinaryOp::Sub => l - r,
-                    ir::BinaryOp::Mul => l * r,
-                    ir::BinaryOp::Div => l / r,
-                    ir::BinaryOp::Shl => l << r,
-                    ir::BinaryOp::Shr => l >> r,
-                    ir::BinaryOp::And => l & r,
-                };
+            if let (Some((l, l_cast)), Some((r, r_cast))) = (Self::constant(left), Self::constant(right))
+                && let Some(result) = Self::fold(op, l, r, &v_type) {
                 self.changed = true;
-                *expr = ir::Expr::Literal(ir::Literal::Int(result));
+                *expr = match result {
+                    // Keep the type of the operands for whatever uses the result
+                    ir::Literal::Int(_) if l_cast || r_cast => ir::Expr::Cast { value: Box::new(ir::Expr::Literal(result)), v_type },
+                    _ => ir::Expr::Literal(result),
+                };
                 return; // early return since we have replaced expr.
             }
-            self.visit_expr(left);
-            self.visit_expr(right);
+            self.visit_expr(left, &v_type);
+            self.visit_expr(right, &v_type);
+        } else if let ir::Expr::Cast { value, .. } = expr {
+            self.visit_expr(value, &v_type);
         } else if let ir::Expr::Call(call) = expr {
-            for arg in call.arguments.iter_mut() {
-                self.visit_expr(arg);
+            for (i, arg) in call.arguments.iter_mut().enumerate() {
+                // Trailing variadic arguments keep their own type
+                let v_type = match call.reference.arguments.get(i) {
+                    Some((_, v_type)) => v_type.clone(),
+                    None => arg.infer_type(None),
+                };
+                self.visit_expr(arg, &v_type);
             }
         }
     }
@@ -48,11 +145,42 @@ impl ConstantFolding {
     fn visit_stat(&mut self, stat: &mut ir::Statement) {
         match stat {
             ir::Statement::Expr(expr) => {
-                self.visit_expr(expr);
+                let v_type = expr.infer_type(None);
+                self.visit_expr(expr, &v_type);
             }
             ir::Statement::Return(expr) => {
-                self.visit_expr(expr);
+                let v_type = self.state.func.as_ref().map_or_else(|| expr.infer_type(None), |func| func.return_type.clone());
+                self.visit_expr(expr, &v_type);
+            }
+            ir::Statement::Block(body) => {
+                self.visit_body(body);
+            }
+            ir::Statement::If { cond, then, otherwise } => {
+                let v_type = cond.infer_type(None);
+                self.visit_expr(cond, &v_type);
+                self.visit_body(then);
+                self.visit_body(otherwise);
+            }
+            ir::Statement::While { cond, body } => {
+                let v_type = cond.infer_type(None);
+                self.visit_expr(cond, &v_type);
+                self.visit_body(body);
             }
+            ir::Statement::Let { reference, value } => {
+                if let Some(value) = value {
+                    self.visit_expr(value, reference.v_type());
+                }
+            }
+            ir::Statement::Assign { target, value } | ir::Statement::CompoundAssign { target, value, .. } => {
+                self.visit_expr(value, target.v_type());
+            }
+            ir::Statement::Break | ir::Statement::Continue => {}
+        }
+    }
+
+    fn visit_body(&mut self, body: &mut [ir::Statement]) {
+        for stat in body.iter_mut() {
+            self.visit_stat(stat);
         }
     }
 }
@@ -63,9 +191,7 @@ impl opt::OptimizationPass for ConstantFolding {
         for d_fn in ir.functions.iter_mut() {
             self.state.func = Some(Box::new(d_fn.1.clone()));
 
-            for stat in d_fn.1.body.iter_mut() {
-                self.visit_stat(stat);
-            }
+            self.visit_body(&mut d_fn.1.body);
 
             self.state.func = None;
         }
@@ -1,7 +1,9 @@
 use crate::backend::{ir, opt};
 
 mod math_utils {
-    pub fn compute_magic(d: u64, bits: u32) -> (u64, u32) {
+    /// Magic multiplier and shift for an unsigned division by `d`, `None` when
+    /// the multiplier needs more than 64 bits (e.g. dividing a u64 by 7)
+    pub fn compute_magic(d: u64, bits: u32) -> Option<(u64, u32)> {
         if d == 0 {
             panic!("Division by zero");
         }
@@ -16,8 +18,8 @@ mod math_utils {
     
         let mut q1 = (1u128 << (w - 1)) / nc;
         let mut r1 = (1u128 << (w - 1)) - q1 * nc;
-        let mut q2 = (((1u128 << (w - 1)) - 1)) / d;
-        let mut r2 = (((1u128 << (w - 1)) - 1)) - q2 * d;
+        let mut q2 = ((1u128 << (w - 1)) - 1) / d;
+        let mut r2 = ((1u128 << (w - 1)) - 1) - q2 * d;
         let mut delta;
     
         loop {
@@ -26,14 +28,14 @@ mod math_utils {
                 q1 = q1 * 2 + 1;
                 r1 = r1 * 2 - nc;
             } else {
-                q1 = q1 * 2;
-                r1 = r1 * 2;
+                q1 *= 2;
+                r1 *= 2;
             }
             if r2 + 1 >= d - r2 {
                 q2 = q2 * 2 + 1;
                 r2 = r2 * 2 + 1 - d;
             } else {
-                q2 = q2 * 2;
+                q2 *= 2;
                 r2 = r2 * 2 + 1;
             }
             delta = d - 1 - r2;
@@ -42,17 +44,17 @@ mod math_utils {
             }
         }
 
-        let magic = (q2 + 1) as u64;
-        let shift = (p - w) as u32 + bits;
+        let magic = u64::try_from(q2 + 1).ok()?;
+        let shift = (p - w) + bits;
     
-        (magic, shift)
+        Some((magic, shift))
     }
     
 }
 
 struct VisitState {
     func: Option<Box<ir::Function>>,
-    no_opts: Vec<Box<ir::Expr>>
+    no_opts: Vec<ir::Expr>
 }
 
 pub struct Strength {
@@ -64,6 +66,12 @@ fn is_pow2(n: u64) -> bool {
     n & (n - 1) == 0
 }
 
+impl Default for Strength {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Strength {
     pub fn new() -> Self {
         Strength {
@@ -83,43 +91,39 @@ impl Strength {
     fn polynomial_mul_reduction(&mut self, expr: &mut ir::Expr) {
         // polynomial distribution & addition algorithm
         
-        if let ir::Expr::Binary { left, right, .. } = expr {
-            if let ir::Expr::Literal(ir::Literal::Int(r)) = &**right {
-                let target = *r;
-                let np2 = Strength::lwbd_pow2(target) as u64;
-                *expr = ir::Expr::Binary {
-                    op: ir::BinaryOp::Add,
-                    left: Box::new(ir::Expr::Binary {
-                        op: ir::BinaryOp::Shl,
-                        left: Box::new(*left.clone()),
-                        right: Box::new(ir::Expr::Literal(ir::Literal::Int(np2))),
-                    }),
-                    right: Box::new(ir::Expr::Binary {
-                        op: ir::BinaryOp::Mul,
-                        left: Box::new(*left.clone()),
-                        right: Box::new(ir::Expr::Literal(ir::Literal::Int(target - (1 << np2)))),
-                    }),
-                }
+        if let ir::Expr::Binary { left, right, .. } = expr
+            && let ir::Expr::Literal(ir::Literal::Int(r)) = &**right {
+            let target = *r;
+            let np2 = Strength::lwbd_pow2(target) as u64;
+            *expr = ir::Expr::Binary {
+                op: ir::BinaryOp::Add,
+                left: Box::new(ir::Expr::Binary {
+                    op: ir::BinaryOp::Shl,
+                    left: Box::new(*left.clone()),
+                    right: Box::new(ir::Expr::Literal(ir::Literal::Int(np2))),
+                }),
+                right: Box::new(ir::Expr::Binary {
+                    op: ir::BinaryOp::Mul,
+                    left: Box::new(*left.clone()),
+                    right: Box::new(ir::Expr::Literal(ir::Literal::Int(target - (1 << np2)))),
+                }),
             }
         }
     }
 
-    fn magic_div_reduction(&mut self, expr: &mut ir::Expr, left_bits: u32) {
+    fn magic_div_reduction(&mut self, expr: &mut ir::Expr, magic: (u64, u32)) {
         // modular inverse multiplication algorithm
         
-        if let ir::Expr::Binary { left, right, .. } = expr {
-            if let ir::Expr::Literal(ir::Literal::Int(r)) = &**right {
-                let magic = math_utils::compute_magic(*r, left_bits);
-                *expr = ir::Expr::Binary {
-                    op: ir::BinaryOp::Shr,
-                    left: Box::new(ir::Expr::Binary {
-                        op: ir::BinaryOp::Mul,
-                        left: Box::new(*left.clone()),
-                        right: Box::new( ir::Expr::Literal ( ir::Literal::Int( magic.0 as u64 ) ) ),
-                    }),
-                    right: Box::new( ir::Expr::Literal ( ir::Literal::Int( magic.1 as u64 ) ) ),
-                };
-            }
+        if let ir::Expr::Binary { left, .. } = expr {
+            *expr = ir::Expr::Binary {
+                op: ir::BinaryOp::Shr,
+                left: Box::new(ir::Expr::Binary {
+                    op: ir::BinaryOp::Mul,
+                    left: Box::new(*left.clone()),
+                    right: Box::new( ir::Expr::Literal ( ir::Literal::Int( magic.0 ) ) ),
+                }),
+                right: Box::new( ir::Expr::Literal ( ir::Literal::Int( magic.1 as u64 ) ) ),
+            };
         }
     }
 
@@ -127,7 +131,7 @@ impl Strength {
         // if binary op, check if we can simplify
         let expr_np = expr.clone();
         if let ir::Expr::Binary { op, left, right} = expr{
-            if self.state.no_opts.contains(&Box::new(expr_np)) { return; }
+            if self.state.no_opts.contains(&expr_np) { return; }
             self.visit_expr(left);
             if let ir::Expr::Literal(ir::Literal::Int(_)) = &**left {
                 if let ir::Expr::Literal(_) = &**right {}
@@ -145,18 +149,19 @@ impl Strength {
                 if let ir::Expr::Literal(_) = &**left {}
                 else {
                     match op {
-                        ir::BinaryOp::Add => {
-                            if *r == 0 {
-                                *expr = *left.clone();
-                                self.changed = true;
-                                return;
-                            }
+                        ir::BinaryOp::Add if *r == 0 => {
+                            *expr = *left.clone();
+                            self.changed = true;
+                            return;
                         }
                         ir::BinaryOp::Mul => {
+                            // A call on the left must still be made, and only once
                             if *r == 0 {
-                                *expr = ir::Expr::Literal(ir::Literal::Int(0));
-                                self.changed = true;
-                                return;
+                                if !left.has_call() {
+                                    *expr = ir::Expr::Literal(ir::Literal::Int(0));
+                                    self.changed = true;
+                                    return;
+                                }
                             }
                             else if *r == 1 {
                                 *expr = *left.clone();
@@ -166,12 +171,12 @@ impl Strength {
                                 *expr = ir::Expr::Binary {
                                     op: ir::BinaryOp::Shl,
                                     left: Box::new(*left.clone()),
-                                    right: Box::new(ir::Expr::Literal(ir::Literal::Int(*r >> 1))),
+                                    right: Box::new(ir::Expr::Literal(ir::Literal::Int(r.trailing_zeros() as u64))),
                                 };
                                 self.changed = true;
                                 return;
                             }
-                            else {
+                            else if !left.has_call() {
                                 self.polynomial_mul_reduction(expr);
                                 self.changed = true;
                                 return;
@@ -179,33 +184,27 @@ impl Strength {
                         }
                         ir::BinaryOp::Div => {
                             if *r == 0 {
-                                // Shouldn't happen as it will be checked on the compiler level
-                                *expr = *left.clone();
-                                self.changed = true;
-                                return;
+                                // Left for the target, which traps like the program would
                             }
                             else if is_pow2(*r) {
-                                if let ir::DataType::Scalar { signed, .. } = l_type {
-                                    if !signed {
-                                        *expr = ir::Expr::Binary {
-                                            op: ir::BinaryOp::Shr,
-                                            left: Box::new(*left.clone()),
-                                            right: Box::new(ir::Expr::Literal(ir::Literal::Int(*r >> 1))),
-                                        };
-                                        self.changed = true;
-                                        return;
-                                    }
+                                if let ir::DataType::Scalar { signed, .. } = l_type
+                                    && !signed {
+                                    *expr = ir::Expr::Binary {
+                                        op: ir::BinaryOp::Shr,
+                                        left: Box::new(*left.clone()),
+                                        right: Box::new(ir::Expr::Literal(ir::Literal::Int(r.trailing_zeros() as u64))),
+                                    };
+                                    self.changed = true;
+                                    return;
                                 }
                             }
-                            else {
-                                if let ir::DataType::Scalar { size, signed } = l_type {
-                                    if !signed {
-                                        self.magic_div_reduction(expr, (size*8) as u32);
-                                        self.state.no_opts.push(Box::new(expr.clone()));
-                                        self.changed = true;
-                                        return;
-                                    }
-                                }
+                            else if let ir::DataType::Scalar { size, signed } = l_type
+                                && !signed
+                                && let Some(magic) = math_utils::compute_magic(*r, (size*8) as u32) {
+                                self.magic_div_reduction(expr, magic);
+                                self.state.no_opts.push(expr.clone());
+                                self.changed = true;
+                                return;
                             }
                         }
                         _ => {}
@@ -231,6 +230,33 @@ impl Strength {
             ir::Statement::Return(expr) => {
                 self.visit_expr(expr);
             }
+            ir::Statement::Block(body) => {
+                self.visit_body(body);
+            }
+            ir::Statement::If { cond, then, otherwise } => {
+                self.visit_expr(cond);
+                self.visit_body(then);
+                self.visit_body(otherwise);
+            }
+            ir::Statement::While { cond, body } => {
+                self.visit_expr(cond);
+                self.visit_body(body);
+            }
+            ir::Statement::Let { value, .. } => {
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+            }
+            ir::Statement::Assign { value, .. } | ir::Statement::CompoundAssign { value, .. } => {
+                self.visit_expr(value);
+            }
+            ir::Statement::Break | ir::Statement::Continue => {}
+        }
+    }
+
+    fn visit_body(&mut self, body: &mut [ir::Statement]) {
+        for stat in body.iter_mut() {
+            self.visit_stat(stat);
         }
     }
 }
@@ -241,9 +267,7 @@ impl opt::OptimizationPass for Strength {
         for d_fn in ir.functions.iter_mut() {
             self.state.func = Some(Box::new(d_fn.1.clone()));
 
-            for stat in d_fn.1.body.iter_mut() {
-                self.visit_stat(stat);
-            }
+            self.visit_body(&mut d_fn.1.body);
 
             self.state.func = None;
         }
@@ -1,5 +1,4 @@
-use crate::backend::assembler::x86_64::instr::jmp::Jmp;
-use crate::backend::assembler::x86_64::instr::call::Call;
+use crate::backend::assembler::x86_64::x86::{GPR, RIP, ptr, instructions::{Jmp, Jcc, Cond, Call, Lea}};
 use crate::reporter::asm::AssemblerError;
 
 pub mod obj;
@@ -49,7 +48,7 @@ impl Label {
     pub fn resolve_rel_jmp(&mut self, offset: isize) {
         let addr_rel = offset - self.offset - (self.code.len() as isize);
         self.add_bytes(
-            (addr_rel as isize).jmp()
+            addr_rel.jmp()
         );
     }
 }
@@ -79,10 +78,15 @@ impl Section {
 pub struct CodeBuilder {
     pub sections: Vec<Section>,
     current_section: usize,
-    unresolved_jmps: Vec<(String, (Section, Label, isize))>, // (label_name, (section, label, offset))
+    unresolved_jmps: Vec<(String, (usize, usize, isize))>, // (label_name, (section, label, rel32 offset))
     pub external: Vec<(usize, String)>, // (section, symbol)
     pub relocs: Vec<(usize, String, isize)> // (section, symbol, offset)
 }
+impl Default for CodeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl CodeBuilder {
     pub fn new() -> CodeBuilder {
         CodeBuilder {
@@ -101,12 +105,7 @@ impl CodeBuilder {
         self.sections[self.current_section].kind = kind;
     }
     pub fn get_section(&mut self, name: &str) -> Option<&mut Section> {
-        for section in self.sections.iter_mut() {
-            if section.name == name {
-                return Some(section);
-            }
-        }
-        None
+        self.sections.iter_mut().find(|section| section.name == name)
     }
     pub fn add_extern(&mut self, symbol: String) {
         self.external.push(
@@ -120,21 +119,13 @@ impl CodeBuilder {
     pub fn add_label(&mut self, name: String) -> usize {
         let section = &mut self.sections[self.current_section];
 
-        let mut offset = 0;
-        if section.labels.len() > 0 {
-            offset = section.labels[section.labels.len() - 1].offset + section.labels[section.labels.len() - 1].code.len() as isize;
-        }
+        let offset = section.labels.last().map_or(0, |last| last.offset + last.code.len() as isize);
         section.labels.push(Label::new(name, section.labels.len(), offset));
         section.labels.len() - 1
     }
     pub fn get_label(&mut self, name: &str) -> Option<&mut Label> {
         let section = &mut self.sections[self.current_section];
-        for label in section.labels.iter_mut() {
-            if label.name == name {
-                return Some(label);
-            }
-        }
-        None
+        section.labels.iter_mut().find(|label| label.name == name)
     }
     pub fn bind_label(&mut self, i: usize) {
         let section = &mut self.sections[self.current_section];
@@ -166,50 +157,38 @@ impl CodeBuilder {
         let label = &mut section.labels[section.current_label];
         label.resolve_rel_jmp(offset);
     }
-    pub fn symbol_jmp(&mut self, name: String) {
-        let section_clone = self.sections[self.current_section].clone();
-        let label_clone = section_clone.labels[section_clone.current_label].clone();
+    /// Appends an instruction ending with a rel32 field that must point to `name`.
+    fn symbol_ref(&mut self, name: String, code: Vec<u8>) {
         let section = &mut self.sections[self.current_section];
         let label = &mut section.labels[section.current_label];
-        self.unresolved_jmps.push((name, (section_clone, label_clone, label.code.len() as isize)));
-        let placeholder: Vec<u8> = (0x00 as isize).jmp();
-        let current_section = self.current_section;
-        let current_label = self.sections[current_section].current_label;
-        self.sections[current_section].labels[current_label].add_bytes(placeholder);
-    }
-    pub fn symbol_call(&mut self, name:String) {
-        let section_clone = self.sections[self.current_section].clone();
-        let label_clone = section_clone.labels[section_clone.current_label].clone();
-        let section = &mut self.sections[self.current_section];
-        let label = &mut section.labels[section.current_label];
-        self.unresolved_jmps.push((name, (section_clone, label_clone, label.code.len() as isize)));
-        let placeholder: Vec<u8> = (0x00 as isize).call();
-        let current_section = self.current_section;
-        let current_label = self.sections[current_section].current_label;
-        self.sections[current_section].labels[current_label].add_bytes(placeholder);
+        let field = (label.code.len() + code.len()) as isize - 4;
+        self.unresolved_jmps.push((name, (self.current_section, section.current_label, field)));
+        label.add_bytes(code);
+    }
+    pub fn symbol_jmp(&mut self, name: String) {
+        self.symbol_ref(name, 0.jmp());
+    }
+    pub fn symbol_jcc(&mut self, cond: Cond, name: String) {
+        self.symbol_ref(name, 0.jcc(cond));
+    }
+    pub fn symbol_call(&mut self, name: String) {
+        self.symbol_ref(name, 0.call());
+    }
+    pub fn symbol_lea(&mut self, dst: GPR, name: String) {
+        self.symbol_ref(name, (dst, ptr(RIP, 0, 8)).lea());
     }
     pub fn resolve_jmps(&mut self) -> Result<(), AssemblerError> {
-        let mut sections = self.sections.clone();
-        let unresolved_jmps = self.unresolved_jmps.clone();
-        for (name, (src_section, src_label, offset)) in unresolved_jmps.iter() {
-            let mut found = false;
-            for section in sections.iter_mut() {
-                for label in section.labels.iter_mut() {
-                    if label.name == *name {
-                        self.bind_section(src_section.i);
-                        self.bind_label(src_label.i);
-                        self.set_bytes_at(
-                            (label.offset - src_label.offset - offset.clone() as isize).jmp(),
-                            offset.clone()
-                        );
-                        found = true;
-                        break;
-                    }
+        let unresolved_jmps = std::mem::take(&mut self.unresolved_jmps);
+        for (name, (section_i, label_i, field)) in unresolved_jmps {
+            let section = &self.sections[section_i];
+            let at = section.labels[label_i].offset + field;
+            // Only references within the same section can be patched, anything else is left to the linker
+            match section.labels.iter().find(|label| label.name == name) {
+                Some(target) => {
+                    let rel = (target.offset - (at + 4)) as i32;
+                    self.sections[section_i].labels[label_i].set_bytes_at(rel.to_le_bytes().to_vec(), field as usize);
                 }
-                if found { break; }
-            }
-            if !found {
-                self.relocs.push((src_section.i, name.clone(), offset.clone()+src_label.offset+1));
+                None => self.relocs.push((section_i, name, at)),
             }
         }
         Ok(())
@@ -9,6 +9,11 @@ pub struct ObjectBuilder<'a> {
     pub sections: Vec<SectionId>,
     pub raw_obj: Object<'a>
 }
+impl Default for ObjectBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl<'a> ObjectBuilder<'a> {
     pub fn new() -> Self {
         ObjectBuilder {
@@ -52,18 +57,20 @@ impl<'a> ObjectBuilder<'a> {
             let mut code: Vec<u8> = Vec::new();
             for label in section.labels.clone() {
                 code.extend(label.code.clone());
-                let mut visibility: object::SymbolScope = object::SymbolScope::Linkage;
-                if let Some('.') = label.name.chars().nth(0) {
-                    continue;
-                }
-                if label.global {
-                    visibility = object::SymbolScope::Dynamic;
-                }
+                // Dot-prefixed labels stay local to the object but can still be relocated against
+                let visibility = if label.name.starts_with('.') {
+                    object::SymbolScope::Compilation
+                } else if label.global {
+                    object::SymbolScope::Dynamic
+                } else {
+                    object::SymbolScope::Linkage
+                };
+                let kind = if section.kind == SectionKind::Text { object::SymbolKind::Text } else { object::SymbolKind::Data };
                 let s_id = self.raw_obj.add_symbol(Symbol {
                     name: label.name.clone().into(),
                     value: label.offset as u64,
                     size: label.code.len() as u64,
-                    kind: object::SymbolKind::Text,
+                    kind,
                     scope: visibility,
                     weak: label.weak,
                     section: SymbolSection::Section(self.sections[section.i]),
@@ -82,12 +89,18 @@ impl<'a> ObjectBuilder<'a> {
             let (_, s_id) = self.symbols.get(&symbol).ok_or(
                 ObjectError::SymbolNotFound{symbol: symbol.clone()}
             )?;
+            // Symbols from other objects may live in a shared library, go through the PLT
+            let kind = if cb.external.iter().any(|(_, name)| *name == symbol) {
+                object::write::RelocationKind::PltRelative
+            } else {
+                object::write::RelocationKind::Relative
+            };
             self.raw_obj.add_relocation(self.sections[sect], object::write::Relocation{
                 offset: offset as u64,
                 addend: -4, // hardcoded for a 4-byte displacement field
                 symbol: *s_id,
                 flags: object::write::RelocationFlags::Generic{
-                    kind: object::write::RelocationKind::Relative,
+                    kind,
                     encoding: object::write::RelocationEncoding::X86RipRelative,
                     size: 32
                 }
@@ -98,6 +111,8 @@ impl<'a> ObjectBuilder<'a> {
     }
 
     pub fn scan_cb(&mut self, cb: &CodeBuilder) -> Result<(), ObjectError> {
+        // Without it linkers assume the object needs an executable stack
+        self.raw_obj.add_section(vec![], b".note.GNU-stack".to_vec(), SectionKind::Note);
         self.init_sects(cb);
         self.process_ext(cb);
         self.populate_sects(cb)?;
@@ -5,7 +5,12 @@ pub enum Literal {
     INTEGER(i64),
     FLOAT(f64),
     BOOL(bool),
-    STRING(String)
+    STRING(String),
+    /// A literal with a type suffix, like `42u8`.
+    TYPED {
+      literal: Box<Literal>,
+      data_type: DataType
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -16,6 +21,14 @@ pub enum Expression {
       left: Box<Expression>,
       op: String,
       right: Box<Expression>
+    },
+    UNARY {
+      op: String,
+      operand: Box<Expression>
+    },
+    CALL {
+      name: String,
+      arguments: Vec<Expression>
     }
 }
 
@@ -32,6 +45,29 @@ pub enum ASTNode {
 pub enum Statement {
     EXPRESSION(Expression),
     RETURN(Expression),
+    BLOCK(Vec<Statement>),
+    IF {
+      condition: Expression,
+      then: Vec<Statement>,
+      otherwise: Vec<Statement>
+    },
+    WHILE {
+      condition: Expression,
+      body: Vec<Statement>
+    },
+    BREAK,
+    CONTINUE,
+    LET {
+      name: String,
+      data_type: Option<DataType>,
+      value: Option<Expression>,
+      constant: bool
+    },
+    ASSIGN {
+      name: String,
+      op: String,
+      value: Expression
+    },
 }
 
 
@@ -59,7 +95,7 @@ pub enum Reference {
   
 #[derive(Debug, Clone, PartialEq)]
 pub struct Return {
-    value: Option<Box<ASTNode>>,
+    pub value: Option<Box<ASTNode>>,
 }
 
 impl Return {
@@ -72,7 +108,7 @@ impl Return {
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Body {
-    statements: Vec<ASTNode>,
+    pub statements: Vec<ASTNode>,
     pub consts_table: HashMap<String, ASTNode>,
 }
 
@@ -94,6 +130,8 @@ pub struct Function {
     pub return_type: DataType,
     pub flags: u16,
     pub body: Vec<Statement>,
+    /// `///` comments right above the definition, one per line.
+    pub docs: Vec<String>,
 }
 
 impl Function {
@@ -106,6 +144,7 @@ impl Function {
             return_type,
             flags,
             body,
+            docs: Vec::new(),
         }
     }
 }
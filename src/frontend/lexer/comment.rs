@@ -0,0 +1,62 @@
+use std::borrow::Cow;
+
+use crate::frontend::lexer;
+use crate::frontend::token;
+use crate::reporter::lex::LexerError;
+
+impl lexer::Lexer<'_> {
+    /// `// line`, `/// doc` and `/* nested /* block */ */` comments.
+    ///
+    /// Doc comments always become tokens. Other comments are dropped, or kept as
+    /// whitespace holding their line breaks when `allow_ws` is set.
+    pub fn lex_comment(&mut self) -> Result<(), LexerError> {
+        let start = self.buffer.mark();
+
+        if self.byte_at(1) == Some(b'/') {
+            // `////` and longer are plain comments, like in Rust
+            let doc = self.byte_at(2) == Some(b'/') && self.byte_at(3) != Some(b'/');
+            let length = self.buffer.src[self.buffer.index..].find('\n').unwrap_or(self.buffer.src.len() - self.buffer.index);
+            self.buffer.advance(length);
+            let text = self.slice(start.2);
+
+            if doc {
+                let literal = text[3..].strip_prefix(' ').unwrap_or(&text[3..]);
+                self.tokens.push(token::Token::new(token::TokenType::DOC_COMMENT, literal, self.span(start), self.file));
+            } else if self.allow_ws {
+                self.tokens.push(token::Token::new(token::TokenType::WS, " ", self.span(start), self.file));
+            }
+            return Ok(());
+        }
+
+        let mut depth = 0;
+        let mut breaks = String::new();
+        loop {
+            match (self.byte_at(0), self.byte_at(1)) {
+                (Some(b'/'), Some(b'*')) => {
+                    depth += 1;
+                    self.buffer.advance(2);
+                }
+                (Some(b'*'), Some(b'/')) => {
+                    depth -= 1;
+                    self.buffer.advance(2);
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                (Some(b), _) => {
+                    if b == b'\n' {
+                        breaks.push('\n');
+                    }
+                    self.buffer.advance(1);
+                }
+                (None, _) => return Err(LexerError::unterminated_comment(self.name.to_string(), start.0 + 1, start.1 + 1, self.snippet(start))),
+            }
+        }
+
+        if self.allow_ws {
+            let literal: Cow<str> = if breaks.is_empty() { " ".into() } else { breaks.into() };
+            self.tokens.push(token::Token::new(token::TokenType::WS, literal, self.span(start), self.file));
+        }
+        Ok(())
+    }
+}
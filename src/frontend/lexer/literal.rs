@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+
+use crate::frontend::lexer;
+use crate::frontend::token;
+use crate::reporter::lex::LexerError;
+
+impl<'src> lexer::Lexer<'src> {
+    /// Decodes the escape sequence after a `\`, leaving the cursor past it. An
+    /// invalid one is skipped up to the character after the `\`.
+    fn lex_escape(&mut self) -> Result<char, LexerError> {
+        let result = self.decode_escape();
+        let length = match &result {
+            Ok((_, length)) => *length,
+            Err(_) => 1 + self.char_at(1).filter(|c| *c != '\n').map_or(0, char::len_utf8),
+        };
+        self.buffer.advance(length);
+        result.map(|(decoded, _)| decoded)
+    }
+
+    /// The character the escape sequence at the cursor stands for, and its length.
+    fn decode_escape(&self) -> Result<(char, usize), LexerError> {
+        let mark = self.buffer.mark();
+        let at = |i: usize| self.char_at(i);
+        let invalid = |sequence: String| LexerError::invalid_escape(sequence, self.name.to_string(), mark.0 + 1, mark.1 + 1, self.snippet(mark));
+
+        let (decoded, length) = match at(1) {
+            Some('n') => ('\n', 2),
+            Some('t') => ('\t', 2),
+            Some('r') => ('\r', 2),
+            Some('0') => ('\0', 2),
+            Some('\\') => ('\\', 2),
+            Some('"') => ('"', 2),
+            Some('\'') => ('\'', 2),
+            // `\xNN`, ASCII only
+            Some('x') => {
+                let digits: String = (2..4).map_while(|i| at(i).filter(char::is_ascii_hexdigit)).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 && value <= 0x7F => (value as char, 4),
+                    _ => return Err(invalid(format!("\\x{}", digits))),
+                }
+            }
+            // `\u{N...}`, up to six hex digits
+            Some('u') => {
+                if at(2) != Some('{') {
+                    return Err(invalid("\\u".to_string()));
+                }
+                let digits: String = (3..10).map_while(|i| at(i).filter(char::is_ascii_hexdigit)).collect();
+                let close = 3 + digits.len();
+                if at(close) != Some('}') || !(1..=6).contains(&digits.len()) {
+                    return Err(invalid(format!("\\u{{{}", digits)));
+                }
+                match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+                    Some(c) => (c, close + 1),
+                    None => return Err(invalid(format!("\\u{{{}}}", digits))),
+                }
+            }
+            Some(c) => return Err(invalid(format!("\\{}", c))),
+            None => return Err(invalid("\\".to_string())),
+        };
+        Ok((decoded, length))
+    }
+
+    /// Contents of a literal enclosed in `quote`, with escapes decoded. Only
+    /// literals with escapes are copied out of the source. Invalid escapes are
+    /// reported and decoded as U+FFFD.
+    fn lex_quoted(&mut self, quote: char, kind: &str) -> Result<(Cow<'src, str>, token::Position), LexerError> {
+        let start = self.buffer.mark();
+        let unterminated = |lexer: &Self| LexerError::unterminated_literal(kind.to_string(), lexer.name.to_string(), start.0 + 1, start.1 + 1, lexer.snippet(start));
+        let mut escaped: Option<String> = None;
+        self.buffer.advance(1);
+
+        loop {
+            match self.char_at(0) {
+                Some(c) if c == quote => break,
+                Some('\\') => {
+                    let prefix = self.slice(start.2 + 1);
+                    let decoded = self.lex_escape().unwrap_or_else(|error| {
+                        self.errors.push(error);
+                        char::REPLACEMENT_CHARACTER
+                    });
+                    escaped.get_or_insert_with(|| prefix.to_string()).push(decoded);
+                }
+                Some('\n') | None => return Err(unterminated(self)),
+                Some(c) => {
+                    if let Some(literal) = escaped.as_mut() {
+                        literal.push(c);
+                    }
+                    self.buffer.advance(c.len_utf8());
+                }
+            }
+        }
+
+        let literal = match escaped {
+            Some(literal) => Cow::Owned(literal),
+            None => Cow::Borrowed(self.slice(start.2 + 1)),
+        };
+        self.buffer.advance(1);
+        Ok((literal, self.span(start)))
+    }
+
+    pub fn lex_string(&mut self) -> Result<(), LexerError> {
+        let (literal, position) = self.lex_quoted('"', "string")?;
+        self.tokens.push(token::Token::new(token::TokenType::STRING, literal, position, self.file));
+        Ok(())
+    }
+
+    pub fn lex_char(&mut self) -> Result<(), LexerError> {
+        let start = self.buffer.mark();
+        let (literal, position) = self.lex_quoted('\'', "character")?;
+        if literal.chars().count() != 1 {
+            return Err(LexerError::invalid_char_literal(token::escape(&literal, '\''), self.name.to_string(), start.0 + 1, start.1 + 1, self.snippet(start)));
+        }
+        self.tokens.push(token::Token::new(token::TokenType::CHAR, literal, position, self.file));
+        Ok(())
+    }
+}
@@ -1,116 +1,224 @@
+use crate::frontend::source::{FileId, SourceFile, SourceMap};
 use crate::frontend::token;
 use crate::reporter::lex;
 
 pub mod simd;
+pub mod literal;
+pub mod comment;
+pub mod number;
 
-struct BufferStream {
-    src: Vec<char>,
+struct BufferStream<'src> {
+    src: &'src str,
     index: usize,
+    /// Line and column of the cursor, both 0-based. Columns count characters.
     position: (usize, usize)
 }
 
-impl BufferStream {
-    pub fn new(src: String) -> BufferStream {
+impl<'src> BufferStream<'src> {
+    pub fn new(src: &'src str) -> BufferStream<'src> {
         BufferStream {
-            src: src.chars().collect(),
+            src,
             index: 0,
             position: (0, 0)
         }
     }
 
+    /// Moves the cursor `n` bytes forward.
     pub fn advance(&mut self, n: usize) {
-        for _ in 0..n {
-            if self.src[self.index] == '\n' {
-                self.position.0 += 1;
-                self.position.1 = 0;
+        let skipped = &self.src.as_bytes()[self.index..self.index + n];
+        // Characters are counted by their leading bytes
+        let chars = |bytes: &[u8]| bytes.iter().filter(|b| **b & 0xC0 != 0x80).count();
+        match skipped.iter().rposition(|b| *b == b'\n') {
+            Some(last) => {
+                self.position.0 += skipped.iter().filter(|b| **b == b'\n').count();
+                self.position.1 = chars(&skipped[last + 1..]);
             }
-            self.index += 1;
-            self.position.1 += 1;
+            None => self.position.1 += chars(skipped),
         }
+        self.index += n;
+    }
+
+    /// Line, column and byte offset of the cursor, to start a span with.
+    pub fn mark(&self) -> (usize, usize, usize) {
+        (self.position.0, self.position.1, self.index)
     }
 }
 
-pub struct Lexer {
-    buffer: BufferStream,
-    pub tokens: Vec<token::Token>,
-    allow_ws: bool
+/// Decodes the bytes of the file `name` as UTF-8 source text.
+pub fn decode(name: &str, src: Vec<u8>) -> Result<String, lex::LexerError> {
+    String::from_utf8(src).map_err(|e| utf8_error(name, e.as_bytes(), e.utf8_error().valid_up_to()))
 }
 
-const ASCII_EXTENDED: char = 128 as char;
-const ASCII_EXT_END:  char = 255 as char;
+fn utf8_error(name: &str, src: &[u8], offset: usize) -> lex::LexerError {
+    // Everything before `offset` is valid, so it can be walked for a position
+    let mut buffer = BufferStream::new(std::str::from_utf8(&src[..offset]).unwrap_or_default());
+    buffer.advance(offset);
+    lex::LexerError::invalid_utf8(offset, name.to_string(), buffer.position.0 + 1, buffer.position.1 + 1)
+}
+
+/// Lexes a borrowed source buffer. Tokens borrow from it wherever they can.
+pub struct Lexer<'src> {
+    buffer: BufferStream<'src>,
+    pub tokens: Vec<token::Token<'src>>,
+    errors: Vec<lex::LexerError>,
+    allow_ws: bool,
+    file: FileId,
+    name: &'src str
+}
 
-impl Lexer {
-    pub fn new(src: String, allow_ws: bool) -> Lexer {
+impl<'src> Lexer<'src> {
+    pub fn new(src: &'src str, allow_ws: bool) -> Lexer<'src> {
         Lexer {
             buffer: BufferStream::new(src),
             tokens: Vec::new(),
-            allow_ws
+            errors: Vec::new(),
+            allow_ws,
+            file: FileId::default(),
+            name: "<input>"
         }
     }
 
-    fn stream_to_bytes(&self) -> Vec<u8> {
-        self.buffer.src.iter().map(|c| *c as u8).collect()
+    /// Lexer over a file registered in `map`. Tokens and errors refer back to it.
+    pub fn from_map(map: &'src SourceMap, file: FileId, allow_ws: bool) -> Lexer<'src> {
+        Lexer::from_source(map.get(file), file, allow_ws)
     }
 
-    fn char_err(&mut self, c: char) -> lex::LexerError {
-        let mut line: String = "".to_string();
-        for i in (0..self.buffer.position.1).rev() {
-            if self.buffer.src[i] == '\n' {
-                line = self.buffer.src[i..self.buffer.position.1].iter().collect::<String>();
-                break;
-            }
+    /// Lexer over `source`, known as `file`.
+    pub fn from_source(source: &'src SourceFile, file: FileId, allow_ws: bool) -> Lexer<'src> {
+        Lexer {
+            file,
+            name: &source.name,
+            ..Lexer::new(&source.text, allow_ws)
         }
-        line.push(c);
-        for i in self.buffer.position.1..self.buffer.src.len() {
-            if self.buffer.src[i] == '\n' {
-                break;
-            }
-            line.push(self.buffer.src[i]);
+    }
+
+    /// Lexer over raw source bytes, which must be valid UTF-8.
+    pub fn from_bytes(src: &'src [u8], allow_ws: bool) -> Result<Lexer<'src>, lex::LexerError> {
+        match std::str::from_utf8(src) {
+            Ok(src) => Ok(Lexer::new(src, allow_ws)),
+            Err(e) => Err(utf8_error("<input>", src, e.valid_up_to())),
         }
-        lex::LexerError::invalid_character(c, self.buffer.position.0+1, self.buffer.position.1, line.to_string())
+    }
+
+    /// File the tokens are lexed from.
+    pub fn file(&self) -> FileId {
+        self.file
+    }
+
+    /// Name of that file, as errors print it.
+    pub fn name(&self) -> &'src str {
+        self.name
+    }
+
+    /// Source text from byte `start` up to the cursor.
+    fn slice(&self, start: usize) -> &'src str {
+        let src: &'src str = self.buffer.src;
+        &src[start..self.buffer.index]
+    }
+
+    /// Span from `start`, a `mark()`, up to the cursor.
+    fn span(&self, start: (usize, usize, usize)) -> token::Position {
+        let end = (self.buffer.position.0, self.buffer.position.1.saturating_sub(1));
+        (start.0..end.0, start.1..end.1, start.2..self.buffer.index)
+    }
+
+    /// Text of the line `at`, a `mark()`, is on, underlined there, for diagnostics.
+    fn snippet(&self, at: (usize, usize, usize)) -> String {
+        let (_, column, index) = at;
+        let src = self.buffer.src;
+        let start = src[..index].rfind('\n').map_or(0, |i| i + 1);
+        let end = src[index..].find('\n').map_or(src.len(), |i| index + i);
+        let line = &src[start..end];
+        // Tabs are kept so the caret lines up whatever the tab width
+        let pad: String = line.chars().take(column).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        format!("{}\n    {}^", line, pad)
+    }
+
+    /// Byte `offset` places past the cursor, if any.
+    fn byte_at(&self, offset: usize) -> Option<u8> {
+        self.buffer.src.as_bytes().get(self.buffer.index + offset).copied()
+    }
+
+    /// Character starting `offset` bytes past the cursor, if any.
+    fn char_at(&self, offset: usize) -> Option<char> {
+        self.buffer.src.get(self.buffer.index + offset..)?.chars().next()
+    }
+
+    fn char_err(&mut self, c: char) -> Result<(), lex::LexerError> {
+        let (line, column) = self.buffer.position;
+        let text = self.snippet(self.buffer.mark());
+        self.buffer.advance(c.len_utf8());
+        Err(lex::LexerError::invalid_character(c, self.name.to_string(), line + 1, column + 1, text))
+    }
+
+    /// Records `error` for the text from `start` up to the cursor, and covers
+    /// that text with an `ERROR` token so lexing can go on after it.
+    fn recover(&mut self, start: (usize, usize, usize), reported: usize, error: lex::LexerError) {
+        // Errors further into the token were recorded while lexing it
+        self.errors.insert(reported, error);
+        let token = token::Token::new(token::TokenType::ERROR, self.slice(start.2), self.span(start), self.file);
+        self.tokens.push(token);
+    }
+
+    fn skip_ws(&mut self) {
+        let run = simd::run_len(&self.buffer.src.as_bytes()[self.buffer.index..], simd::Class::Whitespace);
+        self.buffer.advance(run);
     }
 
     fn lex_ws(&mut self) {
-        let start = self.buffer.position;
-        let mut content = "".to_string();
-        while self.buffer.index < self.buffer.src.len() {
-            let c = self.buffer.src[self.buffer.index];
-            if c == ' ' || c == '\n' || c == '\t' {
-                content += &c.to_string();
-                self.buffer.advance(1);
-            } else {
-                break;
-            }
-        }
+        let start = self.buffer.mark();
+        self.skip_ws();
+        let content = self.slice(start.2);
         let end = self.buffer.position;
-        let position = (
-            std::ops::Range { start: start.0, end: end.0 },
-            std::ops::Range { start: start.1, end: end.1 },
-        );
-        self.tokens.push(token::Token::new(token::TokenType::WS, content, position, 0));
-    }
-
-    pub fn lex(&mut self) -> Result<(), lex::LexerError> {
-        let src_bytes = self.stream_to_bytes();
-        while self.buffer.index < src_bytes.len() {
-            let c = src_bytes[self.buffer.index] as char;
-            match c {
-                'a'..='z' | 'A'..='Z' | ASCII_EXTENDED..=ASCII_EXT_END => self.lex_ident(),
-                '0'..='9' => self.lex_number(),
-                '!' | '=' | '<' | '>' => self.lex_punct(),
-                '(' | ')' | '{' | '}' | '[' | ']' | ',' | ':' | ';' => self.lex_punct(),
-                '+' | '-' | '*' | '/' => self.lex_punct(),
-                ' ' | '\n' | '\t' => {
+        let position = (start.0..end.0, start.1..end.1, start.2..self.buffer.index);
+        self.tokens.push(token::Token::new(token::TokenType::WS, content, position, self.file));
+    }
+
+    /// Lexes the whole source. Lexing goes on past errors, so that all of them
+    /// are reported at once, each leaving an `ERROR` token behind.
+    pub fn lex(&mut self) -> Result<(), Vec<lex::LexerError>> {
+        while let Some(byte) = self.byte_at(0) {
+            let start = self.buffer.mark();
+            let reported = self.errors.len();
+            let result = match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                    self.lex_ident();
+                    Ok(())
+                },
+                b'0'..=b'9' => self.lex_number(),
+                b'/' if matches!(self.byte_at(1), Some(b'/' | b'*')) => self.lex_comment(),
+                b'!' | b'=' | b'<' | b'>' | b'%' | b'^' | b'~'
+                | b'(' | b')' | b'{' | b'}' | b'[' | b']' | b',' | b':' | b';'
+                | b'+' | b'-' | b'*' | b'/'
+                | b'&' | b'|' | b'.' => {
+                    self.lex_punct();
+                    Ok(())
+                },
+                b'"' => self.lex_string(),
+                b'\'' => self.lex_char(),
+                b' ' | b'\n' | b'\t' => {
                     if self.allow_ws { self.lex_ws(); }
-                    else { self.buffer.advance(1) }
+                    else { self.skip_ws() }
+                    Ok(())
                 },
                 _ => {
-                    self.buffer.advance(1);
-                    return Err(self.char_err(c));
+                    let c = self.char_at(0).unwrap_or(char::REPLACEMENT_CHARACTER);
+                    if unicode_ident::is_xid_start(c) {
+                        self.lex_ident();
+                        Ok(())
+                    } else {
+                        self.char_err(c)
+                    }
                 },
+            };
+            if let Err(error) = result {
+                self.recover(start, reported, error);
             }
         }
-        Ok(())
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(std::mem::take(&mut self.errors)),
+        }
     }
 
     pub fn dump_tokens(&self) {
@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+
+use crate::frontend::lexer::{self, simd::{self, Class}};
+use crate::frontend::token;
+use crate::reporter::lex::LexerError;
+
+const INTEGER_SUFFIXES: [&str; 8] = ["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64"];
+const FLOAT_SUFFIXES: [&str; 2] = ["f32", "f64"];
+
+/// Largest magnitude a literal with `suffix` can have. Signed types allow their
+/// minimum's magnitude, since a minus sign is a separate token; the lowering
+/// checks the exact range once it knows whether the literal is negated.
+fn integer_limit(suffix: &str) -> u64 {
+    match suffix {
+        "i8" => 1 << 7,
+        "i16" => 1 << 15,
+        "i32" => 1 << 31,
+        "i64" => 1 << 63,
+        "u8" => u8::MAX as u64,
+        "u16" => u16::MAX as u64,
+        "u32" => u32::MAX as u64,
+        _ => u64::MAX,
+    }
+}
+
+impl<'src> lexer::Lexer<'src> {
+    /// Integers in any radix, floats with fractions or exponents, `_` separators
+    /// and type suffixes.
+    ///
+    /// Integers are normalized to decimal, and separators dropped, so that
+    /// `0xFF_u8` yields the literal `255u8`. Literals already in that form
+    /// borrow from the source.
+    pub fn lex_number(&mut self) -> Result<(), LexerError> {
+        let start = self.buffer.mark();
+        let src_bytes = self.buffer.src.as_bytes();
+        let at = |i: usize| self.char_at(i).unwrap_or('\0');
+
+        let (radix, prefix) = match (at(0), at(1)) {
+            ('0', 'x') => (16, "0x"),
+            ('0', 'o') => (8, "0o"),
+            ('0', 'b') => (2, "0b"),
+            _ => (10, ""),
+        };
+        let mut length = prefix.len();
+        length += simd::run_len(&src_bytes[self.buffer.index + length..], if radix == 16 { Class::HexDigits } else { Class::Digits });
+
+        let mut float = false;
+        if radix == 10 {
+            // `1.5`, but neither `1..2` nor `1.max`
+            if at(length) == '.' && at(length + 1).is_ascii_digit() {
+                float = true;
+                length += 1;
+                length += simd::run_len(&src_bytes[self.buffer.index + length..], Class::Digits);
+            }
+            let sign = matches!(at(length + 1), '+' | '-') as usize;
+            if matches!(at(length), 'e' | 'E') && at(length + 1 + sign).is_ascii_digit() {
+                float = true;
+                length += 1 + sign;
+                length += simd::run_len(&src_bytes[self.buffer.index + length..], Class::Digits);
+            }
+        }
+        let digits_end = length;
+        while at(length).is_ascii_alphanumeric() || at(length) == '_' {
+            length += 1;
+        }
+
+        let src: &'src str = self.buffer.src;
+        let text = &src[self.buffer.index..self.buffer.index + length];
+        let digits: Cow<str> = match text[prefix.len()..digits_end].contains('_') {
+            true => text[prefix.len()..digits_end].replace('_', "").into(),
+            false => text[prefix.len()..digits_end].into(),
+        };
+        let suffix = &text[digits_end..];
+        // Past the whole literal even if it is invalid, to go on after it
+        self.buffer.advance(length);
+        let (line, column) = (start.0 + 1, start.1 + 1);
+        let invalid = |reason: String| LexerError::invalid_number(text.to_string(), reason, self.name.to_string(), line, column, self.snippet(start));
+        let overflow = |v_type: &str| LexerError::number_overflow(text.to_string(), v_type.to_string(), self.name.to_string(), line, column, self.snippet(start));
+
+        if digits.is_empty() {
+            return Err(invalid(format!("missing digits after `{}`", prefix)));
+        }
+        if let Some(digit) = digits.chars().find(|c| !float && !c.is_digit(radix)) {
+            return Err(invalid(format!("invalid digit `{}` in a base {} literal", digit, radix)));
+        }
+        if FLOAT_SUFFIXES.contains(&suffix) {
+            if radix != 10 {
+                return Err(invalid(format!("`{}` on a base {} literal", suffix, radix)));
+            }
+            float = true;
+        } else if INTEGER_SUFFIXES.contains(&suffix) {
+            if float {
+                return Err(invalid(format!("integer suffix `{}` on a float", suffix)));
+            }
+        } else if !suffix.is_empty() {
+            return Err(invalid(format!("unknown suffix `{}`", suffix)));
+        }
+
+        let normalized = matches!(digits, Cow::Borrowed(_)) && radix == 10;
+        let (token_type, literal): (_, Cow<str>) = if float {
+            let value: f64 = digits.parse().map_err(|_| invalid("malformed float".to_string()))?;
+            let v_type = if suffix.is_empty() { "f64" } else { suffix };
+            if value.is_infinite() || (v_type == "f32" && value > f32::MAX as f64) {
+                return Err(overflow(v_type));
+            }
+            match normalized {
+                true => (token::TokenType::FLOAT, text.into()),
+                false => (token::TokenType::FLOAT, format!("{}{}", digits, suffix).into()),
+            }
+        } else {
+            let v_type = if suffix.is_empty() { "u64" } else { suffix };
+            let value = u64::from_str_radix(&digits, radix).map_err(|_| overflow(v_type))?;
+            if value > integer_limit(suffix) {
+                return Err(overflow(v_type));
+            }
+            // Leading zeros are dropped too
+            match normalized && (digits.len() == 1 || !digits.starts_with('0')) {
+                true => (token::TokenType::INTEGER, text.into()),
+                false => (token::TokenType::INTEGER, format!("{}{}", value, suffix).into()),
+            }
+        };
+
+        self.tokens.push(token::Token::new(token_type, literal, self.span(start), self.file));
+        Ok(())
+    }
+}
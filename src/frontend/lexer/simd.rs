@@ -1,335 +1,254 @@
 use crate::frontend::lexer;
 use crate::frontend::token;
-use std::simd::cmp::SimdPartialEq;
-use std::simd::{Simd, prelude::SimdPartialOrd};
 
+#[cfg(feature = "simd")]
+use std::simd::{cmp::SimdPartialEq, prelude::SimdPartialOrd, Mask, Simd};
 
-impl lexer::Lexer {
-    pub fn lex_ident(&mut self) {
-        let mut literal = String::new();
-        let start = (self.buffer.position.0, self.buffer.position.1);
-        let src_bytes = self.stream_to_bytes();
-    
-        while self.buffer.index < src_bytes.len() {
-            let remaining = src_bytes.len() - self.buffer.index;
-    
-            if remaining >= 8 {
-                let chunk = Simd::<u8, 8>::from_slice(&src_bytes[self.buffer.index..self.buffer.index + 8]);
-    
-                let lower_bound_lowercase = Simd::splat(b'a');
-                let upper_bound_lowercase = Simd::splat(b'z');
-                let lower_bound_uppercase = Simd::splat(b'A');
-                let upper_bound_uppercase = Simd::splat(b'Z');
-                let lower_bound_numeric = Simd::splat(b'0');
-                let upper_bound_numeric = Simd::splat(b'9');
-                let lower_bound_extended = Simd::splat(lexer::ASCII_EXTENDED as u8);
-                let upper_bound_extended = Simd::splat(lexer::ASCII_EXT_END as u8);
-                let underscore = Simd::splat(b'_');
-                let colon = Simd::splat(b':');
-    
-                let is_lower = chunk.simd_ge(lower_bound_lowercase) & chunk.simd_le(upper_bound_lowercase);
-                let is_upper = chunk.simd_ge(lower_bound_uppercase) & chunk.simd_le(upper_bound_uppercase);
-                let is_numeric = chunk.simd_ge(lower_bound_numeric) & chunk.simd_le(upper_bound_numeric);
-                let is_underscore = chunk.simd_eq(underscore);
-                let is_double_colon = chunk.simd_eq(colon).to_array();
-                let is_extended = chunk.simd_ge(lower_bound_extended) & chunk.simd_le(upper_bound_extended);
-    
-                let is_valid = is_lower | is_upper | is_numeric | is_underscore | is_extended;
-    
-                let mut break_index = 8;
-                let mut i = 0;
-                while i < 8 {
-                    if !is_valid.to_array()[i] {
-                        if i < 7 && is_double_colon[i] && is_double_colon[i + 1] {
-                            literal.push_str("::");
-                            i += 2;
-                        } else {
-                            break_index = i;
-                            break;
-                        }
-                    } else {
-                        literal.push(src_bytes[self.buffer.index + i] as char);
-                        i += 1;
-                    }
-                }
-                self.buffer.advance(i);
-                if break_index < 8 {
-                    break;
-                }
-            } else {
-                let c = src_bytes[self.buffer.index] as char;
-                let next = if self.buffer.index + 1 < src_bytes.len() {
-                    src_bytes[self.buffer.index + 1] as char
-                } else {
-                    '\0'
-                };
-    
-                if c.is_alphanumeric() || c == '_' || (c == ':' && next == ':') {
-                    if c == ':' && next == ':' {
-                        literal.push_str("::");
-                        self.buffer.advance(2);
-                    } else {
-                        literal.push(c);
-                        self.buffer.advance(1);
-                    }
-                } else {
-                    break;
-                }
-            }
+/// Multiplier of the keyword hash, picked so that every keyword lands in its own slot.
+const KEYWORD_HASH: u64 = 0x05b6_e6e3_07d4_bedd;
+
+/// Packs up to 8 bytes into a little-endian word, zero padded.
+const fn pack(bytes: &[u8]) -> u64 {
+    let mut word = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        word |= (bytes[i] as u64) << (8 * i);
+        i += 1;
+    }
+    word
+}
+
+const fn slot(word: u64) -> usize {
+    (word.wrapping_mul(KEYWORD_HASH) >> 59) as usize
+}
+
+/// Perfect hash table of `token::KEYWORDS`. A collision fails the build.
+const KEYWORD_TABLE: [(u64, Option<token::TokenType>); 32] = {
+    let mut table = [(0, None); 32];
+    let mut i = 0;
+    while i < token::KEYWORDS.len() {
+        let (keyword, token_type) = token::KEYWORDS[i];
+        assert!(keyword.len() <= 8, "keywords must fit in a word");
+        let word = pack(keyword.as_bytes());
+        assert!(table[slot(word)].1.is_none(), "keyword hash collision");
+        table[slot(word)] = (word, Some(token_type));
+        i += 1;
+    }
+    table
+};
+
+/// Keyword token for `literal`, or `IDENTIFIER`. One hash and one word compare.
+pub fn classify_ident(literal: &str) -> token::TokenType {
+    let bytes = literal.as_bytes();
+    if bytes.len() > 8 {
+        return token::TokenType::IDENTIFIER;
+    }
+    let word = pack(bytes);
+    match KEYWORD_TABLE[slot(word)] {
+        (keyword, Some(token_type)) if keyword == word => token_type,
+        _ => token::TokenType::IDENTIFIER,
+    }
+}
+
+/// Byte classes the lexer scans runs of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    /// ASCII letters, digits and `_`
+    Ident,
+    /// Decimal digits and `_`
+    Digits,
+    /// Hex digits and `_`
+    HexDigits,
+    /// ` `, `\t` and `\n`
+    Whitespace,
+}
+
+/// Instruction set `run_len` scans with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Scalar,
+    Sse2,
+    Avx2,
+    Avx512,
+}
+
+impl Level {
+    /// Bytes compared at once.
+    pub const fn width(self) -> usize {
+        match self {
+            Level::Scalar => 1,
+            Level::Sse2 => 16,
+            Level::Avx2 => 32,
+            Level::Avx512 => 64,
         }
-    
-        let end = (self.buffer.position.0, self.buffer.position.1.saturating_sub(1));
-        let position = (
-            std::ops::Range { start: start.0, end: end.0 },
-            std::ops::Range { start: start.1, end: end.1 },
-        );
-        let token = token::Token::new(token::TokenType::IDENTIFIER, literal, position, 0);
-        self.tokens.push(token);
     }
-    
+}
 
-    pub fn lex_number(&mut self) {
-        let mut literal = String::new();
-        let start = (self.buffer.position.0, self.buffer.position.1);
-        let src_bytes = self.stream_to_bytes();
+pub mod scalar {
+    use super::Class;
 
-        if src_bytes[self.buffer.index] == b'0' && src_bytes[self.buffer.index + 1] == b'x' {
-            literal.push('0');
-            literal.push('x');
-            self.buffer.advance(2);
+    pub const fn matches(byte: u8, class: Class) -> bool {
+        match class {
+            Class::Ident => byte.is_ascii_alphanumeric() || byte == b'_',
+            Class::Digits => byte.is_ascii_digit() || byte == b'_',
+            Class::HexDigits => byte.is_ascii_hexdigit() || byte == b'_',
+            Class::Whitespace => matches!(byte, b' ' | b'\t' | b'\n'),
         }
-        if src_bytes[self.buffer.index] == b'0' && src_bytes[self.buffer.index + 1] == b'b' {
-            literal.push('0');
-            literal.push('b');
-            self.buffer.advance(2);
+    }
+
+    /// Length of the run of `class` bytes `bytes` starts with.
+    pub fn run_len(bytes: &[u8], class: Class) -> usize {
+        bytes.iter().position(|byte| !matches(*byte, class)).unwrap_or(bytes.len())
+    }
+}
+
+/// `lo <= chunk <= hi` per lane, in a single unsigned compare.
+#[cfg(feature = "simd")]
+#[inline(always)]
+fn in_range<const N: usize>(chunk: Simd<u8, N>, lo: u8, hi: u8) -> Mask<i8, N> {
+    (chunk - Simd::splat(lo)).simd_le(Simd::splat(hi - lo))
+}
+
+#[cfg(feature = "simd")]
+#[inline(always)]
+fn matches<const N: usize>(chunk: Simd<u8, N>, class: Class) -> Mask<i8, N> {
+    let underscore = chunk.simd_eq(Simd::splat(b'_'));
+    // Setting bit 5 folds `A`-`Z` onto `a`-`z`
+    let lower = chunk | Simd::splat(0x20);
+    match class {
+        Class::Ident => in_range(lower, b'a', b'z') | in_range(chunk, b'0', b'9') | underscore,
+        Class::Digits => in_range(chunk, b'0', b'9') | underscore,
+        Class::HexDigits => in_range(lower, b'a', b'f') | in_range(chunk, b'0', b'9') | underscore,
+        Class::Whitespace => chunk.simd_eq(Simd::splat(b' ')) | chunk.simd_eq(Simd::splat(b'\t')) | chunk.simd_eq(Simd::splat(b'\n')),
+    }
+}
+
+/// `run_len` over `N` byte chunks. The first mismatch is the lowest clear bit
+/// of the chunk's bitmask; the tail shorter than a chunk is scanned bytewise.
+#[cfg(feature = "simd")]
+#[inline(always)]
+pub fn run_len_lanes<const N: usize>(bytes: &[u8], class: Class) -> usize {
+    let mut index = 0;
+    while index + N <= bytes.len() {
+        let chunk = Simd::<u8, N>::from_slice(&bytes[index..index + N]);
+        // Bits past lane `N` are set by the negation, so a full match counts `N`
+        let run = (!matches(chunk, class).to_bitmask()).trailing_zeros() as usize;
+        index += run;
+        if run < N {
+            return index;
         }
-        
-        while self.buffer.index < src_bytes.len() {
-            let remaining: usize = src_bytes.len() - self.buffer.index;
-            if remaining >= 8 {
-                let chunk = Simd::<u8, 8>::from_slice(&src_bytes[self.buffer.index..self.buffer.index+8]);
-                let lower_bound = Simd::splat(b'0');
-                let upper_bound = Simd::splat(b'9');
-                let hex_lower_bound = Simd::splat(b'a');
-                let hex_upper_bound = Simd::splat(b'f');
-                
-                let is_decimal = chunk.simd_ge(lower_bound) & chunk.simd_le(upper_bound);
-                let is_hex = chunk.simd_ge(hex_lower_bound) & chunk.simd_le(hex_upper_bound);
+    }
+    index + scalar::run_len(&bytes[index..], class)
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use super::{run_len_lanes, Class};
+
+    #[target_feature(enable = "avx512f,avx512bw")]
+    pub unsafe fn run_len_avx512(bytes: &[u8], class: Class) -> usize {
+        run_len_lanes::<64>(bytes, class)
+    }
 
-                let is_number: std::simd::Mask<i8, 8> = is_decimal | is_hex;
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn run_len_avx2(bytes: &[u8], class: Class) -> usize {
+        run_len_lanes::<32>(bytes, class)
+    }
 
-                let mut break_index = 8;
-                for i in 0..8 {
-                    if !is_number.to_array()[i] {
-                        break_index = i;
-                        break;
-                    }
-                }
-                
-                for i in 0..break_index {
-                    literal.push(src_bytes[self.buffer.index + i] as char);
-                }
-                self.buffer.advance(break_index);
-                if break_index < 8 {
-                    break;
-                }
-            } else {
-                let c = src_bytes[self.buffer.index] as char;
-                if c.is_ascii_hexdigit() {
-                    literal.push(c);
-                    self.buffer.advance(1);
-                } else {
-                    break;
-                }
+    pub unsafe fn run_len_sse2(bytes: &[u8], class: Class) -> usize {
+        run_len_lanes::<16>(bytes, class)
+    }
+}
+
+/// Widest instruction set this CPU supports. Detected once.
+pub fn level() -> Level {
+    static LEVEL: std::sync::OnceLock<Level> = std::sync::OnceLock::new();
+    *LEVEL.get_or_init(|| {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx512bw") {
+                return Level::Avx512;
+            }
+            if is_x86_feature_detected!("avx2") {
+                return Level::Avx2;
             }
+            Level::Sse2
         }
-        let end = (self.buffer.position.0, self.buffer.position.1.saturating_sub(1));
-        let position = (
-            std::ops::Range { start: start.0, end: end.0 },
-            std::ops::Range { start: start.1, end: end.1 },
-        );
-        let token = token::Token::new(token::TokenType::INTEGER, literal, position, 0);
-        self.tokens.push(token);
+        // Other targets get 16 lanes from whatever vector unit they have
+        #[cfg(all(feature = "simd", not(target_arch = "x86_64")))]
+        {
+            Level::Sse2
+        }
+        #[cfg(not(feature = "simd"))]
+        {
+            Level::Scalar
+        }
+    })
+}
+
+/// Length of the run of `class` bytes `bytes` starts with, at the widest
+/// supported `level()`.
+pub fn run_len(bytes: &[u8], class: Class) -> usize {
+    run_len_at(level(), bytes, class)
+}
+
+/// `run_len` at a given `level`, which must be supported by this CPU.
+pub fn run_len_at(level: Level, bytes: &[u8], class: Class) -> usize {
+    assert!(level.width() <= self::level().width(), "{:?} is not supported here", level);
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    // SAFETY: the assert above checks the CPU has the target features
+    unsafe {
+        match level {
+            Level::Avx512 => x86::run_len_avx512(bytes, class),
+            Level::Avx2 => x86::run_len_avx2(bytes, class),
+            Level::Sse2 => x86::run_len_sse2(bytes, class),
+            Level::Scalar => scalar::run_len(bytes, class),
+        }
+    }
+    #[cfg(all(feature = "simd", not(target_arch = "x86_64")))]
+    match level {
+        Level::Scalar => scalar::run_len(bytes, class),
+        _ => run_len_lanes::<16>(bytes, class),
     }
+    #[cfg(not(feature = "simd"))]
+    scalar::run_len(bytes, class)
+}
+
+impl lexer::Lexer<'_> {
+    /// Identifiers start with `_` or an XID start character, and go on with XID
+    /// continue characters. ASCII runs are scanned a vector at a time.
+    pub fn lex_ident(&mut self) {
+        let start = self.buffer.mark();
+        self.buffer.advance(self.char_at(0).map_or(1, char::len_utf8));
+
+        loop {
+            let run = run_len(&self.buffer.src.as_bytes()[self.buffer.index..], Class::Ident);
+            self.buffer.advance(run);
+            match self.char_at(0) {
+                Some(c) if !c.is_ascii() && unicode_ident::is_xid_continue(c) => self.buffer.advance(c.len_utf8()),
+                _ => break,
+            }
+        }
 
-    pub fn off_range(&self, start: (usize, usize), off: usize) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
-        let end = (self.buffer.position.0, self.buffer.position.1.saturating_add(off));
-        (
-            std::ops::Range { start: start.0, end: end.0 },
-            std::ops::Range { start: start.1, end: end.1 },
-        )
+        let literal = self.slice(start.2);
+        let token = token::Token::new(classify_ident(literal), literal, self.span(start), self.file);
+        self.tokens.push(token);
     }
 
     pub fn lex_punct(&mut self) {
-        let start = (self.buffer.position.0, self.buffer.position.1);
-        let src_bytes = self.stream_to_bytes();
-        let c = src_bytes[self.buffer.index] as char;
-        let next = if self.buffer.index + 1 < src_bytes.len() {
-            src_bytes[self.buffer.index + 1] as char
-        } else {
-            '\0'
-        };
+        let start = self.buffer.mark();
+        let src = &self.buffer.src.as_bytes()[self.buffer.index..];
+        // Comparing first bytes up front skips most of the table cheaply
+        let matched = token::PUNCTUATION.iter()
+            .find(|(text, _)| text.as_bytes()[0] == src[0] && src.starts_with(text.as_bytes()));
 
-        match c {
-            '+' => {
-                if next == '=' {
-                    let token = token::Token::new(token::TokenType::PLUS_ASSIGN, "+=".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                }
-                else {
-                    let token = token::Token::new(token::TokenType::PLUS, "+".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '-' => {
-                if next == '=' {
-                    let token = token::Token::new(token::TokenType::MINUS_ASSIGN, "-=".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                }
-                else {
-                    let token = token::Token::new(token::TokenType::MINUS, "-".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '*' => {
-                let token = token::Token::new(token::TokenType::ASTERISK, "*".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            '/' => {
-                let token = token::Token::new(token::TokenType::SLASH, "/".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            '=' => {
-                if next == '=' {
-                    let token = token::Token::new(token::TokenType::EQ, "==".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                } 
-                else if next == '>' {
-                    let token = token::Token::new(token::TokenType::ARROW, "=>".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                }
-                else {
-                    let token = token::Token::new(token::TokenType::ASSIGN, "=".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '!' => {
-                if next == '=' {
-                    let token = token::Token::new(token::TokenType::NEQ, "!=".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                } else {
-                    let token = token::Token::new(token::TokenType::NOT, "!".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '<' => {
-                if next == '=' {
-                    let token = token::Token::new(token::TokenType::LTE, "<=".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                } else {
-                    let token = token::Token::new(token::TokenType::LT, "<".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '>' => {
-                if next == '=' {
-                    let token = token::Token::new(token::TokenType::GTE, ">=".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                } else {
-                    let token = token::Token::new(token::TokenType::GT, ">".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '(' => {
-                let token = token::Token::new(token::TokenType::LPAREN, "(".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            ')' => {
-                let token = token::Token::new(token::TokenType::RPAREN, ")".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            '{' => {
-                let token = token::Token::new(token::TokenType::LBRACE, "{".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            '}' => {
-                let token = token::Token::new(token::TokenType::RBRACE, "}".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            '[' => {
-                let token = token::Token::new(token::TokenType::LBRACKET, "[".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            ']' => {
-                let token = token::Token::new(token::TokenType::RBRACKET, "]".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            ',' => {
-                let token = token::Token::new(token::TokenType::COMMA, ",".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            ':' => {
-                let token = token::Token::new(token::TokenType::COLON, ":".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            ';' => {
-                let token = token::Token::new(token::TokenType::SEMICOLON, ";".to_string(), self.off_range(start, 0), 0);
-                self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            '&' => {
-                if next == '&' {
-                    let token = token::Token::new(token::TokenType::LOGICAL_AND, "&&".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                } else {
-                    let token = token::Token::new(token::TokenType::AND, "&".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '|' => {
-                if next == '|' {
-                    let token = token::Token::new(token::TokenType::LOGICAL_OR, "||".to_string(), self.off_range(start, 1), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(2);
-                } else {
-                    let token = token::Token::new(token::TokenType::OR, "|".to_string(), self.off_range(start, 0), 0);
-                    self.tokens.push(token);
-                    self.buffer.advance(1);
-                }
-            },
-            '.' => {
-                let token = token::Token::new(token::TokenType::DOT, ".".to_string(), self.off_range(start, 0), 0);
+        match matched {
+            Some((text, token_type)) => {
+                self.buffer.advance(text.len());
+                let token = token::Token::new(*token_type, *text, self.span(start), self.file);
                 self.tokens.push(token);
-                self.buffer.advance(1);
-            },
-            _ => {
-                self.buffer.advance(1);
             }
+            None => self.buffer.advance(1),
         }
     }
 }
\ No newline at end of file
@@ -2,4 +2,5 @@ pub mod preprocessor;
 pub mod lexer;
 pub mod parser;
 pub mod token;
-pub mod ast;
\ No newline at end of file
+pub mod ast;
+pub mod source;
\ No newline at end of file
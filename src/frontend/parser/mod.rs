@@ -1,157 +1,522 @@
 use std::{collections::LinkedList, iter::Peekable, slice::Iter};
 
-use crate::frontend::{lexer::Lexer, token::{Token, TokenType}};
+use crate::backend::ir::flags::FunctionModifer;
+use crate::frontend::{lexer::Lexer, source::SourceMap, token::{Token, TokenType}};
 
-use crate::frontend::ast::{ASTNode, Expression, Literal, Variable};
+use crate::frontend::ast::{ASTNode, Expression, Function, Literal, Statement, Variable};
+use crate::reporter::parse::ParserError;
 
-use super::token::BINARY_OPERATORS;
+use super::token::{ASSIGN_OPERATORS, UNARY_OPERATORS};
+
+/// Tokens that can start an expression.
+const FACTOR_START: [TokenType; 8] = [
+    TokenType::INTEGER, TokenType::FLOAT, TokenType::STRING, TokenType::CHAR,
+    TokenType::TRUE, TokenType::FALSE, TokenType::IDENTIFIER, TokenType::LPAREN
+];
+
+/// Operand precedence of prefix operators, above every binary operator.
+const UNARY_PRECEDENCE: u8 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// Precedence and associativity of a binary operator. The higher, the tighter it binds.
+pub const fn get_operator_precedence(tok: TokenType) -> Option<(u8, Associativity)> {
+    use Associativity::*;
+    match tok {
+        TokenType::ASSIGN | TokenType::PLUS_ASSIGN | TokenType::MINUS_ASSIGN
+        | TokenType::ASTERISK_ASSIGN | TokenType::SLASH_ASSIGN | TokenType::PERCENT_ASSIGN
+        | TokenType::AND_ASSIGN | TokenType::OR_ASSIGN | TokenType::CARET_ASSIGN
+        | TokenType::SHL_ASSIGN | TokenType::SHR_ASSIGN => Some((1, Right)),
+        TokenType::LOGICAL_OR => Some((2, Left)),
+        TokenType::LOGICAL_AND => Some((3, Left)),
+        TokenType::EQ | TokenType::NEQ => Some((4, Left)),
+        TokenType::LT | TokenType::GT | TokenType::LTE | TokenType::GTE => Some((5, Left)),
+        TokenType::OR => Some((6, Left)),
+        TokenType::CARET => Some((7, Left)),
+        TokenType::AND => Some((8, Left)),
+        TokenType::SHL | TokenType::SHR => Some((9, Left)),
+        TokenType::PLUS | TokenType::MINUS => Some((10, Left)),
+        TokenType::ASTERISK | TokenType::SLASH | TokenType::PERCENT => Some((11, Left)),
+        _ => None
+    }
+}
+
+/// Splits a numeric literal from the lexer into its digits and type suffix.
+fn split_suffix(literal: &str) -> (&str, Option<&str>) {
+    match literal.find(['i', 'u', 'f']) {
+        Some(index) => (&literal[..index], Some(&literal[index..])),
+        None => (literal, None),
+    }
+}
+
+fn with_suffix(literal: Literal, suffix: Option<&str>) -> Literal {
+    match suffix {
+        Some(suffix) => Literal::TYPED { literal: Box::new(literal), data_type: suffix.to_string() },
+        None => literal,
+    }
+}
 
 
 pub struct Parser<'a> {
-    token_iterator: Peekable<Iter<'a, Token>>,
-    current_token: Option<&'a Token>,
+    token_iterator: Peekable<Iter<'a, Token<'a>>>,
+    current_token: Option<&'a Token<'a>>,
     
-    parsed_ast_nodes: LinkedList<ASTNode>
+    parsed_ast_nodes: LinkedList<ASTNode>,
+    errors: Vec<ParserError>,
+    /// Doc comments right before the current token.
+    docs: Vec<String>,
+    /// Name of the file being parsed, for errors.
+    file: &'a str,
+    /// Files tokens come from, when they come from more than one.
+    sources: Option<&'a SourceMap>,
+    /// Whether the tokens are in a function body, whose expressions must be
+    /// ones the backend can lower.
+    in_body: bool
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(lexer: &'a Lexer) -> Self {
-        let mut iterator = lexer.tokens.iter().peekable();
+    pub fn new(lexer: &'a Lexer<'a>) -> Self {
+        Self::with_tokens(&lexer.tokens, lexer.name(), None)
+    }
+
+    /// Parser of preprocessed `tokens`, each from its own file of `sources`.
+    pub fn from_tokens(tokens: &'a [Token<'a>], sources: &'a SourceMap) -> Self {
+        Self::with_tokens(tokens, "<input>", Some(sources))
+    }
+
+    fn with_tokens(tokens: &'a [Token<'a>], file: &'a str, sources: Option<&'a SourceMap>) -> Self {
+        let mut iterator = tokens.iter().peekable();
         
         // get first token 
         let current_token = iterator.next();
 
-        Self {
+        let mut parser = Self {
             token_iterator: iterator,
-            current_token: current_token,
-            parsed_ast_nodes: LinkedList::new()
-        }        
+            current_token,
+            parsed_ast_nodes: LinkedList::new(),
+            errors: Vec::new(),
+            docs: Vec::new(),
+            file,
+            sources,
+            in_body: false
+        };
+        parser.collect_docs();
+        parser
+    }
+
+    /// Name of the file `token` comes from, for errors.
+    fn file_of(&self, token: &Token) -> String {
+        match self.sources {
+            Some(sources) => sources.get(token.src).name.clone(),
+            None => self.file.to_string(),
+        }
+    }
+
+    /// An error for the current token, which was none of `expected`.
+    fn unexpected(&self, expected: &[TokenType]) -> ParserError {
+        match self.current_token {
+            Some(token) => ParserError::unexpected_token(expected.to_vec(), token.literal.to_string(), self.file_of(token), token.position.clone()),
+            None => ParserError::unexpected_eof(expected.to_vec()),
+        }
+    }
+
+    fn advance(&mut self) {
+        self.current_token = self.token_iterator.next();
+        self.docs.clear();
+        self.collect_docs();
     }
 
-    fn eat(&mut self, expected: TokenType) {
-        if self.current_token.unwrap().token_type == expected {
+    /// Doc comments are not part of the grammar; they are set aside for the next item.
+    fn collect_docs(&mut self) {
+        while let Some(token) = self.current_token
+            && token.token_type == TokenType::DOC_COMMENT {
+            self.docs.push(token.literal.to_string());
             self.current_token = self.token_iterator.next();
-        } else {
-            panic!("Expected {:?}, but found {:?}", expected, self.current_token);
         }
     }
 
-    /// The highest, the most preceding.
-    const fn get_operator_precedence(&self, tok: TokenType) -> u8 {
-        match tok {
-            TokenType::PLUS | TokenType::MINUS => 1,
-            TokenType::ASTERISK | TokenType::SLASH => 2,
-            TokenType::EQ | TokenType::LT | TokenType::GT | TokenType::LTE | TokenType::GTE  => 2,
-            // TokenType::CAST_SYMBOL => 3
-        
-            // TODO: More
-            _ => 0
+    fn eat(&mut self, expected: TokenType) -> Result<(), ParserError> {
+        if self.at(expected) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.unexpected(&[expected]))
         }
     }
 
-    pub fn parse_expression(&mut self, precedence: u8) -> Expression {
-        let mut left = self.parse_factor();
-    
+    fn at(&self, expected: TokenType) -> bool {
+        self.current_token.is_some_and(|token| token.token_type == expected)
+    }
+
+    fn eat_identifier(&mut self) -> Result<String, ParserError> {
+        let name = self.current_token.map(|token| token.literal.to_string());
+        self.eat(TokenType::IDENTIFIER)?;
+        Ok(name.unwrap_or_default())
+    }
 
+    /// A type name, or `*type` for a pointer to it.
+    fn eat_type(&mut self) -> Result<String, ParserError> {
+        if self.at(TokenType::ASTERISK) {
+            self.eat(TokenType::ASTERISK)?;
+            return Ok(format!("*{}", self.eat_type()?));
+        }
+        self.eat_identifier()
+    }
+
+    /// Panic-mode recovery: skips past the next `;`, or up to the next `}`.
+    fn synchronize(&mut self) {
         while let Some(token) = self.current_token {
-            if !BINARY_OPERATORS.contains(&token.token_type) {    
-                break;
+            match token.token_type {
+                TokenType::SEMICOLON => {
+                    self.advance();
+                    return;
+                }
+                TokenType::RBRACE => return,
+                _ => self.advance(),
             }
+        }
+    }
 
-            let new_precedence = self.get_operator_precedence(token.token_type.clone());
-            if new_precedence < precedence {
-                break;
-            }
-    
-            let op = self.current_token.unwrap().clone();
-            self.eat(op.token_type.clone());
-    
-            let mut right = self.parse_factor();  // Right side with higher precedence
-            
-            let token_after_factor = self.current_token.unwrap().clone();
-            
-            if BINARY_OPERATORS.contains(&token_after_factor.token_type) {
-                let next_precedence = self.get_operator_precedence(self.current_token.unwrap().token_type);
-                if new_precedence < next_precedence {
-                    let next_expr = self.parse_expression(next_precedence+1);
-                    if matches!(next_expr, Expression::VARIABLE(_)) {
-                        right = Expression::BINARY { left: Box::new(right), op: token_after_factor.literal.clone(), right: Box::new(next_expr) };
-                    } else {
-                        right = Expression::BINARY { left: Box::new(next_expr), op: token_after_factor.literal.clone(), right: Box::new(right) };
-                    }
-                }
+    /// An error for operator `op`, which function bodies cannot use as `what`.
+    fn unsupported(&self, what: &str, op: &Token) -> ParserError {
+        ParserError::unsupported_operator(what.to_string(), op.literal.to_string(), self.file_of(op), op.position.clone())
+    }
+
+    pub fn parse_expression(&mut self, min_precedence: u8) -> Result<Expression, ParserError> {
+        let mut left = self.parse_prefix()?;
+
+        while let Some(op) = self.current_token
+            && let Some((precedence, associativity)) = get_operator_precedence(op.token_type)
+            && precedence >= min_precedence {
+            // Assignments are statements of their own in a function
+            if self.in_body && ASSIGN_OPERATORS.contains(&op.token_type) {
+                return Err(self.unsupported("Assignment inside an expression", op));
             }
-    
+            self.advance();
+            let next_precedence = match associativity {
+                Associativity::Left => precedence + 1,
+                Associativity::Right => precedence,
+            };
+            let right = self.parse_expression(next_precedence)?;
             left = Expression::BINARY {
                 left: Box::new(left),
+                op: op.literal.to_string(),
                 right: Box::new(right),
-                op: op.literal.clone(),
             };
         }
-    
-        left
-    }    
 
-    pub fn parse_expression_enforce_semicolon(&mut self) -> Expression {
-        let exp = self.parse_expression(0);  // Start with lowest precedence
-        self.eat(TokenType::SEMICOLON);
-        exp
+        Ok(left)
     }
 
-    pub fn parse_term(&mut self) -> Expression {
-        let mut left = self.parse_factor();
-
-        while matches!(self.current_token.expect("Unexpected end of input.").token_type, TokenType::ASTERISK | TokenType::SLASH) {
-            let op = self.current_token.clone();
-            self.eat(op.unwrap().token_type.clone());
-            let right = self.parse_factor();
-            left = Expression::BINARY{ left: Box::new(left), right: Box::new(right), op: op.unwrap().literal.clone() };
+    /// Prefix operators bind tighter than any binary operator.
+    fn parse_prefix(&mut self) -> Result<Expression, ParserError> {
+        match self.current_token {
+            Some(op) if UNARY_OPERATORS.contains(&op.token_type) => {
+                match op.token_type {
+                    TokenType::ASTERISK if self.in_body => return Err(self.unsupported("Dereferencing a pointer", op)),
+                    TokenType::AND if self.in_body => return Err(self.unsupported("Taking an address", op)),
+                    _ => {}
+                }
+                self.advance();
+                let operand = self.parse_expression(UNARY_PRECEDENCE)?;
+                Ok(Expression::UNARY { op: op.literal.to_string(), operand: Box::new(operand) })
+            }
+            _ => self.parse_factor(),
         }
+    }
 
-        left
+    pub fn parse_expression_enforce_semicolon(&mut self) -> Result<Expression, ParserError> {
+        let exp = self.parse_expression(0)?;  // Start with lowest precedence
+        self.eat(TokenType::SEMICOLON)?;
+        Ok(exp)
     }
 
-    pub fn parse_factor(&mut self) -> Expression {
-        let current_token = self.current_token.expect("Unexpected end of input");
+    pub fn parse_factor(&mut self) -> Result<Expression, ParserError> {
+        let Some(current_token) = self.current_token else {
+            return Err(self.unexpected(&FACTOR_START));
+        };
+        let invalid = || ParserError::invalid_literal(current_token.literal.to_string(), self.file_of(current_token), current_token.position.clone());
         match &current_token.token_type {
+            // The lexer leaves integers in decimal, so values above `i64::MAX` keep their bits
             TokenType::INTEGER => {
-                let parsed_value = current_token.literal.parse::<i64>().expect("Token has wrong type.");
-                let expr = Expression::LITERAL(Literal::INTEGER(parsed_value));
-                self.eat(current_token.token_type.clone());
-                expr
+                let (digits, suffix) = split_suffix(&current_token.literal);
+                let parsed_value = digits.parse::<u64>().map_err(|_| invalid())?;
+                self.advance();
+                Ok(Expression::LITERAL(with_suffix(Literal::INTEGER(parsed_value as i64), suffix)))
             }
             TokenType::FLOAT => {
-                let parsed_value = current_token.literal.parse::<f64>().expect("Token has wrong type.");
-                let expr = Expression::LITERAL(Literal::FLOAT(parsed_value));
-                self.eat(current_token.token_type.clone());
-                expr
+                let (digits, suffix) = split_suffix(&current_token.literal);
+                let parsed_value = digits.parse::<f64>().map_err(|_| invalid())?;
+                self.advance();
+                Ok(Expression::LITERAL(with_suffix(Literal::FLOAT(parsed_value), suffix)))
+            }
+            TokenType::TRUE | TokenType::FALSE => {
+                self.advance();
+                Ok(Expression::LITERAL(Literal::BOOL(current_token.token_type == TokenType::TRUE)))
+            }
+            TokenType::STRING => {
+                self.advance();
+                Ok(Expression::LITERAL(Literal::STRING(current_token.literal.to_string())))
+            }
+            // Characters are their code point
+            TokenType::CHAR => {
+                let c = current_token.literal.chars().next().ok_or_else(invalid)?;
+                self.advance();
+                Ok(Expression::LITERAL(Literal::INTEGER(c as i64)))
             }
             TokenType::IDENTIFIER => {
-                let expr = Expression::VARIABLE(Variable { name: current_token.literal.clone() });
-                self.eat(current_token.token_type.clone());
-                expr
+                let name = current_token.literal.to_string();
+                self.advance();
+                if self.at(TokenType::LPAREN) {
+                    let arguments = self.parse_arguments()?;
+                    return Ok(Expression::CALL { name, arguments });
+                }
+                Ok(Expression::VARIABLE(Variable { name }))
             }
             TokenType::LPAREN => {
-                self.eat(TokenType::LPAREN);
-                let inner_expression = self.parse_expression(0);
-                self.eat(TokenType::RPAREN);
-                inner_expression
+                self.eat(TokenType::LPAREN)?;
+                let inner_expression = self.parse_expression(0)?;
+                self.eat(TokenType::RPAREN)?;
+                Ok(inner_expression)
             }
-            _ if BINARY_OPERATORS.contains(&current_token.token_type) => {
-                self.eat(self.current_token.unwrap().token_type.clone());
-                self.parse_factor()
+            _ => Err(self.unexpected(&FACTOR_START)),
+        }
+    }
+
+    /// `(expr, expr, ...)`
+    fn parse_arguments(&mut self) -> Result<Vec<Expression>, ParserError> {
+        let mut arguments = Vec::new();
+        self.eat(TokenType::LPAREN)?;
+        while !self.at(TokenType::RPAREN) {
+            arguments.push(self.parse_expression(0)?);
+            if !self.at(TokenType::COMMA) {
+                break;
             }
-            _ => panic!("Unexpected token: {:?}", self.current_token),
+            self.eat(TokenType::COMMA)?;
         }
+        if !self.at(TokenType::RPAREN) {
+            return Err(self.unexpected(&[TokenType::COMMA, TokenType::RPAREN]));
+        }
+        self.advance();
+        Ok(arguments)
+    }
+
+    /// `fn name(arg: type, ...) => type { ... }`, returning `void` when no type is given.
+    ///
+    /// `extern fn name(arg: type, ...) => type;` declares a function defined
+    /// elsewhere, taking any further arguments when its list ends with `...`.
+    pub fn parse_function(&mut self) -> Result<Function, ParserError> {
+        let docs = std::mem::take(&mut self.docs);
+        let external = self.at(TokenType::EXTERN);
+        let mut flags = 0;
+        if external {
+            self.eat(TokenType::EXTERN)?;
+            flags |= FunctionModifer::Extern as u16;
+        }
+        self.eat(TokenType::FN)?;
+        let name = self.eat_identifier()?;
+
+        let mut arguments = Vec::new();
+        self.eat(TokenType::LPAREN)?;
+        while !self.at(TokenType::RPAREN) {
+            if external && self.at(TokenType::DOT) {
+                for _ in 0..3 {
+                    self.eat(TokenType::DOT)?;
+                }
+                flags |= FunctionModifer::Variadic as u16;
+                break;
+            }
+            let argument = self.eat_identifier()?;
+            self.eat(TokenType::COLON)?;
+            arguments.push((argument, self.eat_type()?));
+            if !self.at(TokenType::COMMA) {
+                break;
+            }
+            self.eat(TokenType::COMMA)?;
+        }
+        if !self.at(TokenType::RPAREN) {
+            return Err(self.unexpected(&[TokenType::COMMA, TokenType::RPAREN]));
+        }
+        self.advance();
+
+        let body_start = if external { TokenType::SEMICOLON } else { TokenType::LBRACE };
+        let return_type = if self.at(TokenType::ARROW) {
+            self.eat(TokenType::ARROW)?;
+            self.eat_type()?
+        } else if self.at(body_start) {
+            "void".to_string()
+        } else {
+            return Err(self.unexpected(&[TokenType::ARROW, body_start]));
+        };
+
+        let body = if external {
+            self.eat(TokenType::SEMICOLON)?;
+            vec![]
+        } else {
+            self.in_body = true;
+            let body = self.parse_block();
+            self.in_body = false;
+            body?
+        };
+        let mut function = Function::new(name, String::new(), arguments, return_type, flags, body);
+        function.docs = docs;
+        Ok(function)
+    }
+
+    /// `{ statement* }`, recovering from errors in each statement.
+    pub fn parse_block(&mut self) -> Result<Vec<Statement>, ParserError> {
+        let mut statements = Vec::new();
+        self.eat(TokenType::LBRACE)?;
+        while self.current_token.is_some() && !self.at(TokenType::RBRACE) {
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize();
+                }
+            }
+        }
+        self.eat(TokenType::RBRACE)?;
+        Ok(statements)
+    }
+
+    pub fn parse_statement(&mut self) -> Result<Statement, ParserError> {
+        if self.at(TokenType::LBRACE) {
+            return Ok(Statement::BLOCK(self.parse_block()?));
+        }
+        if self.at(TokenType::LET) || self.at(TokenType::CONST) {
+            return self.parse_declaration();
+        }
+        if self.at(TokenType::IF) {
+            return self.parse_if();
+        }
+        if self.at(TokenType::WHILE) {
+            self.eat(TokenType::WHILE)?;
+            let condition = self.parse_expression(0)?;
+            let body = self.parse_block()?;
+            return Ok(Statement::WHILE { condition, body });
+        }
+        if self.at(TokenType::RETURN) {
+            self.eat(TokenType::RETURN)?;
+            return Ok(Statement::RETURN(self.parse_expression_enforce_semicolon()?));
+        }
+        if self.at(TokenType::BREAK) {
+            self.eat(TokenType::BREAK)?;
+            self.eat(TokenType::SEMICOLON)?;
+            return Ok(Statement::BREAK);
+        }
+        if self.at(TokenType::CONTINUE) {
+            self.eat(TokenType::CONTINUE)?;
+            self.eat(TokenType::SEMICOLON)?;
+            return Ok(Statement::CONTINUE);
+        }
+
+        // `name op= expr;`
+        let assigns = self.token_iterator.peek().is_some_and(|next| ASSIGN_OPERATORS.contains(&next.token_type));
+        if self.at(TokenType::IDENTIFIER) && assigns {
+            let name = self.eat_identifier()?;
+            let op = self.current_token.map(|token| token.literal.to_string()).unwrap_or_default();
+            self.advance();
+            let value = self.parse_expression_enforce_semicolon()?;
+            return Ok(Statement::ASSIGN { name, op, value });
+        }
+
+        Ok(Statement::EXPRESSION(self.parse_expression_enforce_semicolon()?))
+    }
+
+    /// `let|const name (: type)? (= expr)?;`
+    fn parse_declaration(&mut self) -> Result<Statement, ParserError> {
+        let constant = self.at(TokenType::CONST);
+        if constant {
+            self.eat(TokenType::CONST)?;
+        } else {
+            self.eat(TokenType::LET)?;
+        }
+        let name = self.eat_identifier()?;
+
+        let data_type = if self.at(TokenType::COLON) {
+            self.eat(TokenType::COLON)?;
+            Some(self.eat_type()?)
+        } else {
+            None
+        };
+        let value = if self.at(TokenType::ASSIGN) {
+            self.eat(TokenType::ASSIGN)?;
+            Some(self.parse_expression(0)?)
+        } else {
+            None
+        };
+        if !self.at(TokenType::SEMICOLON) {
+            let expected = match (&data_type, &value) {
+                (_, Some(_)) => vec![TokenType::SEMICOLON],
+                (Some(_), None) => vec![TokenType::ASSIGN, TokenType::SEMICOLON],
+                (None, None) => vec![TokenType::COLON, TokenType::ASSIGN, TokenType::SEMICOLON],
+            };
+            return Err(self.unexpected(&expected));
+        }
+        self.advance();
+
+        Ok(Statement::LET { name, data_type, value, constant })
     }
 
+    /// `if cond { ... } (else if cond { ... })* (else { ... })?`
+    fn parse_if(&mut self) -> Result<Statement, ParserError> {
+        self.eat(TokenType::IF)?;
+        let condition = self.parse_expression(0)?;
+        let then = self.parse_block()?;
 
-    pub fn parse_all_tokens(&mut self) -> &LinkedList<ASTNode> {
+        let otherwise = if self.at(TokenType::ELSE) {
+            self.eat(TokenType::ELSE)?;
+            if self.at(TokenType::IF) {
+                vec![self.parse_if()?]
+            } else {
+                self.parse_block()?
+            }
+        } else {
+            vec![]
+        };
+
+        Ok(Statement::IF { condition, then, otherwise })
+    }
+
+    fn parse_top_level(&mut self) -> Result<ASTNode, ParserError> {
+        if self.at(TokenType::FN) || self.at(TokenType::EXTERN) {
+            Ok(ASTNode::FUNCTION(self.parse_function()?))
+        } else {
+            Ok(ASTNode::EXPRESSION(self.parse_expression_enforce_semicolon()?))
+        }
+    }
+
+    /// Functions at the top level; anything else is read as an expression statement.
+    ///
+    /// Parsing carries on after a syntax error, so every error in the input is returned.
+    pub fn parse_all_tokens(&mut self) -> Result<&LinkedList<ASTNode>, Vec<ParserError>> {
         while self.current_token.is_some() {
-            let node = ASTNode::EXPRESSION(self.parse_expression_enforce_semicolon());
-            self.parsed_ast_nodes.push_back(node);
+            match self.parse_top_level() {
+                Ok(node) => self.parsed_ast_nodes.push_back(node),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.synchronize();
+                    // A stray `}` cannot close anything at the top level.
+                    if self.at(TokenType::RBRACE) {
+                        self.advance();
+                    }
+                }
+            }
         }
-    
+
+        if self.errors.is_empty() {
+            Ok(&self.parsed_ast_nodes)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    pub fn get_nodes(&self) -> &LinkedList<ASTNode> {
         &self.parsed_ast_nodes
     }
 
@@ -162,4 +527,3 @@ impl<'a> Parser<'a> {
     }
     
 }
-
@@ -0,0 +1,27 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Macros the preprocessor defines itself, unless a macro of the same name is defined.
+pub const BUILTINS: [&str; 4] = ["__FILE__", "__LINE__", "__COUNTER__", "__DATE__"];
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// `__DATE__` for `secs` seconds since the Unix epoch, in UTC and spelled as
+/// in C, e.g. "Oct  8 2026".
+pub fn date(secs: u64) -> String {
+    // Howard Hinnant's days-to-civil, with years starting in March
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    format!("{} {:>2} {}", MONTHS[month as usize - 1], day, year)
+}
+
+/// `__DATE__` of the current day.
+pub fn today() -> String {
+    date(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()))
+}
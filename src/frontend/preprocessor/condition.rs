@@ -0,0 +1,185 @@
+use crate::frontend::token::{Token, TokenType};
+
+use super::macros::{BodyNode, Macro};
+
+/// Evaluates `!if` and `!elif` conditions: 64-bit integer arithmetic where
+/// `defined(NAME)` is 1 for a defined macro, a macro stands for the value of
+/// its body, and any other name is 0.
+pub struct Evaluator<'m> {
+    macros: &'m [Macro],
+    /// Macros whose bodies are being evaluated. Inside themselves they are 0.
+    expanding: Vec<String>
+}
+
+/// Tokens of one condition, without whitespace.
+struct Cursor<'t, 'src> {
+    tokens: Vec<&'t Token<'src>>,
+    index: usize
+}
+
+impl<'t, 'src> Cursor<'t, 'src> {
+    fn peek(&self) -> Option<&'t Token<'src>> {
+        self.tokens.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<&'t Token<'src>> {
+        let token = self.peek();
+        self.index += 1;
+        token
+    }
+
+    fn expect(&mut self, token_type: TokenType) -> Result<&'t Token<'src>, String> {
+        match self.next() {
+            Some(token) if token.token_type == token_type => Ok(token),
+            Some(token) => Err(format!("expected {:?}, found `{}`", token_type, token.literal)),
+            None => Err(format!("expected {:?}, found the end of the line", token_type)),
+        }
+    }
+}
+
+/// Binding power of a binary operator, from `||` up to `*`.
+fn precedence(op: TokenType) -> Option<u8> {
+    use TokenType::*;
+    match op {
+        LOGICAL_OR => Some(1),
+        LOGICAL_AND => Some(2),
+        OR => Some(3),
+        CARET => Some(4),
+        AND => Some(5),
+        EQ | NEQ => Some(6),
+        LT | LTE | GT | GTE => Some(7),
+        SHL | SHR => Some(8),
+        PLUS | MINUS => Some(9),
+        ASTERISK | SLASH | PERCENT => Some(10),
+        _ => None,
+    }
+}
+
+impl<'m> Evaluator<'m> {
+    pub fn new(macros: &'m [Macro]) -> Evaluator<'m> {
+        Evaluator { macros, expanding: Vec::new() }
+    }
+
+    pub fn evaluate(&mut self, tokens: &[Token]) -> Result<i64, String> {
+        let mut cursor = Cursor {
+            tokens: tokens.iter().filter(|t| !matches!(t.token_type, TokenType::WS | TokenType::DOC_COMMENT)).collect(),
+            index: 0
+        };
+        if cursor.tokens.is_empty() {
+            return Err("missing condition".to_string());
+        }
+        let value = self.expression(&mut cursor, 1, true)?;
+        match cursor.peek() {
+            Some(token) => Err(format!("unexpected `{}`", token.literal)),
+            None => Ok(value),
+        }
+    }
+
+    /// Operators binding at least `min`. Where `live` is false the value is
+    /// discarded, as on the right of a short-circuited `&&`, so it may not fail.
+    fn expression(&mut self, cursor: &mut Cursor, min: u8, live: bool) -> Result<i64, String> {
+        let mut left = self.unary(cursor, live)?;
+        while let Some(op) = cursor.peek().map(|t| t.token_type)
+            && let Some(power) = precedence(op)
+            && power >= min {
+            cursor.next();
+            let right_live = live && match op {
+                TokenType::LOGICAL_AND => left != 0,
+                TokenType::LOGICAL_OR => left == 0,
+                _ => true,
+            };
+            let right = self.expression(cursor, power + 1, right_live)?;
+            left = match (op, live) {
+                (TokenType::SLASH | TokenType::PERCENT, false) if right == 0 => 0,
+                _ => Self::binary(op, left, right)?,
+            };
+        }
+        Ok(left)
+    }
+
+    fn binary(op: TokenType, left: i64, right: i64) -> Result<i64, String> {
+        use TokenType::*;
+        let shift = || u32::try_from(right).ok().filter(|s| *s < 64).ok_or(format!("cannot shift by {}", right));
+        Ok(match op {
+            LOGICAL_OR => (left != 0 || right != 0) as i64,
+            LOGICAL_AND => (left != 0 && right != 0) as i64,
+            OR => left | right,
+            CARET => left ^ right,
+            AND => left & right,
+            EQ => (left == right) as i64,
+            NEQ => (left != right) as i64,
+            LT => (left < right) as i64,
+            LTE => (left <= right) as i64,
+            GT => (left > right) as i64,
+            GTE => (left >= right) as i64,
+            SHL => left.wrapping_shl(shift()?),
+            SHR => left.wrapping_shr(shift()?),
+            PLUS => left.wrapping_add(right),
+            MINUS => left.wrapping_sub(right),
+            ASTERISK => left.wrapping_mul(right),
+            SLASH | PERCENT if right == 0 => return Err("division by zero".to_string()),
+            SLASH => left.wrapping_div(right),
+            PERCENT => left.wrapping_rem(right),
+            _ => return Err(format!("unsupported operator {:?}", op)),
+        })
+    }
+
+    fn unary(&mut self, cursor: &mut Cursor, live: bool) -> Result<i64, String> {
+        let Some(token) = cursor.next() else {
+            return Err("unexpected end of the condition".to_string());
+        };
+        match token.token_type {
+            TokenType::NOT => Ok((self.unary(cursor, live)? == 0) as i64),
+            TokenType::MINUS => Ok(self.unary(cursor, live)?.wrapping_neg()),
+            TokenType::PLUS => self.unary(cursor, live),
+            TokenType::TILDE => Ok(!self.unary(cursor, live)?),
+            TokenType::LPAREN => {
+                let value = self.expression(cursor, 1, live)?;
+                cursor.expect(TokenType::RPAREN)?;
+                Ok(value)
+            }
+            TokenType::INTEGER => {
+                // The lexer writes integers in decimal, suffix aside
+                let digits = token.literal.trim_end_matches(|c: char| !c.is_ascii_digit());
+                digits.parse::<u64>().map(|value| value as i64).map_err(|_| format!("invalid integer `{}`", token.literal))
+            }
+            TokenType::TRUE => Ok(1),
+            TokenType::FALSE => Ok(0),
+            TokenType::IDENTIFIER if token.literal == "defined" => {
+                let parenthesized = cursor.peek().is_some_and(|t| t.token_type == TokenType::LPAREN);
+                if parenthesized {
+                    cursor.next();
+                }
+                let name = cursor.expect(TokenType::IDENTIFIER)?;
+                if parenthesized {
+                    cursor.expect(TokenType::RPAREN)?;
+                }
+                Ok(self.macros.iter().any(|m| m.name == name.literal) as i64)
+            }
+            TokenType::IDENTIFIER => self.value_of(&token.literal),
+            _ => Err(format!("unexpected `{}`", token.literal)),
+        }
+    }
+
+    /// Value of the macro `name`, or 0 if there is none.
+    fn value_of(&mut self, name: &str) -> Result<i64, String> {
+        if self.expanding.iter().any(|expanding| expanding == name) {
+            return Ok(0);
+        }
+        let Some(macro_) = self.macros.iter().find(|m| m.name == name) else {
+            return Ok(0);
+        };
+        if !macro_.params.is_empty() {
+            return Err(format!("macro `{}` takes arguments", name));
+        }
+        let body: Vec<Token> = macro_.body.iter().filter_map(BodyNode::token).cloned().collect();
+        if body.iter().all(|token| token.token_type == TokenType::WS) {
+            return Ok(0);
+        }
+
+        self.expanding.push(name.to_string());
+        let value = self.evaluate(&body);
+        self.expanding.pop();
+        value.map_err(|reason| format!("{}, in macro `{}`", reason, name))
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::frontend::lexer;
+use crate::frontend::source::{FileId, SourceFile};
+use crate::frontend::token;
+use crate::reporter::prep::PreprocessorError;
+
+/// Finds, reads and lexes the files `!include` names.
+pub struct Includes {
+    dirs: Vec<PathBuf>,
+    /// Path of each file by id, where it is known.
+    paths: Vec<Option<PathBuf>>,
+    /// Files loaded so far, the first of them with id `first`.
+    files: Vec<SourceFile>,
+    first: usize,
+    /// Files that asked, with `!once`, to be included only once.
+    once: HashSet<PathBuf>
+}
+
+impl Default for Includes {
+    fn default() -> Self {
+        // The including file is usually the first one
+        Self::new(FileId(1))
+    }
+}
+
+impl Includes {
+    /// Loader whose files get ids from `first` on.
+    pub fn new(first: FileId) -> Includes {
+        Includes {
+            dirs: Vec::new(),
+            paths: Vec::new(),
+            files: Vec::new(),
+            first: first.0,
+            once: HashSet::new()
+        }
+    }
+
+    /// Directory to search after the one of the including file.
+    pub fn add_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.dirs.push(dir.into());
+    }
+
+    /// Records that `file` was read from `path`, so its includes resolve next to it.
+    pub fn set_path(&mut self, file: FileId, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        if self.paths.len() <= file.0 {
+            self.paths.resize(file.0 + 1, None);
+        }
+        self.paths[file.0] = Some(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+    }
+
+    pub fn path(&self, file: FileId) -> Option<&Path> {
+        self.paths.get(file.0)?.as_deref()
+    }
+
+    /// Name of `file` for diagnostics.
+    pub fn name(&self, file: FileId) -> String {
+        match self.path(file) {
+            Some(path) => path.display().to_string(),
+            None => "<input>".to_string(),
+        }
+    }
+
+    /// Finds `name` next to the file `from`, then in each include directory.
+    pub fn resolve(&self, name: &str, from: FileId) -> Option<PathBuf> {
+        let here = self.path(from).and_then(Path::parent).map(Path::to_path_buf).unwrap_or_default();
+        std::iter::once(here).chain(self.dirs.iter().cloned())
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+            .map(|path| path.canonicalize().unwrap_or(path))
+    }
+
+    pub fn mark_once(&mut self, file: FileId) {
+        if let Some(path) = self.path(file) {
+            self.once.insert(path.to_path_buf());
+        }
+    }
+
+    /// Whether `path` was marked `!once` and so is not included again.
+    pub fn is_once(&self, path: &Path) -> bool {
+        self.once.contains(path)
+    }
+
+    /// Reads and lexes `path`, keeping the file for diagnostics. Tokens are
+    /// lexed with whitespace, as for any preprocessor input.
+    pub fn load(&mut self, path: &Path) -> Result<Vec<token::Token<'static>>, Vec<PreprocessorError>> {
+        let name = path.display().to_string();
+        let bytes = std::fs::read(path).map_err(|e| vec![PreprocessorError::file_read_error(name.clone(), e)])?;
+        let text = lexer::decode(&name, bytes).map_err(|e| vec![e.into()])?;
+
+        let file = FileId(self.first + self.files.len());
+        self.set_path(file, path);
+        self.files.push(SourceFile::new(name, text));
+        let Some(source) = self.files.last() else {
+            return Err(vec![PreprocessorError::Unknown]);
+        };
+        let mut lexer = lexer::Lexer::from_source(source, file, true);
+        lexer.lex().map_err(|errors| errors.into_iter().map(PreprocessorError::from).collect::<Vec<_>>())?;
+        Ok(lexer.tokens.into_iter().map(token::Token::into_owned).collect())
+    }
+
+    /// Files loaded so far, in id order. Later files keep counting on.
+    pub fn take_files(&mut self) -> Vec<SourceFile> {
+        self.first += self.files.len();
+        std::mem::take(&mut self.files)
+    }
+}
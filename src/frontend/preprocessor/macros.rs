@@ -1,8 +1,23 @@
+use super::mapping::Origin;
+use crate::frontend::lexer::Lexer;
+use crate::frontend::token::{Token, TokenType};
+
 #[derive(Clone, Debug)]
 pub enum BodyNode {
-    Lexical(String),
+    Lexical(Token<'static>),
     Param(String),
+    /// `!for::arg { ... }`: the body once for each argument, as `arg`.
     ArgIteration(Vec<BodyNode>),
+    /// `!str(...)`: a string of the tokens, arguments not expanded.
+    Stringify(Vec<BodyNode>),
+    /// `!paste(...)`: the tokens joined into one, arguments not expanded.
+    Paste(Vec<BodyNode>),
+    /// `!count`: the number of arguments.
+    ArgCount,
+    /// `!index`: the index of `arg` in `!for::arg`.
+    ArgIndex,
+    /// `!sep { ... }`: tokens written after every `arg` but the last.
+    Separator(Vec<BodyNode>),
 }
 
 impl BodyNode {
@@ -10,10 +25,10 @@ impl BodyNode {
         matches!(self, BodyNode::Param(_))
     }
 
-    pub fn content(&self) -> Option<&String> {
+    pub fn token(&self) -> Option<&Token<'static>> {
         match self {
-            BodyNode::Lexical(content) | BodyNode::Param(content) => Some(content),
-            BodyNode::ArgIteration(_) => None,
+            BodyNode::Lexical(token) => Some(token),
+            _ => None,
         }
     }
 }
@@ -22,12 +37,29 @@ impl BodyNode {
 pub struct Macro {
     pub name: String,
     pub body: Vec<BodyNode>,
-    pub params: Vec<String>
+    pub params: Vec<String>,
+    /// Whether the macro takes arguments in parentheses, even none.
+    pub function_like: bool,
+    /// The `!macro` directive, if the macro came from source.
+    pub defined: Option<Origin>
 }
 
 impl Macro {
     pub fn new(name: String, params: Vec<String>, body: Vec<BodyNode>) -> Self {
-        Self { name, params, body }
+        let function_like = !params.is_empty();
+        Self { name, params, body, function_like, defined: None }
+    }
+
+    /// Macro without parameters standing for `value`, as `-DNAME=value` defines.
+    pub fn define(name: String, value: &str) -> Self {
+        let mut lexer = Lexer::new(value, true);
+        // Text the lexer rejects is kept as error tokens, for the parser to report
+        let _ = lexer.lex();
+        let body = lexer.tokens.into_iter()
+            .filter(|token| token.token_type != TokenType::WS || !token.literal.contains('\n'))
+            .map(|token| BodyNode::Lexical(token.into_owned()))
+            .collect();
+        Self::new(name, vec![], body)
     }
 
     pub fn uses_param(&self, param: &str) -> bool {
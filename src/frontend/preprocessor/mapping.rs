@@ -0,0 +1,96 @@
+use std::ops::Range;
+
+use crate::frontend::source::{FileId, SourceMap};
+use crate::frontend::token;
+
+/// A span of some input file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub file: FileId,
+    pub position: token::Position
+}
+
+impl Origin {
+    pub fn new(file: FileId, position: token::Position) -> Origin {
+        Origin { file, position }
+    }
+
+    pub fn of(token: &token::Token) -> Origin {
+        Origin::new(token.src, token.position.clone())
+    }
+}
+
+/// One macro being expanded: where it was defined and where it was called.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    pub name: String,
+    /// `None` for macros added without a definition, e.g. from the command line.
+    pub defined: Option<Origin>,
+    pub called: Origin
+}
+
+impl Expansion {
+    /// Note for diagnostics, e.g. "in expansion of macro `FT` defined at a.sz:3:8".
+    pub fn describe(&self, sources: &SourceMap) -> String {
+        match &self.defined {
+            Some(defined) => format!("in expansion of macro `{}` defined at {}", self.name, sources.location(defined.file, defined.position.2.start)),
+            None => format!("in expansion of macro `{}`", self.name),
+        }
+    }
+}
+
+/// A run of output tokens expanded at one call.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// Indices of the tokens in the output, whitespace left out.
+    pub output: Range<usize>,
+    /// The outermost macro call, in the input.
+    pub origin: Origin,
+    /// Macros being expanded, outermost first.
+    pub expansions: Vec<Expansion>
+}
+
+/// Records which macros each output token was expanded from. Tokens copied
+/// from the input have no entry.
+#[derive(Debug, Clone, Default)]
+pub struct ExpansionMap {
+    segments: Vec<Segment>
+}
+
+impl ExpansionMap {
+    pub fn new() -> ExpansionMap {
+        ExpansionMap { segments: Vec::new() }
+    }
+
+    /// Records that the tokens `output` were expanded at `origin` from `expansions`.
+    pub fn record(&mut self, output: Range<usize>, origin: Origin, expansions: &[Expansion]) {
+        if output.is_empty() {
+            return;
+        }
+        // Tokens of one expansion are kept as a single segment
+        if let Some(last) = self.segments.last_mut()
+            && last.output.end == output.start && last.origin == origin && last.expansions == expansions {
+            last.output.end = output.end;
+            return;
+        }
+        self.segments.push(Segment { output, origin, expansions: expansions.to_vec() });
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Segment holding output token `index`.
+    pub fn segment(&self, index: usize) -> Option<&Segment> {
+        let at = self.segments.partition_point(|segment| segment.output.end <= index);
+        self.segments.get(at).filter(|segment| segment.output.contains(&index))
+    }
+
+    /// Macros expanded at the call `position` of `file`, outermost first.
+    /// Expanded tokens all point at that call.
+    pub fn expansions_at(&self, file: FileId, position: &token::Position) -> &[Expansion] {
+        self.segments.iter()
+            .find(|segment| segment.origin.file == file && segment.origin.position == *position)
+            .map_or(&[], |segment| &segment.expansions)
+    }
+}
@@ -1,29 +1,106 @@
+use std::path::PathBuf;
+
+use condition::Evaluator;
+use include::Includes;
 use macros::BodyNode;
+use mapping::{Expansion, ExpansionMap, Origin};
 
+use crate::frontend::lexer::Lexer;
+use crate::frontend::source::SourceFile;
 use crate::frontend::token;
+use crate::reporter::prep::PreprocessorError;
 
+pub mod builtins;
+pub mod condition;
+pub mod include;
 pub mod macros;
+pub mod mapping;
+
+struct BufferStream<'src> {
+    src: Vec<token::Token<'src>>,
+    index: usize,
+}
 
-struct BufferStream {
-    src: Vec<token::Token>,
+/// Tokens a macro expanded to, or an argument being expanded, read before
+/// the input goes on.
+struct Frame<'src> {
+    tokens: Vec<token::Token<'src>>,
     index: usize,
+    /// Whether the frame is a macro expansion, with an entry in `expansions`.
+    expands: bool
+}
+
+/// A macro call being substituted.
+struct Call<'src> {
+    /// The macro name, where it was called.
+    name: token::Token<'src>,
+    macro_: macros::Macro,
+    args: Vec<Vec<token::Token<'src>>>,
+    /// Arguments expanded so far, by index.
+    expanded: Vec<Option<Vec<token::Token<'src>>>>
+}
+
+/// Directives of conditional compilation.
+const CONDITIONALS: [&str; 7] = ["if", "ifdef", "ifndef", "elif", "else", "endif", "undef"];
+
+/// An `!if`, `!ifdef` or `!ifndef` whose `!endif` is still to come.
+struct Conditional<'src> {
+    /// The `!` it starts with.
+    start: token::Token<'src>,
+    directive: String,
+    /// Whether the code around it is kept.
+    outer: bool,
+    /// Whether one of its branches was kept already.
+    taken: bool,
+    /// Whether the current branch is kept.
+    keep: bool,
+    seen_else: bool
 }
 
-pub struct Preprocessor {
-    src: BufferStream,
-    plain: String,
-    macros: Vec<macros::Macro>
+pub struct Preprocessor<'src> {
+    src: BufferStream<'src>,
+    out: Vec<token::Token<'src>>,
+    /// Tokens other than whitespace written to `out`.
+    emitted: usize,
+    macros: Vec<macros::Macro>,
+    mapping: ExpansionMap,
+    /// Expansions being rescanned, innermost last.
+    frames: Vec<Frame<'src>>,
+    /// Macros being expanded, outermost first. These are not expanded again.
+    expansions: Vec<Expansion>,
+    /// Whether an argument is being expanded on its own, into a scratch `out`.
+    isolated: bool,
+    /// Uses of `__COUNTER__` so far.
+    counter: usize,
+    /// `__DATE__`, once it is used.
+    date: Option<String>,
+    includes: Includes,
+    /// Files being included, outermost first, each with the index its tokens end at.
+    active: Vec<(PathBuf, usize)>,
+    conditionals: Vec<Conditional<'src>>,
+    errors: Vec<PreprocessorError>
 }
 
-impl Preprocessor {
-    pub fn new(src: Vec<token::Token>) -> Preprocessor {
+impl<'src> Preprocessor<'src> {
+    pub fn new(src: Vec<token::Token<'src>>) -> Preprocessor<'src> {
         Preprocessor {
             src: BufferStream {
-                src: src,
+                src,
                 index: 0,
             },
-            plain: "".to_string(),
-            macros: vec![]
+            out: vec![],
+            emitted: 0,
+            macros: vec![],
+            mapping: ExpansionMap::new(),
+            frames: vec![],
+            expansions: vec![],
+            isolated: false,
+            counter: 0,
+            date: None,
+            includes: Includes::default(),
+            active: vec![],
+            conditionals: vec![],
+            errors: vec![]
         }
     }
 
@@ -31,338 +108,794 @@ impl Preprocessor {
         self.src.index += n;
     }
 
-    fn expect(&mut self, token_type: token::TokenType) -> token::Token {
+    fn expect(&mut self, token_type: token::TokenType) -> Result<token::Token<'src>, PreprocessorError> {
         self.skip_ws();
-        let token= self.current().clone();
-        self.advance(1);
+        let Some(token) = self.current().cloned() else {
+            let (file, line, column) = self.locate_end();
+            return Err(PreprocessorError::unexpected_token(format!("{:?}", token_type), "the end of the input".to_string(), file, line, column));
+        };
         if token.token_type != token_type {
-            panic!("Unexpected token: {:?}", token);
+            let (file, line, column) = self.locate(&token);
+            return Err(PreprocessorError::unexpected_token(format!("{:?}", token_type), format!("`{}`", token.literal), file, line, column));
         }
-        token
+        self.advance(1);
+        Ok(token)
+    }
+
+    fn current(&self) -> Option<&token::Token<'src>> {
+        self.src.src.get(self.src.index)
     }
 
-    fn peek(&self, n: usize) -> &token::Token {
-        &self.src.src[self.src.index + n]
+    /// Whether the current token is of `token_type`.
+    fn at(&self, token_type: token::TokenType) -> bool {
+        self.current().is_some_and(|token| token.token_type == token_type)
     }
 
-    fn current(&self) -> &token::Token {
-        &self.src.src[self.src.index]
+    /// Whether the current token ends a line, or there is none.
+    fn at_line_end(&self) -> bool {
+        self.current().is_none_or(|token| token.token_type == token::TokenType::WS && token.literal.contains('\n'))
     }
 
     fn skip_ws(&mut self) {
-        while {
-            let current_token = self.current().clone();
-            current_token.token_type == token::TokenType::WS
-        } {
-            let current_token = self.current().clone();
-            for c in current_token.literal.chars() {
-                if c == '\n' {
-                    self.plain.push('\n');
-                }
-            }
+        while let Some(token) = self.current().filter(|token| token.token_type == token::TokenType::WS).cloned() {
+            self.emit_newlines(&token);
+            self.advance(1);
+        }
+    }
+
+    /// Skips the rest of the line, after a directive that could not be read.
+    fn skip_line(&mut self) {
+        while !self.at_line_end() {
             self.advance(1);
         }
     }
 
     pub fn find_macro(&mut self, name: String) -> Option<&macros::Macro> {
-        for macro_ in self.macros.iter() {
-            if macro_.name == name {
-                return Some(macro_);
+        self.macros.iter().find(|macro_| macro_.name == name)
+    }
+
+    /// Writes `token` to the output. Tokens macros expanded to are placed at
+    /// the outermost call, the expansions being recorded in the mapping.
+    fn emit(&mut self, mut token: token::Token<'src>) {
+        if self.isolated {
+            self.out.push(token);
+            return;
+        }
+        if let Some(outermost) = self.expansions.first() {
+            token.position = outermost.called.position.clone();
+            token.src = outermost.called.file;
+        }
+        if token.token_type != token::TokenType::WS {
+            if !self.expansions.is_empty() {
+                let index = self.emitted;
+                self.mapping.record(index..index + 1, Origin::of(&token), &self.expansions);
             }
+            self.emitted += 1;
         }
-        None
+        self.out.push(token);
     }
-    
-    fn expand_macro(&mut self, macro_: &macros::Macro, args: Vec<String>) {
-        let params_map: Vec<(String, String)> = macro_.params.iter().zip(args.iter()).map(|(a, b)| (a.clone(), b.clone())).collect();
-        for node in macro_.body.iter() {
-            match node {
-                BodyNode::Lexical(content) => {
-                    self.expand_identstr(content.clone());
-                }
-                BodyNode::Param(param) => {
-                    if let Some((_, value)) = params_map.iter().find(|(a, _)| a == param) {
-                        self.expand_identstr(value.clone())
-                    }
+
+    /// Writes the line breaks of the whitespace `token`, so that skipped
+    /// lines still take up room in the output.
+    fn emit_newlines(&mut self, token: &token::Token<'src>) {
+        for _ in token.literal.matches('\n') {
+            self.emit(token::Token::new(token::TokenType::WS, "\n", token.position.clone(), token.src));
+        }
+    }
+
+    fn pop_frame(&mut self) {
+        if let Some(frame) = self.frames.pop()
+            && frame.expands {
+            self.expansions.pop();
+        }
+    }
+
+    /// Drops the expansions whose tokens were all read.
+    fn pop_finished_frames(&mut self) {
+        while self.frames.last().is_some_and(|frame| frame.index >= frame.tokens.len()) {
+            self.pop_frame();
+        }
+    }
+
+    /// Next token to expand, from the innermost expansion or else from the
+    /// input. The first `floor` frames are not left, so reading stops at the
+    /// end of the frame an argument is expanded in.
+    fn next_token(&mut self, floor: usize) -> Option<token::Token<'src>> {
+        loop {
+            let depth = self.frames.len();
+            match self.frames.last_mut() {
+                Some(frame) if frame.index < frame.tokens.len() => {
+                    frame.index += 1;
+                    return Some(frame.tokens[frame.index - 1].clone());
                 }
-                BodyNode::ArgIteration(body) => {
-                    for arg in args.iter() {
-                        let mut macro_clone = macro_.clone();
-                        macro_clone.params.push("arg".to_string());
-                        self.expand_macro(
-                            &macros::Macro::new(
-                                macro_clone.name.clone(),
-                                macro_clone.params.clone(),
-                                body.clone()
-                            ),
-                            vec![arg.clone()]
-                        );
-                    }
+                Some(_) if depth > floor => self.pop_frame(),
+                Some(_) => return None,
+                None if self.src.index < self.src.src.len() => {
+                    self.advance(1);
+                    return Some(self.src.src[self.src.index - 1].clone());
                 }
+                None => return None,
             }
         }
     }
 
-    fn process_macro_find(&mut self, macro_: &macros::Macro) {
-        if self.current().token_type == token::TokenType::LPAREN {
-            self.expect(token::TokenType::LPAREN);
-            let mut paren_count = 1;
-            let mut args: Vec<String> = vec![];
-            let mut latest_arg = String::new();
-    
-            while paren_count > 0 && self.src.index < self.src.src.len() {
-                let tk = self.current();
-                match tk.token_type {
-                    token::TokenType::LPAREN => {
-                        paren_count += 1;
-                        latest_arg.push('(');
-                        self.advance(1);
-                    }
-                    token::TokenType::RPAREN => {
-                        paren_count -= 1;
-                        if paren_count == 0 {
-                            break;
-                        }
-                        latest_arg.push(')');
-                        self.advance(1);
-                    }
-                    token::TokenType::COMMA => {
-                        if paren_count == 1 {
-                            args.push(latest_arg.clone());
-                            latest_arg.clear();
-                            self.expect(token::TokenType::COMMA);
-                        } else {
-                            latest_arg.push(',');
-                            self.advance(1);
-                        }
-                    }
-                    _ => {
-                        latest_arg.push_str(&tk.literal);
-                        self.advance(1);
-                    }
+    /// Whether the next token other than whitespace opens a call.
+    fn at_call(&self, floor: usize) -> bool {
+        let opens = |tokens: &[token::Token]| tokens.iter()
+            .find(|t| t.token_type != token::TokenType::WS)
+            .map(|t| t.token_type == token::TokenType::LPAREN);
+        for (depth, frame) in self.frames.iter().enumerate().rev() {
+            if let Some(opens) = opens(&frame.tokens[frame.index..]) {
+                return opens;
+            }
+            if depth < floor {
+                return false;
+            }
+        }
+        opens(&self.src.src[self.src.index..]).unwrap_or(false)
+    }
+
+    /// Arguments of a call to `name`, whose `(` comes next, without the
+    /// whitespace around them.
+    fn collect_args(&mut self, name: &token::Token<'src>, floor: usize) -> Option<Vec<Vec<token::Token<'src>>>> {
+        while self.next_token(floor)?.token_type != token::TokenType::LPAREN {}
+        let mut args = vec![vec![]];
+        let mut depth = 0;
+        loop {
+            let Some(token) = self.next_token(floor) else {
+                let (file, line, column) = self.locate_call(name);
+                self.errors.push(PreprocessorError::unterminated_macro_call(name.literal.to_string(), file, line, column));
+                return None;
+            };
+            match token.token_type {
+                token::TokenType::LPAREN => depth += 1,
+                token::TokenType::RPAREN if depth == 0 => break,
+                token::TokenType::RPAREN => depth -= 1,
+                token::TokenType::COMMA if depth == 0 => {
+                    args.push(vec![]);
+                    continue;
                 }
+                _ => {}
             }
-    
-            if !latest_arg.is_empty() {
-                args.push(latest_arg);
+            if let Some(arg) = args.last_mut() {
+                arg.push(token);
             }
-    
-            self.expect(token::TokenType::RPAREN);
-            self.expand_macro(macro_, args);
-        } else {
-            self.expand_macro(macro_, vec![]);
         }
+
+        for arg in args.iter_mut() {
+            let start = arg.iter().position(|t| t.token_type != token::TokenType::WS).unwrap_or(arg.len());
+            let end = arg.iter().rposition(|t| t.token_type != token::TokenType::WS).map_or(start, |i| i + 1);
+            arg.truncate(end);
+            arg.drain(..start);
+        }
+        // `()` passes no argument rather than an empty one
+        if let [arg] = args.as_slice() && arg.is_empty() {
+            args.clear();
+        }
+        Some(args)
     }
 
-    fn find_str_param_macro(&mut self, macro_: &macros::Macro, s: String) {
-        if !s.contains('(') {
-            return;
+    /// File name, line and column errors about `token` point at: the
+    /// outermost call it was expanded in, if any, as that is in the input.
+    fn locate_call(&self, token: &token::Token) -> (String, usize, usize) {
+        let origin = self.expansions.first().map_or_else(|| Origin::of(token), |outermost| outermost.called.clone());
+        (self.includes.name(origin.file), origin.position.0.start + 1, origin.position.1.start + 1)
+    }
+
+    /// Expands `tokens` on their own, as a macro argument is before it is substituted.
+    fn expand_isolated(&mut self, tokens: Vec<token::Token<'src>>) -> Vec<token::Token<'src>> {
+        let out = std::mem::take(&mut self.out);
+        let isolated = std::mem::replace(&mut self.isolated, true);
+        self.frames.push(Frame { tokens, index: 0, expands: false });
+        let floor = self.frames.len();
+        while let Some(token) = self.next_token(floor) {
+            self.expand_token(token, floor);
         }
-        let Some(start_index) = s.find('(') else {
-            return;
-        };
-        let mut paren_count = 0;
-        let mut latest_arg = String::new();
-        let mut args = Vec::new();
-        for c in s[start_index..].chars() {
-            match c {
-                '(' => {
-                    if paren_count > 0 {
-                        latest_arg.push('(');
+        self.frames.pop();
+        self.isolated = isolated;
+        std::mem::replace(&mut self.out, out)
+    }
+
+    /// Body of the macro of `call` with its arguments substituted for its
+    /// parameters. `arg` is the argument `!for::arg` is at, if any. Unless
+    /// `raw`, arguments are expanded once, when first used.
+    fn substitute(&mut self, call: &mut Call<'src>, body: &[BodyNode], arg: Option<usize>, raw: bool, out: &mut Vec<token::Token<'src>>) {
+        let (position, src) = (call.name.position.clone(), call.name.src);
+        let integer = |value: usize| token::Token::new(token::TokenType::INTEGER, value.to_string(), position.clone(), src);
+        for node in body {
+            match node {
+                BodyNode::Lexical(token) => out.push(token.clone()),
+                BodyNode::Param(param) => {
+                    let index = match arg {
+                        Some(index) if param == "arg" => Some(index),
+                        _ => call.macro_.params.iter().position(|p| p == param),
+                    };
+                    let Some(index) = index.filter(|index| *index < call.args.len()) else {
+                        continue;
+                    };
+                    if raw {
+                        out.extend(call.args[index].iter().cloned());
+                        continue;
                     }
-                    paren_count += 1;
+                    if call.expanded[index].is_none() {
+                        call.expanded[index] = Some(self.expand_isolated(call.args[index].clone()));
+                    }
+                    out.extend(call.expanded[index].iter().flatten().cloned());
                 }
-                ')' => {
-                    paren_count -= 1;
-                    if paren_count == 0 {
-                        if !latest_arg.is_empty() {
-                            args.push(latest_arg);
-                        }
-                        break;
-                    } else {
-                        latest_arg.push(')');
+                BodyNode::ArgIteration(body) => {
+                    for index in 0..call.args.len() {
+                        self.substitute(call, body, Some(index), raw, out);
                     }
                 }
-                ',' => {
-                    if paren_count == 1 {
-                        args.push(latest_arg.clone());
-                        latest_arg.clear();
-                    } else {
-                        latest_arg.push(',');
+                BodyNode::Stringify(body) => {
+                    let mut tokens = vec![];
+                    self.substitute(call, body, arg, true, &mut tokens);
+                    out.push(token::Token::new(token::TokenType::STRING, Self::stringify(&tokens), position.clone(), src));
+                }
+                BodyNode::Paste(body) => {
+                    let mut tokens = vec![];
+                    self.substitute(call, body, arg, true, &mut tokens);
+                    if let Some(token) = self.paste(&call.name, &tokens) {
+                        out.push(token);
                     }
                 }
-                _ => {
-                    latest_arg.push(c);
+                BodyNode::ArgCount => out.push(integer(call.args.len())),
+                BodyNode::ArgIndex => out.push(integer(arg.unwrap_or(0))),
+                BodyNode::Separator(body) => {
+                    if arg.is_some_and(|index| index + 1 < call.args.len()) {
+                        self.substitute(call, body, arg, raw, out);
+                    }
                 }
             }
         }
-        self.expand_macro(macro_, args);
     }
 
-    fn expand_identstr(&mut self, str: String) {
-        let mut name = str.clone();
-        let mut prm: bool =false;
-        if let Some(index) = name.find('(') {
-            name = name[..index].to_string();
-            if name.starts_with(' ') {
-                name = name[1..].to_string();
+    /// Spelling of `tokens`, with any whitespace between them as one space.
+    fn stringify(tokens: &[token::Token]) -> String {
+        let mut text = String::new();
+        for token in tokens {
+            if token.token_type != token::TokenType::WS {
+                text.push_str(&token.spelling());
+            } else if !text.is_empty() && !text.ends_with(' ') {
+                text.push(' ');
             }
-            prm = true;
         }
+        text.trim_end().to_string()
+    }
 
-        let macro_exists = self.macros.iter()
-            .any(|m| m.name == name);
-        if macro_exists {
-            if let Some(index) = self.macros.iter().position(|m| m.name == name) {
-                let macro_ = self.macros[index].clone();
-                if prm {
-                    self.find_str_param_macro(&macro_, str);
-                    return;
+    /// The single token `tokens` spell when joined, leaving out whitespace
+    /// and commas, for `!paste` in a call to `name`.
+    fn paste(&mut self, name: &token::Token<'src>, tokens: &[token::Token]) -> Option<token::Token<'src>> {
+        let text: String = tokens.iter()
+            .filter(|t| !matches!(t.token_type, token::TokenType::WS | token::TokenType::COMMA))
+            .map(token::Token::spelling)
+            .collect();
+        let mut lexer = Lexer::new(&text, false);
+        if lexer.lex().is_ok() && let [token] = lexer.tokens.as_slice() {
+            return Some(token::Token { position: name.position.clone(), src: name.src, ..token.clone().into_owned() });
+        }
+        let (file, line, column) = self.locate_call(name);
+        self.errors.push(PreprocessorError::invalid_paste(text, file, line, column));
+        None
+    }
+
+    /// Value of the builtin `token` names, if it names one.
+    fn builtin(&mut self, token: &token::Token<'src>) -> Option<token::Token<'src>> {
+        let (file, line, _) = self.locate_call(token);
+        let (token_type, literal) = match &*token.literal {
+            "__FILE__" => (token::TokenType::STRING, file),
+            "__LINE__" => (token::TokenType::INTEGER, line.to_string()),
+            "__COUNTER__" => {
+                self.counter += 1;
+                (token::TokenType::INTEGER, (self.counter - 1).to_string())
+            }
+            "__DATE__" => (token::TokenType::STRING, self.date.get_or_insert_with(builtins::today).clone()),
+            _ => return None,
+        };
+        Some(token::Token::new(token_type, literal, token.position.clone(), token.src))
+    }
+
+    /// Expands `token` if it names a macro, or else writes it out. Expansions
+    /// are pushed as frames, to be rescanned along with what follows them.
+    fn expand_token(&mut self, token: token::Token<'src>, floor: usize) {
+        let macro_ = match token.token_type {
+            token::TokenType::IDENTIFIER => self.macros.iter().find(|m| m.name == token.literal).cloned(),
+            _ => None,
+        };
+        let Some(macro_) = macro_ else {
+            let builtin = match token.token_type {
+                token::TokenType::IDENTIFIER => self.builtin(&token),
+                _ => None,
+            };
+            return self.emit(builtin.unwrap_or(token));
+        };
+
+        if self.expansions.iter().any(|expansion| expansion.name == macro_.name) {
+            // A macro is not expanded inside itself, which would never end
+            if !macro_.function_like || self.at_call(floor) {
+                let (file, line, column) = self.locate_call(&token);
+                self.errors.push(PreprocessorError::recursive_macro(macro_.name.clone(), file, line, column));
+            }
+            return self.emit(token);
+        }
+
+        let args = match macro_.function_like {
+            true if self.at_call(floor) => match self.collect_args(&token, floor) {
+                Some(args) => args,
+                None => return,
+            },
+            // A function-like macro without arguments is just a name
+            true => return self.emit(token),
+            false => vec![],
+        };
+        let iterates = macro_.body.iter().any(|node| matches!(node, BodyNode::ArgIteration(_)));
+        if args.len() < macro_.params.len() || (args.len() > macro_.params.len() && !iterates) {
+            let (file, line, column) = self.locate_call(&token);
+            self.errors.push(PreprocessorError::macro_arguments(macro_.name.clone(), macro_.params.len(), args.len(), file, line, column));
+            return;
+        }
+
+        let expansion = Expansion {
+            name: macro_.name.clone(),
+            defined: macro_.defined.clone(),
+            called: Origin::of(&token)
+        };
+        let body = macro_.body.clone();
+        let mut call = Call { name: token, macro_, expanded: vec![None; args.len()], args };
+        let mut tokens = vec![];
+        self.substitute(&mut call, &body, None, false, &mut tokens);
+        self.expansions.push(expansion);
+        self.frames.push(Frame { tokens, index: 0, expands: true });
+    }
+
+    /// Whether the directive `!name` starts at the current token.
+    fn at_directive(&self, name: &str) -> bool {
+        let tokens = &self.src.src[self.src.index..];
+        // Some directives, like `!if`, are spelled as keywords
+        matches!(tokens, [not, ident, ..] if not.token_type == token::TokenType::NOT
+            && !matches!(ident.token_type, token::TokenType::STRING | token::TokenType::CHAR) && ident.literal == name)
+    }
+
+    /// Whether the current token is the first one on its line, or of an
+    /// included file. Directives only start there, so `return !once;` stays
+    /// an expression.
+    fn at_line_start(&self) -> bool {
+        let Some(current) = self.current() else {
+            return false;
+        };
+        match self.src.index.checked_sub(1).map(|previous| &self.src.src[previous]) {
+            Some(previous) => previous.src != current.src
+                || previous.token_type == token::TokenType::WS && previous.literal.contains('\n'),
+            None => true,
+        }
+    }
+
+    /// File name, line and column of `token`, for errors.
+    fn locate(&self, token: &token::Token) -> (String, usize, usize) {
+        (self.includes.name(token.src), token.position.0.start + 1, token.position.1.start + 1)
+    }
+
+    /// File name, line and column of the end of the input, for errors.
+    fn locate_end(&self) -> (String, usize, usize) {
+        match self.src.src.last() {
+            Some(last) => (self.includes.name(last.src), last.position.0.end + 1, last.position.1.end + 2),
+            None => (self.includes.name(Default::default()), 1, 1),
+        }
+    }
+
+    /// Whether a directive that is not known starts at the current token: a
+    /// `!` first on its line, right before a name, and followed by the end of
+    /// the line or by an operand. `!a && b;` is an expression, `!a b` is not.
+    fn at_unknown_directive(&self) -> bool {
+        use token::TokenType::*;
+        let [not, name, rest @ ..] = &self.src.src[self.src.index..] else {
+            return false;
+        };
+        let next = rest.iter().find(|t| t.token_type != WS || t.literal.contains('\n'));
+        self.at_line_start() && not.token_type == NOT && name.token_type == IDENTIFIER
+            && not.src == name.src && not.position.2.end == name.position.2.start
+            && next.is_none_or(|next| matches!(next.token_type, WS | IDENTIFIER | STRING | CHAR | INTEGER | FLOAT))
+    }
+
+    fn process_include(&mut self) -> Result<(), PreprocessorError> {
+        let Some(directive) = self.current().cloned() else {
+            return Ok(());
+        };
+        self.advance(2);
+        self.skip_ws();
+        let name = match self.current() {
+            Some(name) if name.token_type == token::TokenType::STRING => name.literal.to_string(),
+            _ => {
+                let (file, line, column) = self.locate(&directive);
+                return Err(PreprocessorError::unexpected_directive("include".to_string(), "it takes a file name in quotes".to_string(), file, line, column));
+            }
+        };
+        self.advance(1);
+
+        let Some(path) = self.includes.resolve(&name, directive.src) else {
+            let (file, line, column) = self.locate(&directive);
+            return Err(PreprocessorError::include_not_found(name, file, line, column));
+        };
+        if self.includes.is_once(&path) {
+            return Ok(());
+        }
+        if let Some(at) = self.active.iter().position(|(active, _)| *active == path) {
+            let mut chain: Vec<String> = self.active[at..].iter().map(|(active, _)| active.display().to_string()).collect();
+            chain.push(path.display().to_string());
+            return Err(PreprocessorError::include_cycle(chain));
+        }
+
+        match self.includes.load(&path) {
+            Ok(tokens) => {
+                let tokens: Vec<token::Token<'src>> = tokens;
+                let (index, count) = (self.src.index, tokens.len());
+                self.src.src.splice(index..index, tokens);
+                for (_, end) in self.active.iter_mut() {
+                    *end += count;
                 }
-                self.process_macro_find(&macro_);
-                return;
+                self.active.push((path, index + count));
             }
+            Err(errors) => self.errors.extend(errors),
+        }
+        Ok(())
+    }
+
+    fn process_once(&mut self) {
+        if let Some(token) = self.current() {
+            self.includes.mark_once(token.src);
         }
-        
-        self.plain.push_str(&str);
+        self.advance(2);
     }
 
-    fn process_ident(&mut self) {
-        let token_literal = self.expect(token::TokenType::IDENTIFIER).literal;
-        self.expand_identstr(token_literal);
+    /// Whether code is kept at this point, rather than skipped by a conditional.
+    fn keeping(&self) -> bool {
+        self.conditionals.last().is_none_or(|conditional| conditional.keep)
+    }
+
+    /// Tokens after the current directive, up to the end of its line.
+    fn directive_line(&mut self) -> Vec<token::Token<'src>> {
+        self.advance(2);
+        let mut line = vec![];
+        while !self.at_line_end() && let Some(token) = self.current() {
+            line.push(token.clone());
+            self.advance(1);
+        }
+        line
+    }
+
+    /// The macro name a directive `line` consists of.
+    fn macro_name<'l>(line: &'l [token::Token]) -> Option<&'l str> {
+        let mut tokens = line.iter().filter(|t| t.token_type != token::TokenType::WS);
+        match (tokens.next(), tokens.next()) {
+            (Some(name), None) if name.token_type == token::TokenType::IDENTIFIER => Some(&name.literal),
+            _ => None,
+        }
+    }
+
+    /// Whether the condition `line` of the directive at `start` holds.
+    fn condition(&self, start: &token::Token, directive: &str, line: &[token::Token]) -> Result<bool, PreprocessorError> {
+        let value = match directive {
+            "ifdef" | "ifndef" => match Self::macro_name(line) {
+                Some(name) => Ok((self.macros.iter().any(|m| m.name == name) == (directive == "ifdef")) as i64),
+                None => Err(format!("`!{}` takes a macro name", directive)),
+            },
+            _ => Evaluator::new(&self.macros).evaluate(line),
+        };
+        value.map(|value| value != 0).map_err(|reason| {
+            let (file, line, column) = self.locate(start);
+            PreprocessorError::invalid_condition(reason, file, line, column)
+        })
     }
-    
 
-    fn process_bnode(&mut self, mut params: Vec<String>) -> BodyNode {
-        let cur = self.current().clone();
-        match cur.token_type {
-            token::TokenType::IDENTIFIER => {
-                if params.contains(&self.current().literal) {
-                    BodyNode::Param(self.expect(token::TokenType::IDENTIFIER).literal)
+    fn process_conditional(&mut self, directive: &str) {
+        let Some(start) = self.current().cloned() else {
+            return;
+        };
+        let line = self.directive_line();
+        let (file, row, column) = self.locate(&start);
+        let unexpected = |reason: &str| PreprocessorError::unexpected_directive(directive.to_string(), reason.to_string(), file.clone(), row, column);
+
+        match directive {
+            "if" | "ifdef" | "ifndef" => {
+                let outer = self.keeping();
+                // Conditions in skipped code are not evaluated
+                let keep = outer && self.condition(&start, directive, &line).unwrap_or_else(|e| {
+                    self.errors.push(e);
+                    false
+                });
+                self.conditionals.push(Conditional { start, directive: directive.to_string(), outer, taken: keep, keep, seen_else: false });
+            }
+            "elif" => {
+                let Some(top) = self.conditionals.last() else {
+                    return self.errors.push(unexpected("there is no `!if` to continue"));
+                };
+                if top.seen_else {
+                    return self.errors.push(unexpected("it follows `!else`"));
                 }
-                else {
-                    BodyNode::Lexical(self.expect(token::TokenType::IDENTIFIER).literal)
+                let keep = top.outer && !top.taken && self.condition(&start, directive, &line).unwrap_or_else(|e| {
+                    self.errors.push(e);
+                    false
+                });
+                if let Some(top) = self.conditionals.last_mut() {
+                    top.taken |= keep;
+                    top.keep = keep;
                 }
             }
-            token::TokenType::NOT => {
-                let next = self.peek(1).literal.clone();
-                if next.contains(&"for::arg".to_string()) {
-                    self.expect(token::TokenType::NOT);
-                    self.expect(token::TokenType::IDENTIFIER);
-                    self.skip_ws();
-                    let mut braces = (self.current().token_type == token::TokenType::LBRACE) as usize;
-                    let mut body: Vec<macros::BodyNode> = vec![];
-                    self.expect(token::TokenType::LBRACE);
-                    params.push("arg".to_string());
-                    while braces > 0 {
-                        if self.current().token_type == token::TokenType::LBRACE {
-                            braces += 1;
-                        } else if self.current().token_type == token::TokenType::RBRACE {
-                            braces -= 1;
-                        }
-                        if braces == 0 {
-                            break;
-                        }
-                        body.push(self.process_bnode(params.clone()));
-                    }
-                    self.expect(token::TokenType::RBRACE);
-                    params.pop();
-                    BodyNode::ArgIteration(body)
+            "else" => match self.conditionals.last_mut() {
+                None => self.errors.push(unexpected("there is no `!if` to continue")),
+                Some(top) if top.seen_else => self.errors.push(unexpected("it follows `!else`")),
+                Some(top) => {
+                    top.keep = top.outer && !top.taken;
+                    top.taken = true;
+                    top.seen_else = true;
                 }
-                else {
-                    self.advance(1);
-                    BodyNode::Lexical(cur.literal.clone())
+            },
+            "endif" => {
+                if self.conditionals.pop().is_none() {
+                    self.errors.push(unexpected("there is no `!if` to end"));
                 }
             }
             _ => {
-                self.advance(1);
-                BodyNode::Lexical(cur.literal.clone())
+                if !self.keeping() {
+                    return;
+                }
+                match Self::macro_name(&line) {
+                    Some(name) => self.macros.retain(|m| m.name != name),
+                    None => self.errors.push(unexpected("it takes a macro name")),
+                }
             }
         }
     }
 
-    fn process_macrodef(&mut self) {
-        self.expect(token::TokenType::NOT);
-        self.expect(token::TokenType::IDENTIFIER);
-        let name = self.expect(token::TokenType::IDENTIFIER).literal;
-        let mut params: Vec<String> = vec![];
-        self.expect(token::TokenType::COLON);
-        self.skip_ws();
-        if self.current().token_type == token::TokenType::LPAREN {
-            self.expect(token::TokenType::LPAREN);
-            while self.current().token_type != token::TokenType::RPAREN && self.src.index < self.src.src.len() {
-                params.push(self.expect(token::TokenType::IDENTIFIER).literal);
-                if self.current().token_type == token::TokenType::COMMA {
-                    self.expect(token::TokenType::COMMA);
-                }
+    /// Whether `!for::arg` starts at the current token.
+    fn at_arg_iteration(&self) -> bool {
+        let tokens = &self.src.src[self.src.index..];
+        matches!(tokens, [_, f, c, a, ..] if f.token_type == token::TokenType::FOR
+            && c.token_type == token::TokenType::DOUBLE_COLON && a.literal == "arg")
+    }
+
+    /// Body nodes between `open` and the `close` matching it, which come next.
+    fn process_group(&mut self, params: &[String], open: token::TokenType, close: token::TokenType) -> Result<Vec<BodyNode>, PreprocessorError> {
+        self.expect(open)?;
+        let mut depth = 1;
+        let mut body = vec![];
+        while let Some(token_type) = self.current().map(|token| token.token_type) {
+            if token_type == open {
+                depth += 1;
+            } else if token_type == close {
+                depth -= 1;
+            }
+            if depth == 0 {
+                break;
             }
-            self.expect(token::TokenType::RPAREN);
+            body.push(self.process_bnode(params)?);
         }
-        let mut body: Vec<macros::BodyNode> = vec![];
+        self.expect(close)?;
+        Ok(body)
+    }
+
+    /// Body nodes in the parentheses of `!str` or `!paste`, the `!` of which is `start`.
+    fn process_arguments(&mut self, params: &[String], start: &token::Token) -> Result<Vec<BodyNode>, PreprocessorError> {
+        let directive = format!("`!{}`", self.src.src[self.src.index + 1].literal);
+        self.advance(2);
+        self.skip_ws();
+        if !self.at(token::TokenType::LPAREN) {
+            let (file, line, column) = self.locate(start);
+            return Err(PreprocessorError::missing_parenthesis("(".to_string(), directive, file, line, column));
+        }
+        self.process_group(params, token::TokenType::LPAREN, token::TokenType::RPAREN)
+    }
+
+    fn process_bnode(&mut self, params: &[String]) -> Result<BodyNode, PreprocessorError> {
+        let Some(cur) = self.current().cloned() else {
+            return self.expect(token::TokenType::RBRACE).map(|token| BodyNode::Lexical(token.into_owned()));
+        };
+        let iterating = params.last().is_some_and(|param| param == "arg");
+        let node = match cur.token_type {
+            token::TokenType::IDENTIFIER if params.iter().any(|param| *param == cur.literal) => {
+                self.advance(1);
+                BodyNode::Param(cur.literal.into_owned())
+            }
+            token::TokenType::NOT if self.at_arg_iteration() => {
+                self.advance(4);
+                let mut params = params.to_vec();
+                params.push("arg".to_string());
+                BodyNode::ArgIteration(self.process_group(&params, token::TokenType::LBRACE, token::TokenType::RBRACE)?)
+            }
+            token::TokenType::NOT if self.at_directive("str") => BodyNode::Stringify(self.process_arguments(params, &cur)?),
+            token::TokenType::NOT if self.at_directive("paste") => BodyNode::Paste(self.process_arguments(params, &cur)?),
+            token::TokenType::NOT if self.at_directive("count") => {
+                self.advance(2);
+                BodyNode::ArgCount
+            }
+            token::TokenType::NOT if iterating && self.at_directive("index") => {
+                self.advance(2);
+                BodyNode::ArgIndex
+            }
+            token::TokenType::NOT if iterating && self.at_directive("sep") => {
+                self.advance(2);
+                BodyNode::Separator(self.process_group(params, token::TokenType::LBRACE, token::TokenType::RBRACE)?)
+            }
+            _ => {
+                self.advance(1);
+                BodyNode::Lexical(cur.into_owned())
+            }
+        };
+        Ok(node)
+    }
 
+    fn process_macrodef(&mut self) {
+        match self.macro_definition() {
+            Ok(macro_) => self.add_macro(macro_),
+            Err(e) => {
+                self.errors.push(e);
+                self.skip_line();
+            }
+        }
+    }
 
+    /// Reads the `!macro` directive at the current token.
+    fn macro_definition(&mut self) -> Result<macros::Macro, PreprocessorError> {
+        let Some(start) = self.current().cloned() else {
+            return Err(PreprocessorError::Unknown);
+        };
+        self.advance(2);
+        let name = self.expect(token::TokenType::IDENTIFIER)?.literal.into_owned();
+        self.expect(token::TokenType::COLON)?;
         self.skip_ws();
-        let mut braces = (self.current().token_type == token::TokenType::LBRACE) as usize;
-        if self.current().token_type == token::TokenType::LBRACE {
-            self.expect(token::TokenType::LBRACE);
+
+        let mut params: Vec<String> = vec![];
+        let function_like = self.at(token::TokenType::LPAREN);
+        if function_like {
+            let open = self.expect(token::TokenType::LPAREN)?;
             self.skip_ws();
-            while braces > 0 {
-                if self.current().token_type == token::TokenType::LBRACE {
-                    braces += 1;
-                } else if self.current().token_type == token::TokenType::RBRACE {
-                    braces -= 1;
-                }
-                if braces <= 0 {
-                    self.advance(1);
+            while !self.at(token::TokenType::RPAREN) {
+                params.push(self.expect(token::TokenType::IDENTIFIER)?.literal.into_owned());
+                self.skip_ws();
+                if !self.at(token::TokenType::COMMA) {
                     break;
                 }
-                body.push(self.process_bnode(params.clone()));
+                self.advance(1);
             }
-            if body.len() > 0 {
-                if let Some(BodyNode::Lexical(content)) = body.last() {
-                    if content == "\n" {
-                        body.pop();
-                    }
-                }
+            if !self.at(token::TokenType::RPAREN) {
+                let (file, line, column) = self.locate(&open);
+                return Err(PreprocessorError::missing_parenthesis(")".to_string(), format!("the parameters of `{}`", name), file, line, column));
             }
-        } else {
-            body.push(self.process_bnode(params.clone()));
             self.advance(1);
         }
-        self.add_macro(macros::Macro::new(name, params, body));
-    }
 
-    pub fn process(&mut self) {
-        while self.src.index < self.src.src.len() {
-            let token = {
-                let t = self.current();
-                t
+        let mut body: Vec<macros::BodyNode> = vec![];
+        self.skip_ws();
+        if self.at(token::TokenType::LBRACE) {
+            body = match self.process_group(&params, token::TokenType::LBRACE, token::TokenType::RBRACE) {
+                Ok(body) => body,
+                Err(_) if self.current().is_none() => {
+                    let (file, line, column) = self.locate(&start);
+                    return Err(PreprocessorError::unterminated_macro(name, file, line, column));
+                }
+                Err(e) => return Err(e),
             };
-            let token_literal = token.literal.clone();
-            let token_type = token.token_type.clone();
-    
-            match token_type {
-                token::TokenType::IDENTIFIER => {
-                    self.process_ident();
+            let leading = body.iter().take_while(|node| matches!(node, BodyNode::Lexical(token) if token.token_type == token::TokenType::WS)).count();
+            body.drain(..leading);
+        } else {
+            // Without braces, the body is the rest of the line
+            while !self.at_line_end() {
+                body.push(self.process_bnode(&params)?);
+            }
+        }
+        while matches!(body.last(), Some(BodyNode::Lexical(last)) if last.token_type == token::TokenType::WS) {
+            body.pop();
+        }
+        Ok(macros::Macro { defined: Some(Origin::of(&start)), function_like, ..macros::Macro::new(name, params, body) })
+    }
+
+    /// Preprocesses the input, collecting every error found on the way.
+    pub fn process(&mut self) -> Result<(), Vec<PreprocessorError>> {
+        // The main file is active throughout, so including it again is a cycle
+        if let Some(path) = self.src.src.first().and_then(|token| self.includes.path(token.src)) {
+            self.active.push((path.to_path_buf(), self.src.src.len()));
+        }
+        loop {
+            self.pop_finished_frames();
+            // Directives only come from the input, outside of expansions
+            if self.frames.is_empty() {
+                if self.src.index >= self.src.src.len() {
+                    break;
                 }
-                token::TokenType::NOT => {
-                    if self.peek(1) == &token::Token::new(token::TokenType::IDENTIFIER, "macro".to_string(), (0..0, 0..0), 0) {
-                        self.process_macrodef();
-                    }
+                while self.active.last().is_some_and(|(_, end)| *end <= self.src.index) {
+                    self.active.pop();
                 }
-                _ => {
-                    self.plain.push_str(&token_literal);
+                let line_start = self.at_line_start();
+                if let Some(directive) = CONDITIONALS.iter().find(|directive| line_start && self.at_directive(directive)) {
+                    self.process_conditional(directive);
+                    continue;
+                }
+                if !self.keeping() {
+                    if let Some(token) = self.current().filter(|token| token.token_type == token::TokenType::WS).cloned() {
+                        self.emit_newlines(&token);
+                    }
                     self.advance(1);
+                    continue;
+                }
+                if line_start && self.at_directive("macro") {
+                    self.process_macrodef();
+                    continue;
+                } else if line_start && self.at_directive("include") {
+                    if let Err(e) = self.process_include() {
+                        self.errors.push(e);
+                        self.skip_line();
+                    }
+                    continue;
+                } else if line_start && self.at_directive("once") {
+                    self.process_once();
+                    continue;
+                } else if self.at_unknown_directive() {
+                    let directive = self.src.src[self.src.index + 1].literal.to_string();
+                    if let Some(start) = self.current() {
+                        let (file, line, column) = self.locate(start);
+                        self.errors.push(PreprocessorError::unknown_directive(directive, file, line, column));
+                    }
+                    self.skip_line();
+                    continue;
                 }
             }
+            let Some(token) = self.next_token(0) else {
+                break;
+            };
+            self.expand_token(token, 0);
+        }
+        for conditional in std::mem::take(&mut self.conditionals) {
+            let (file, line, column) = self.locate(&conditional.start);
+            self.errors.push(PreprocessorError::unterminated_conditional(conditional.directive, file, line, column));
+        }
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(std::mem::take(&mut self.errors)),
         }
     }
     
+    /// Where `!include` looks for files, and the ids loaded files get.
+    pub fn set_includes(&mut self, includes: Includes) {
+        self.includes = includes;
+    }
+
+    /// Defines `macro_`, replacing any macro of the same name.
     pub fn add_macro(&mut self, macro_: macros::Macro) {
+        self.macros.retain(|m| m.name != macro_.name);
         self.macros.push(macro_);
     }
 
+    /// The output as text. Tokens that were apart in the input, like those
+    /// of expansions, are spaced so that they lex the same again.
     pub fn get_processed(&self) -> String {
-        self.plain.clone()
+        let mut text = String::new();
+        let mut previous: Option<&token::Token> = None;
+        for token in self.out.iter() {
+            if let Some(previous) = previous
+                && previous.token_type != token::TokenType::WS && token.token_type != token::TokenType::WS
+                && (previous.src != token.src || previous.position.2.end != token.position.2.start) {
+                text.push(' ');
+            }
+            text.push_str(&token.spelling());
+            previous = Some(token);
+        }
+        text
+    }
+
+    /// The output for the parser, without whitespace. Leaves no output behind.
+    pub fn take_tokens(&mut self) -> Vec<token::Token<'src>> {
+        let mut tokens = std::mem::take(&mut self.out);
+        tokens.retain(|token| token.token_type != token::TokenType::WS);
+        tokens
+    }
+
+    /// Files read by `!include`, in id order, to add to the source map.
+    pub fn take_files(&mut self) -> Vec<SourceFile> {
+        self.includes.take_files()
+    }
+
+    /// Which macros each output token was expanded from. Leaves an empty map behind.
+    pub fn take_mapping(&mut self) -> ExpansionMap {
+        std::mem::take(&mut self.mapping)
     }
 }
@@ -1,5 +1,8 @@
+use std::borrow::Cow;
 use std::ops::Range;
 
+use crate::frontend::source::FileId;
+
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 #[allow(non_camel_case_types)]
 pub enum TokenType {
@@ -16,11 +19,20 @@ pub enum TokenType {
     ELSE,
     WHILE,
     RETURN,
+    BREAK,
+    CONTINUE,
+    FOR,
+    STRUCT,
+    EXTERN,
+    IMPORT,
+    TRUE,
+    FALSE,
 
     PLUS,
     MINUS,
     ASTERISK,
     SLASH,
+    PERCENT,
 
     EQ,
     NEQ,
@@ -32,6 +44,10 @@ pub enum TokenType {
     NOT,
     AND,
     OR,
+    CARET,
+    TILDE,
+    SHL,
+    SHR,
 
     LOGICAL_AND,
     LOGICAL_OR,
@@ -39,8 +55,19 @@ pub enum TokenType {
     ASSIGN,
     PLUS_ASSIGN,
     MINUS_ASSIGN,
+    ASTERISK_ASSIGN,
+    SLASH_ASSIGN,
+    PERCENT_ASSIGN,
+    AND_ASSIGN,
+    OR_ASSIGN,
+    CARET_ASSIGN,
+    SHL_ASSIGN,
+    SHR_ASSIGN,
 
     DOT,
+    /// `->`
+    THIN_ARROW,
+    DOUBLE_COLON,
 
     LPAREN,
     RPAREN,
@@ -53,40 +80,85 @@ pub enum TokenType {
     COLON,
     SEMICOLON,
 
+    /// `=>`
     ARROW,
 
+    /// `/// text`, with `text` as the literal.
+    DOC_COMMENT,
+
+    /// Text the lexer reported an error for.
+    ERROR,
+
     WS
 }
 
 use TokenType::*;
+/// Reserved words. `lexer::simd` classifies identifiers against these with a perfect hash.
+pub const KEYWORDS: [(&str, TokenType); 15] = [
+    ("fn", FN), ("let", LET), ("const", CONST), ("if", IF), ("else", ELSE),
+    ("while", WHILE), ("return", RETURN), ("break", BREAK), ("continue", CONTINUE),
+    ("for", FOR), ("struct", STRUCT), ("extern", EXTERN), ("import", IMPORT),
+    ("true", TRUE), ("false", FALSE),
+];
+
+/// Punctuation, longest first so that the lexer can take the first match.
+pub const PUNCTUATION: [(&str, TokenType); 44] = [
+    ("<<=", SHL_ASSIGN), (">>=", SHR_ASSIGN),
+
+    ("+=", PLUS_ASSIGN), ("-=", MINUS_ASSIGN), ("*=", ASTERISK_ASSIGN), ("/=", SLASH_ASSIGN),
+    ("%=", PERCENT_ASSIGN), ("&=", AND_ASSIGN), ("|=", OR_ASSIGN), ("^=", CARET_ASSIGN),
+    ("==", EQ), ("!=", NEQ), ("<=", LTE), (">=", GTE), ("<<", SHL), (">>", SHR),
+    ("&&", LOGICAL_AND), ("||", LOGICAL_OR), ("=>", ARROW), ("->", THIN_ARROW), ("::", DOUBLE_COLON),
+
+    ("+", PLUS), ("-", MINUS), ("*", ASTERISK), ("/", SLASH), ("%", PERCENT),
+    ("=", ASSIGN), ("!", NOT), ("<", LT), (">", GT),
+    ("&", AND), ("|", OR), ("^", CARET), ("~", TILDE),
+    ("(", LPAREN), (")", RPAREN), ("{", LBRACE), ("}", RBRACE), ("[", LBRACKET), ("]", RBRACKET),
+    (",", COMMA), (":", COLON), (";", SEMICOLON), (".", DOT),
+];
+
 pub const LOGICAL_OPERATORS:        [TokenType; 2] = [LOGICAL_AND, LOGICAL_OR];
-pub const ARITHMETIC_OPERATORS:     [TokenType; 4] = [PLUS, MINUS, ASTERISK, SLASH];
-pub const ASSIGN_OPERATORS:         [TokenType; 3] = [ASSIGN, PLUS_ASSIGN, MINUS_ASSIGN];
+pub const ARITHMETIC_OPERATORS:     [TokenType; 5] = [PLUS, MINUS, ASTERISK, SLASH, PERCENT];
+pub const BITWISE_OPERATORS:        [TokenType; 5] = [AND, OR, CARET, SHL, SHR];
+pub const ASSIGN_OPERATORS:         [TokenType; 11] = [
+    ASSIGN, PLUS_ASSIGN, MINUS_ASSIGN, ASTERISK_ASSIGN, SLASH_ASSIGN, PERCENT_ASSIGN,
+    AND_ASSIGN, OR_ASSIGN, CARET_ASSIGN, SHL_ASSIGN, SHR_ASSIGN
+];
 pub const COMPARISSON_OPERATORS:    [TokenType; 6] = [EQ, NEQ, GT, GTE, LT, LTE];
+pub const UNARY_OPERATORS:          [TokenType; 5] = [MINUS, NOT, TILDE, ASTERISK, AND];
 
 
 const BINARY_OPERATORS_LENGHT: usize = 
     LOGICAL_OPERATORS.len() + 
     ARITHMETIC_OPERATORS.len() + 
+    BITWISE_OPERATORS.len() + 
     ASSIGN_OPERATORS.len() + 
     COMPARISSON_OPERATORS.len();
 
 pub const BINARY_OPERATORS:         [TokenType; BINARY_OPERATORS_LENGHT] = [
     LOGICAL_AND, LOGICAL_OR,
-    PLUS, MINUS, ASTERISK, SLASH, 
-    ASSIGN, PLUS_ASSIGN, MINUS_ASSIGN, 
+    PLUS, MINUS, ASTERISK, SLASH, PERCENT,
+    AND, OR, CARET, SHL, SHR,
+    ASSIGN, PLUS_ASSIGN, MINUS_ASSIGN, ASTERISK_ASSIGN, SLASH_ASSIGN, PERCENT_ASSIGN,
+    AND_ASSIGN, OR_ASSIGN, CARET_ASSIGN, SHL_ASSIGN, SHR_ASSIGN,
     EQ, NEQ, GT, GTE, LT, LTE
 ];
 
+/// Lines, columns and byte offsets a token covers. Lines and columns are
+/// 0-based and inclusive, columns counting characters. Byte offsets are half-open.
+pub type Position = (Range<usize>, Range<usize>, Range<usize>);
+
+/// A token of the source it was lexed from. Most literals borrow from the source;
+/// only those the lexer rewrites, like escaped strings, own their text.
 #[derive(Eq, Clone)]
-pub struct Token {
+pub struct Token<'src> {
     pub token_type: TokenType,
-    pub literal: String,
-    pub position: (Range<usize>, Range<usize>),
-    pub src: usize
+    pub literal: Cow<'src, str>,
+    pub position: Position,
+    pub src: FileId
 }
 
-impl std::fmt::Debug for Token {
+impl std::fmt::Debug for Token<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f,
             "[{:?}]: {:?} at {:?}", self.token_type, self.literal, self.position
@@ -94,18 +166,61 @@ impl std::fmt::Debug for Token {
     }
 }
 
-impl Token {
-    pub fn new(token_type: TokenType, literal: String, position: (Range<usize>, Range<usize>), src: usize) -> Token {
+impl<'src> Token<'src> {
+    pub fn new(token_type: TokenType, literal: impl Into<Cow<'src, str>>, position: Position, src: FileId) -> Token<'src> {
         Token {
-            token_type: token_type,
-            literal: literal,
-            position: position,
-            src: src
+            token_type,
+            literal: literal.into(),
+            position,
+            src
+        }
+    }
+
+    /// The token with its literal copied, so it outlives the source.
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            token_type: self.token_type,
+            literal: Cow::Owned(self.literal.into_owned()),
+            position: self.position,
+            src: self.src
+        }
+    }
+
+    /// The token as it would be written in source. Literals are re-quoted.
+    pub fn spelling(&self) -> String {
+        match self.token_type {
+            STRING => escape(&self.literal, '"'),
+            CHAR => escape(&self.literal, '\''),
+            DOC_COMMENT => format!("/// {}", self.literal),
+            _ => self.literal.to_string(),
+        }
+    }
+}
+
+/// Quotes `literal` with `quote`, escaping whatever the lexer would decode.
+pub fn escape(literal: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(literal.len() + 2);
+    escaped.push(quote);
+    for c in literal.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
         }
     }
+    escaped.push(quote);
+    escaped
 }
 
-impl PartialEq for Token {
+impl PartialEq for Token<'_> {
     fn eq(&self, other: &Self) -> bool {
         self.token_type == other.token_type && self.literal == other.literal
     }
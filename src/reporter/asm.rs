@@ -7,6 +7,11 @@ pub enum AssemblerError {
         symbol: String,
     },
 
+    #[error("🌋 Cannot generate code for {what}")]
+    Unsupported {
+        what: String,
+    },
+
     #[error("💥 Unknown error occurred.")]
     Unknown,
 }
@@ -17,6 +17,53 @@ pub enum CompilerError {
         name: String,
     },
 
+    #[error("🔍 Variable `{name}` not found")]
+    VariableNotFound {
+        name: String,
+    },
+
+    #[error("⚖ Type mismatch: expected `{expected}`, found `{found}`")]
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+
+    #[error("⚠ Unsupported operator `{op}`")]
+    UnsupportedOperator {
+        op: String,
+    },
+
+    #[error("⚠ Unexpected {node} outside of a function")]
+    UnexpectedNode {
+        node: String,
+    },
+
+    #[error("🔒 Cannot assign twice to constant `{name}`")]
+    AssignToConst {
+        name: String,
+    },
+
+    #[error("❓ Cannot infer the type of `{name}`")]
+    UntypedVariable {
+        name: String,
+    },
+
+    #[error("🔒 Constant `{name}` needs a value")]
+    UninitializedConst {
+        name: String,
+    },
+
+    #[error("🔁 `{keyword}` outside of a loop")]
+    OutsideLoop {
+        keyword: String,
+    },
+
+    #[error("🔢 Literal `{literal}` does not fit in `{v_type}`")]
+    LiteralOutOfRange {
+        literal: String,
+        v_type: String,
+    },
+
     #[error("💥 Unknown error occurred.")]
     Unknown,
 }
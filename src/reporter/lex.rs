@@ -2,20 +2,112 @@ use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum LexerError {
-    #[error("⚠ Invalid character `{character}` at line {line}, column {column}.\n 📖 {text}")]
+    #[error("⚠ Invalid character `{character}` at {file}:{line}:{column}.\n 📖 {text}")]
     InvalidCharacter {
         character: char,
+        file: String,
         line: usize,
         column: usize,
         text: String,
     },
 
+    #[error("🧵 Unterminated {kind} literal starting at {file}:{line}:{column}.\n 📖 {text}")]
+    UnterminatedLiteral {
+        kind: String,
+        file: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+
+    #[error("💬 Unterminated block comment starting at {file}:{line}:{column}.\n 📖 {text}")]
+    UnterminatedComment {
+        file: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+
+    #[error("⚠ Invalid escape sequence `{sequence}` at {file}:{line}:{column}.\n 📖 {text}")]
+    InvalidEscape {
+        sequence: String,
+        file: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+
+    #[error("⚠ Character literal `{literal}` must hold exactly one character, at {file}:{line}:{column}.\n 📖 {text}")]
+    InvalidCharLiteral {
+        literal: String,
+        file: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+
+    #[error("🔢 Invalid number `{literal}`: {reason}, at {file}:{line}:{column}.\n 📖 {text}")]
+    InvalidNumber {
+        literal: String,
+        reason: String,
+        file: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+
+    #[error("🔢 Number `{literal}` does not fit in `{v_type}`, at {file}:{line}:{column}.\n 📖 {text}")]
+    NumberOverflow {
+        literal: String,
+        v_type: String,
+        file: String,
+        line: usize,
+        column: usize,
+        text: String,
+    },
+
+    #[error("🔣 Invalid UTF-8 at {file}:{line}:{column}, byte {offset}.")]
+    InvalidUtf8 {
+        offset: usize,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
     #[error("💥 Unknown error occurred.")]
     Unknown,
 }
 
 impl LexerError {
-    pub fn invalid_character(character: char, line: usize, column: usize, text: String) -> Self {
-        LexerError::InvalidCharacter { character, line, column, text }
+    pub fn invalid_character(character: char, file: String, line: usize, column: usize, text: String) -> Self {
+        LexerError::InvalidCharacter { character, file, line, column, text }
+    }
+
+    pub fn unterminated_literal(kind: String, file: String, line: usize, column: usize, text: String) -> Self {
+        LexerError::UnterminatedLiteral { kind, file, line, column, text }
+    }
+
+    pub fn unterminated_comment(file: String, line: usize, column: usize, text: String) -> Self {
+        LexerError::UnterminatedComment { file, line, column, text }
+    }
+
+    pub fn invalid_number(literal: String, reason: String, file: String, line: usize, column: usize, text: String) -> Self {
+        LexerError::InvalidNumber { literal, reason, file, line, column, text }
+    }
+
+    pub fn number_overflow(literal: String, v_type: String, file: String, line: usize, column: usize, text: String) -> Self {
+        LexerError::NumberOverflow { literal, v_type, file, line, column, text }
+    }
+
+    pub fn invalid_escape(sequence: String, file: String, line: usize, column: usize, text: String) -> Self {
+        LexerError::InvalidEscape { sequence, file, line, column, text }
+    }
+
+    pub fn invalid_char_literal(literal: String, file: String, line: usize, column: usize, text: String) -> Self {
+        LexerError::InvalidCharLiteral { literal, file, line, column, text }
     }
-}
\ No newline at end of file
+
+    pub fn invalid_utf8(offset: usize, file: String, line: usize, column: usize) -> Self {
+        LexerError::InvalidUtf8 { offset, file, line, column }
+    }
+}
@@ -1,6 +1,7 @@
 pub mod usr;
 pub mod prep;
 pub mod lex;
+pub mod parse;
 pub mod comp;
 pub mod asm;
 pub mod obj;
\ No newline at end of file
@@ -0,0 +1,79 @@
+use thiserror::Error;
+
+use crate::frontend::token::{Position, TokenType};
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ParserError {
+    #[error("⚠ Expected {}, found `{found}` at {file}:{}:{}.", one_of(.expected), .position.0.start + 1, .position.1.start + 1)]
+    UnexpectedToken {
+        expected: Vec<TokenType>,
+        found: String,
+        file: String,
+        position: Position,
+    },
+
+    #[error("⚠ Unexpected end of input, expected {}.", one_of(.expected))]
+    UnexpectedEof {
+        expected: Vec<TokenType>,
+    },
+
+    #[error("⚠ {what} is not supported, found `{op}` at {file}:{}:{}.", .position.0.start + 1, .position.1.start + 1)]
+    UnsupportedOperator {
+        what: String,
+        op: String,
+        file: String,
+        position: Position,
+    },
+
+    #[error("🔢 Invalid literal `{literal}` at {file}:{}:{}.", .position.0.start + 1, .position.1.start + 1)]
+    InvalidLiteral {
+        literal: String,
+        file: String,
+        position: Position,
+    },
+}
+
+fn one_of(expected: &[TokenType]) -> String {
+    let names: Vec<String> = expected.iter().map(|t| format!("{:?}", t)).collect();
+    match names.as_slice() {
+        [] => "a token".to_string(),
+        [name] => name.clone(),
+        [init @ .., last] => format!("one of {} or {}", init.join(", "), last),
+    }
+}
+
+impl ParserError {
+    pub fn unexpected_token(expected: Vec<TokenType>, found: String, file: String, position: Position) -> Self {
+        ParserError::UnexpectedToken { expected, found, file, position }
+    }
+
+    pub fn unexpected_eof(expected: Vec<TokenType>) -> Self {
+        ParserError::UnexpectedEof { expected }
+    }
+
+    pub fn invalid_literal(literal: String, file: String, position: Position) -> Self {
+        ParserError::InvalidLiteral { literal, file, position }
+    }
+
+    pub fn unsupported_operator(what: String, op: String, file: String, position: Position) -> Self {
+        ParserError::UnsupportedOperator { what, op, file, position }
+    }
+
+    /// File the error points into, if it points anywhere.
+    pub fn file(&self) -> Option<&str> {
+        match self {
+            ParserError::UnexpectedToken { file, .. } | ParserError::UnsupportedOperator { file, .. }
+            | ParserError::InvalidLiteral { file, .. } => Some(file),
+            ParserError::UnexpectedEof { .. } => None,
+        }
+    }
+
+    /// Span the error points at, if it has one.
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            ParserError::UnexpectedToken { position, .. } | ParserError::UnsupportedOperator { position, .. }
+            | ParserError::InvalidLiteral { position, .. } => Some(position),
+            ParserError::UnexpectedEof { .. } => None,
+        }
+    }
+}
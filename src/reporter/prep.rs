@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::reporter::lex::LexerError;
+
 #[derive(Debug, Error)]
 pub enum PreprocessorError {
     #[error("📂 Failed to read file `{filename}`:\n  🛑 {source}")]
@@ -9,11 +11,115 @@ pub enum PreprocessorError {
         source: std::io::Error,
     },
 
-    #[error("⚠ Missing parenthesis `(` in macro call.\n 📖 {text}")]
+    #[error("⚠ Missing parenthesis `{paren}` after {after}, at {file}:{line}:{column}.")]
     MissingParenthesis {
+        paren: String,
+        after: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("⚠ Expected {expected}, found {found} at {file}:{line}:{column}.")]
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("🧱 Body of macro `{name}` at {file}:{line}:{column} has no closing `}}`.")]
+    UnterminatedMacro {
+        name: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("❓ Unknown directive `!{directive}` at {file}:{line}:{column}.")]
+    UnknownDirective {
+        directive: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("📂 Cannot find `{name}` to include, at {file}:{line}:{column}.")]
+    IncludeNotFound {
+        name: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("🔁 Include cycle: {chain}.")]
+    IncludeCycle {
+        chain: String,
+    },
+
+    #[error("🧱 `!{directive}` at {file}:{line}:{column} has no matching `!endif`.")]
+    UnterminatedConditional {
+        directive: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("⚠ Unexpected `!{directive}` at {file}:{line}:{column}: {reason}.")]
+    UnexpectedDirective {
+        directive: String,
+        reason: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("⚖ Invalid condition at {file}:{line}:{column}: {reason}.")]
+    InvalidCondition {
+        reason: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("🔁 Macro `{name}` expands into itself, at {file}:{line}:{column}.")]
+    RecursiveMacro {
+        name: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("⚠ Macro `{name}` takes {expected} argument(s) but {found} were given, at {file}:{line}:{column}.")]
+    MacroArguments {
+        name: String,
+        expected: usize,
+        found: usize,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("🧵 Call to macro `{name}` at {file}:{line}:{column} is missing its `)`.")]
+    UnterminatedMacroCall {
+        name: String,
+        file: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("🧩 `{text}` pasted in a macro at {file}:{line}:{column} is not one token.")]
+    InvalidPaste {
         text: String,
+        file: String,
+        line: usize,
+        column: usize,
     },
 
+    #[error(transparent)]
+    Lexer(#[from] LexerError),
+
     #[error("💥 Unknown error occurred.")]
     Unknown,
 }
@@ -23,7 +129,55 @@ impl PreprocessorError {
         PreprocessorError::FileReadError { filename, source }
     }
 
-    pub fn missing_parenthesis(text: String) -> Self {
-        PreprocessorError::MissingParenthesis { text }
+    pub fn missing_parenthesis(paren: String, after: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::MissingParenthesis { paren, after, file, line, column }
+    }
+
+    pub fn unexpected_token(expected: String, found: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::UnexpectedToken { expected, found, file, line, column }
+    }
+
+    pub fn unterminated_macro(name: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::UnterminatedMacro { name, file, line, column }
+    }
+
+    pub fn unknown_directive(directive: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::UnknownDirective { directive, file, line, column }
+    }
+
+    pub fn include_not_found(name: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::IncludeNotFound { name, file, line, column }
+    }
+
+    pub fn include_cycle(chain: Vec<String>) -> Self {
+        PreprocessorError::IncludeCycle { chain: chain.join(" → ") }
+    }
+
+    pub fn unterminated_conditional(directive: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::UnterminatedConditional { directive, file, line, column }
+    }
+
+    pub fn unexpected_directive(directive: String, reason: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::UnexpectedDirective { directive, reason, file, line, column }
+    }
+
+    pub fn recursive_macro(name: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::RecursiveMacro { name, file, line, column }
+    }
+
+    pub fn macro_arguments(name: String, expected: usize, found: usize, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::MacroArguments { name, expected, found, file, line, column }
+    }
+
+    pub fn unterminated_macro_call(name: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::UnterminatedMacroCall { name, file, line, column }
+    }
+
+    pub fn invalid_paste(text: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::InvalidPaste { text, file, line, column }
+    }
+
+    pub fn invalid_condition(reason: String, file: String, line: usize, column: usize) -> Self {
+        PreprocessorError::InvalidCondition { reason, file, line, column }
     }
 }
\ No newline at end of file
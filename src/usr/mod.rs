@@ -1,7 +1,12 @@
 use crate::reporter;
-use crate::frontend::preprocessor;
+use crate::frontend::preprocessor::{self, include::Includes, macros, mapping::ExpansionMap};
 use crate::frontend::lexer;
 use crate::frontend::parser;
+use crate::frontend::source::SourceMap;
+use crate::frontend::token::Token;
+use crate::backend::{ir, lowering, opt, writer};
+use crate::backend::codegen::x86_64::Codegen;
+use crate::backend::opt::pipeline::{folding::ConstantFolding, dead_code::DeadCode, strength::Strength};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
@@ -10,45 +15,166 @@ const HELP: &str = r#"
 📚 Help
 📦 Usage: {} <source file>
 📖 Options:
+  -o <file>: Write the object file to <file>.
+  -I <dir>:  Search <dir> for `!include`d files.
+  -D <name>[=<value>]: Define the macro <name> as <value>, or 1.
+  --dump:    Print the source, preprocessed text, AST, IR and registers.
   --help:    Display this information.
   --version: Display version information.
 "#;
 
 pub struct CompilationInstance {
     pub sources: Vec<String>,
-    pub output: String
+    pub output: String,
+    pub include_dirs: Vec<String>,
+    /// Macros from `-D`, as names and values.
+    pub defines: Vec<(String, String)>,
+    /// Print what each stage produced, from `--dump`.
+    pub dump: bool,
+    /// Every file read or expanded so far.
+    pub map: SourceMap
+}
+
+impl Default for CompilationInstance {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CompilationInstance {
     pub fn new() -> CompilationInstance {
         CompilationInstance {
             sources: Vec::new(),
-            output: String::new()
+            output: String::new(),
+            include_dirs: Vec::new(),
+            defines: Vec::new(),
+            dump: false,
+            map: SourceMap::new()
         }
     }
 }
 
-fn process_file(file: &str) -> Result<(), reporter::usr::CliError> {
-    let src = std::fs::read_to_string(file).map_err(|e| reporter::usr::CliError::file_read_error(file.to_string(), e))?;
-    println!("📖 Source:\n{}", src);
+/// Name and value of `-D NAME=value`, which is 1 when left out.
+fn parse_define(define: &str) -> (String, String) {
+    match define.split_once('=') {
+        Some((name, value)) => (name.to_string(), value.to_string()),
+        None => (define.to_string(), "1".to_string()),
+    }
+}
+
+/// Object file path for `source`, unless `-o` was given.
+fn output_path(instance: &CompilationInstance, source: &str) -> String {
+    if !instance.output.is_empty() {
+        return instance.output.clone();
+    }
+    std::path::Path::new(source).with_extension("o").to_string_lossy().into_owned()
+}
 
-    let mut prep_lex_inst = lexer::Lexer::new(src, true);
-    if let Err(e) = prep_lex_inst.lex() {
-        eprintln!("{}", e);
+/// Prints a parser error with a note for each macro expansion it is in.
+fn report_parse_error(map: &SourceMap, mapping: &ExpansionMap, e: reporter::parse::ParserError) {
+    eprintln!("{}", e);
+    let (Some(file), Some(position)) = (e.file().and_then(|name| map.find(name)), e.position()) else {
+        return;
+    };
+    for expansion in mapping.expansions_at(file, position).iter().rev() {
+        eprintln!(" 🔁 {}", expansion.describe(map));
+    }
+}
+
+fn process_file(map: &mut SourceMap, include_dirs: &[String], defines: &[(String, String)], dump: bool, file: &str, output: &str) -> Result<(), reporter::usr::CliError> {
+    let src = std::fs::read(file).map_err(|e| reporter::usr::CliError::file_read_error(file.to_string(), e))?;
+    if dump {
+        println!("📖 Source:\n{}", String::from_utf8_lossy(&src));
+    }
+
+    let src = match lexer::decode(file, src) {
+        Ok(src) => src,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Ok(());
+        }
+    };
+    let source_id = map.add(file.to_string(), src);
+
+    let mut prep_lex_inst = lexer::Lexer::from_map(map, source_id, true);
+    if let Err(errors) = prep_lex_inst.lex() {
+        for e in errors {
+            eprintln!("{}", e);
+        }
+        return Ok(());
     }
     //prep_lex_inst.dump_tokens();
-    let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
-    prep_inst.process();
-    let mut lex_inst = lexer::Lexer::new(prep_inst.get_processed(), false);
-    if let Err(e) = lex_inst.lex() {
-        eprintln!("{}", e);
+    // Owned tokens leave the map free for the files `!include` reads
+    let tokens = prep_lex_inst.tokens.into_iter().map(Token::into_owned).collect();
+    let mut includes = Includes::new(map.next_id());
+    includes.set_path(source_id, file);
+    for dir in include_dirs {
+        includes.add_dir(dir);
+    }
+    let mut prep_inst = preprocessor::Preprocessor::new(tokens);
+    prep_inst.set_includes(includes);
+    for (name, value) in defines {
+        prep_inst.add_macro(macros::Macro::define(name.clone(), value));
+    }
+    if let Err(errors) = prep_inst.process() {
+        for e in errors {
+            eprintln!("{}", e);
+        }
+        return Ok(());
+    }
+    if dump {
+        println!("🧩 Preprocessed:\n{}", prep_inst.get_processed());
+    }
+    let tokens = prep_inst.take_tokens();
+    let mapping = prep_inst.take_mapping();
+    for included in prep_inst.take_files() {
+        map.insert(included);
     }
-    lex_inst.dump_tokens();
 
-    let mut parser = parser::Parser::new(&lex_inst);
+    let mut parser = parser::Parser::from_tokens(&tokens, map);
     
-    parser.parse_all_tokens();
-    parser.dump_nodes();
+    if let Err(errors) = parser.parse_all_tokens() {
+        for e in errors.iter().cloned() {
+            report_parse_error(map, &mapping, e);
+        }
+        return Ok(());
+    }
+    if dump {
+        parser.dump_nodes();
+    }
+
+    let mut module = match lowering::Lowering::new(ir::Module::new()).lower(parser.get_nodes()) {
+        Ok(module) => module,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("{}: {}", file, e);
+            }
+            return Ok(());
+        }
+    };
+
+    let mut passes = opt::PassManager::new();
+    passes.add_pass(ConstantFolding::new());
+    passes.add_pass(DeadCode::new());
+    passes.add_pass(Strength::new());
+    passes.run_all(&mut module);
+    if dump {
+        println!("🌳 IR:\n{:#?}", module);
+    }
+
+    let mut cb = writer::CodeBuilder::new();
+    let mut codegen = Codegen::new(&mut cb);
+    let generated = codegen.generate(&module);
+    if dump {
+        println!("🧮 Registers:");
+        codegen.dump_allocations();
+    }
+    if let Err(e) = generated.and_then(|_| cb.finalize()) {
+        eprintln!("{}: {}", file, e);
+        return Ok(());
+    }
+    cb.write_obj(output);
+    println!("📦 Object written to {}", output);
 
     Ok(())
 }
@@ -60,13 +186,34 @@ pub fn run_cli() -> Result<(), reporter::usr::CliError> {
     }
     let mut instance = CompilationInstance::new();
 
-    for arg in args.iter().skip(1) {
-        if arg.starts_with("--") {
+    let mut args_iter = args.iter().skip(1);
+    while let Some(arg) = args_iter.next() {
+        if arg == "-o" {
+            match args_iter.next() {
+                Some(output) => instance.output = output.clone(),
+                None => return Err(reporter::usr::CliError::missing_file_usage(args[0].clone())),
+            }
+        } else if arg == "-I" {
+            match args_iter.next() {
+                Some(dir) => instance.include_dirs.push(dir.clone()),
+                None => return Err(reporter::usr::CliError::missing_file_usage(args[0].clone())),
+            }
+        } else if let Some(dir) = arg.strip_prefix("-I") {
+            instance.include_dirs.push(dir.to_string());
+        } else if arg == "-D" {
+            match args_iter.next() {
+                Some(define) => instance.defines.push(parse_define(define)),
+                None => return Err(reporter::usr::CliError::missing_file_usage(args[0].clone())),
+            }
+        } else if let Some(define) = arg.strip_prefix("-D") {
+            instance.defines.push(parse_define(define));
+        } else if arg.starts_with("--") {
             match arg.as_str() {
                 "--help" => {
                     println!("{}", HELP);
                     return Ok(());
                 },
+                "--dump" => instance.dump = true,
                 "--version" => {
                     println!("📦 Version: {}", VERSION);
                     println!("👤 Authors: {}", AUTHORS);
@@ -83,7 +230,8 @@ pub fn run_cli() -> Result<(), reporter::usr::CliError> {
 
     for source in instance.sources.iter() {
         println!("📂 Reading file: {}", source);
-        if let Err(e) = process_file(source) {
+        let output = output_path(&instance, source);
+        if let Err(e) = process_file(&mut instance.map, &instance.include_dirs, &instance.defines, instance.dump, source, &output) {
             eprintln!("{}", e);
         }
     }
@@ -1,7 +1,6 @@
-
 #[cfg(test)]
 mod tests {
     #[path = "asm/simple.rs"]
-    mod assemble_test;
+    mod simple;
     
 }
\ No newline at end of file
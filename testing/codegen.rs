@@ -0,0 +1,14 @@
+#[cfg(test)]
+mod tests {
+    #[path = "codegen/functions.rs"]
+    mod functions;
+
+    #[path = "codegen/abi.rs"]
+    mod abi;
+
+    #[path = "codegen/regalloc.rs"]
+    mod regalloc;
+
+    #[path = "codegen/runtime.rs"]
+    mod runtime;
+}
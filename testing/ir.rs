@@ -1,7 +1,6 @@
-
 #[cfg(test)]
 mod tests {
     #[path = "ir/tree_init.rs"]
-    mod tree_init_test;
+    mod tree_init;
     
 }
\ No newline at end of file
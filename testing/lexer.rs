@@ -1,6 +1,35 @@
-
 #[cfg(test)]
 mod tests {
     #[path = "lexer/tokenize.rs"]
-    mod tokenize_test;
+    mod tokenize;
+
+    #[path = "lexer/literals.rs"]
+    mod literals;
+
+    #[path = "lexer/comments.rs"]
+    mod comments;
+
+    #[path = "lexer/numbers.rs"]
+    mod numbers;
+
+    #[path = "lexer/keywords.rs"]
+    mod keywords;
+
+    #[path = "lexer/operators.rs"]
+    mod operators;
+
+    #[path = "lexer/unicode.rs"]
+    mod unicode;
+
+    #[path = "lexer/borrowing.rs"]
+    mod borrowing;
+
+    #[path = "lexer/simd.rs"]
+    mod simd;
+
+    #[path = "lexer/recovery.rs"]
+    mod recovery;
+
+    #[path = "lexer/sources.rs"]
+    mod sources;
 }
\ No newline at end of file
@@ -0,0 +1,5 @@
+#[cfg(test)]
+mod tests {
+    #[path = "lowering/functions.rs"]
+    mod functions;
+}
@@ -1,12 +1,11 @@
-
 #[cfg(test)]
 mod tests {
     #[path = "opts/const_folding.rs"]
-    mod const_folding_test;
+    mod const_folding;
     
     #[path = "opts/dead_code.rs"]
-    mod dead_code_test;
+    mod dead_code;
     
     #[path = "opts/strength.rs"]
-    mod strength_test;
+    mod strength;
 }
\ No newline at end of file
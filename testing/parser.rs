@@ -1,9 +1,14 @@
-
 #[cfg(test)]
 mod tests {
     #[path = "parser/simple_parse.rs"]
-    mod simple_parsing_test;
+    mod simple_parse;
 
     #[path = "parser/ops.rs"]
-    mod ops_test;
+    mod ops;
+
+    #[path = "parser/statements.rs"]
+    mod statements;
+
+    #[path = "parser/recovery.rs"]
+    mod recovery;
 }
\ No newline at end of file
@@ -0,0 +1,20 @@
+#[cfg(test)]
+mod tests {
+    #[path = "preprocessor/mapping.rs"]
+    mod mapping;
+
+    #[path = "preprocessor/include.rs"]
+    mod include;
+
+    #[path = "preprocessor/conditionals.rs"]
+    mod conditionals;
+
+    #[path = "preprocessor/expansion.rs"]
+    mod expansion;
+
+    #[path = "preprocessor/builtins.rs"]
+    mod builtins;
+
+    #[path = "preprocessor/errors.rs"]
+    mod errors;
+}
\ No newline at end of file
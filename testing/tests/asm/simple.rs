@@ -35,4 +35,32 @@ mod assemble_test {
         assert_eq!((x86::ptr(x86::RBP, -128, 4), x86::ECX).sub(), vec![0x29, 0x4D, 0x80]);
         assert_eq!((x86::ptr(x86::RBP, -128, 2), 128).sub(), vec![0x66, 0x81, 0x6D, 0x80, 0x80, 0x00]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn asm_assemble_cmp() {
+        assert_eq!((x86::RAX, x86::RCX).cmp(), vec![0x48, 0x39, 0xC8]);
+        assert_eq!((x86::R15D, 128).cmp(), vec![0x41, 0x81, 0xFF, 0x80, 0x00, 0x00, 0x00]);
+        assert_eq!((x86::RSI, x86::ptr(x86::RBP, -8, 8)).cmp(), vec![0x48, 0x3B, 0x75, 0xF8]);
+        assert_eq!((x86::RDI, x86::RDI).test(), vec![0x48, 0x85, 0xFF]);
+    }
+
+    #[test]
+    fn asm_assemble_cond() {
+        assert_eq!(x86::SIL.setcc(Cond::L), vec![0x40, 0x0F, 0x9C, 0xC6]);
+        assert_eq!(x86::R9B.setcc(Cond::E), vec![0x41, 0x0F, 0x94, 0xC1]);
+        assert_eq!(x86::AL.setcc(Cond::A.negate()), vec![0x0F, 0x96, 0xC0]);
+        assert_eq!(16.jcc(Cond::NE), vec![0x0F, 0x85, 0x0A, 0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn asm_assemble_movx() {
+        assert_eq!((x86::EAX, x86::AL).movzx(), vec![0x0F, 0xB6, 0xC0]);
+        // Byte sources 4-7 need a REX prefix to be sil/dil/spl/bpl rather than ah/ch/dh/bh
+        assert_eq!((x86::EDI, x86::DIL).movzx(), vec![0x40, 0x0F, 0xB6, 0xFF]);
+        assert_eq!((x86::ESI, x86::SIL).movzx(), vec![0x40, 0x0F, 0xB6, 0xF6]);
+        assert_eq!((x86::EAX, x86::SPL).movsx(), vec![0x40, 0x0F, 0xBE, 0xC4]);
+        assert_eq!((x86::ECX, x86::BPL).movsx(), vec![0x40, 0x0F, 0xBE, 0xCD]);
+        assert_eq!((x86::RAX, x86::DIL).movsx(), vec![0x48, 0x0F, 0xBE, 0xC7]);
+        assert_eq!((x86::R8D, x86::SIL).movzx(), vec![0x44, 0x0F, 0xB6, 0xC6]);
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(test)]
+mod abi_test {
+    use wind::backend::ir;
+    use wind::backend::assembler::x86_64::x86;
+    use wind::backend::codegen::x86_64::abi::{self, CallLayout, Class, Location, Slot};
+
+    const I32: ir::DataType = ir::DataType::Scalar { size: 4, signed: true };
+    const I64: ir::DataType = ir::DataType::Scalar { size: 8, signed: true };
+    const F32: ir::DataType = ir::DataType::Float { size: 4 };
+    const F64: ir::DataType = ir::DataType::Float { size: 8 };
+
+    fn record(fields: Vec<ir::DataType>) -> ir::DataType {
+        ir::DataType::Struct {
+            path: "S".to_string(),
+            fields: fields.into_iter().enumerate().map(|(i, t)| (format!("f{}", i), t)).collect(),
+        }
+    }
+
+    #[test]
+    fn classifies_scalars() {
+        assert_eq!(abi::classify(&I32), vec![Class::Integer]);
+        assert_eq!(abi::classify(&F64), vec![Class::Sse]);
+        assert_eq!(abi::classify(&ir::DataType::Pointer { target: Box::new(F32) }), vec![Class::Integer]);
+        assert!(abi::classify(&ir::DataType::Scalar { size: 0, signed: false }).is_empty());
+    }
+
+    #[test]
+    fn classifies_small_structs() {
+        // Floats sharing an eightbyte with an integer go to the integer registers
+        assert_eq!(abi::classify(&record(vec![I32, F32, F64])), vec![Class::Integer, Class::Sse]);
+        assert_eq!(abi::classify(&record(vec![F32, F32])), vec![Class::Sse]);
+        assert_eq!(abi::classify(&record(vec![I64, I32])), vec![Class::Integer, Class::Integer]);
+        let array = ir::DataType::Array { target: Box::new(F32), capacity: 4 };
+        assert_eq!(abi::classify(&record(vec![array])), vec![Class::Sse, Class::Sse]);
+    }
+
+    #[test]
+    fn large_structs_go_to_memory() {
+        assert_eq!(abi::classify(&record(vec![I64, I64, I32])), vec![Class::Memory]);
+    }
+
+    #[test]
+    fn lays_out_mixed_arguments() {
+        let pair = record(vec![I64, F64]);
+        let mut arguments = vec![pair, F32, record(vec![I64, I64, I64])];
+        arguments.extend(std::iter::repeat_n(I32, 6));
+        let layout = CallLayout::new(&arguments, &I32);
+
+        assert_eq!(layout.arguments[0], Location::Regs(vec![Slot::Gpr(x86::RDI), Slot::Xmm(0)]));
+        assert_eq!(layout.arguments[1], Location::Regs(vec![Slot::Xmm(1)]));
+        assert_eq!(layout.arguments[2], Location::Stack { offset: 0 });
+        assert_eq!(layout.arguments[3], Location::Regs(vec![Slot::Gpr(x86::RSI)]));
+        assert_eq!(layout.arguments[7], Location::Regs(vec![Slot::Gpr(x86::R9)]));
+        // Integer registers are exhausted
+        assert_eq!(layout.arguments[8], Location::Stack { offset: 24 });
+        assert_eq!(layout.stack_size, 32);
+        assert_eq!(layout.sse_used, 2);
+        assert_eq!(layout.ret, Location::Regs(vec![Slot::Gpr(x86::RAX)]));
+    }
+
+    #[test]
+    fn struct_spills_whole_when_registers_run_out() {
+        let pair = record(vec![I64, I64]);
+        let mut arguments: Vec<ir::DataType> = std::iter::repeat_n(I64, 5).collect();
+        arguments.push(pair);
+        arguments.push(I64);
+        let layout = CallLayout::new(&arguments, &F64);
+
+        assert_eq!(layout.arguments[5], Location::Stack { offset: 0 });
+        assert_eq!(layout.arguments[6], Location::Regs(vec![Slot::Gpr(x86::R9)]));
+        assert_eq!(layout.ret, Location::Regs(vec![Slot::Xmm(0)]));
+    }
+
+    #[test]
+    fn memory_return_takes_rdi() {
+        let layout = CallLayout::new(&[I32], &record(vec![I64, I64, I64]));
+        assert!(layout.indirect_ret);
+        assert_eq!(layout.arguments[0], Location::Regs(vec![Slot::Gpr(x86::RSI)]));
+    }
+}
@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod functions_test {
+    use wind::backend::{ir, writer, codegen::x86_64::Codegen};
+    use wind::reporter::asm::AssemblerError;
+
+    fn module() -> ir::Module {
+        let mut module = ir::Module::new();
+        let i32_t = module.resolve_type("i32".to_string()).unwrap();
+        let add = ir::Function::new(
+            "add".to_string(),
+            vec![("a".to_string(), i32_t.clone()), ("b".to_string(), i32_t.clone())],
+            i32_t.clone(),
+            0,
+            vec![
+                ir::Statement::Return(ir::Expr::Binary {
+                    op: ir::BinaryOp::Add,
+                    left: Box::new(ir::Expr::Reference(ir::Reference::Argument { index: 0, v_type: i32_t.clone() })),
+                    right: Box::new(ir::Expr::Reference(ir::Reference::Argument { index: 1, v_type: i32_t.clone() })),
+                })
+            ]
+        );
+        let call = ir::Expr::Call(ir::FunctionCall {
+            reference: add.clone(),
+            arguments: vec![ir::Expr::Literal(ir::Literal::Int(5)), ir::Expr::Literal(ir::Literal::Int(3))],
+        });
+        let main = ir::Function::new(
+            "main".to_string(), vec![], i32_t, ir::flags::FunctionModifer::NoMangle as u16,
+            vec![ir::Statement::Return(call)]
+        );
+        module.push(add).unwrap();
+        module.push(main).unwrap();
+        module
+    }
+
+    #[test]
+    fn emits_one_label_per_function() {
+        let module = module();
+        let mut cb = writer::CodeBuilder::new();
+        Codegen::new(&mut cb).generate(&module).unwrap();
+        cb.finalize().unwrap();
+
+        let text = &cb.sections[0];
+        assert_eq!(text.name, ".text");
+        let add = text.labels.iter().find(|l| l.name == module.functions["add"].mangled).unwrap();
+        let main = text.labels.iter().find(|l| l.name == "main").unwrap();
+        assert!(main.global);
+        assert!(!add.global);
+
+        // push rbp; mov rbp, rsp ... leave; ret
+        assert_eq!(add.code[..4], [0x55, 0x48, 0x89, 0xE5]);
+        assert_eq!(add.code[add.code.len() - 2..], [0xC9, 0xC3]);
+        // Both calls are resolved within the section
+        assert!(cb.relocs.is_empty());
+    }
+
+    #[test]
+    fn keeps_arguments_in_registers() {
+        let module = module();
+        let mut cb = writer::CodeBuilder::new();
+        Codegen::new(&mut cb).generate(&module).unwrap();
+
+        let add = cb.sections[0].labels.iter().find(|l| l.name == module.functions["add"].mangled).unwrap();
+        // Nothing to spill, no sub rsp after push rbp; mov rbp, rsp
+        assert_ne!(add.code[4..7], [0x48, 0x83, 0xEC]);
+    }
+
+    #[test]
+    fn string_literals_go_to_rodata() {
+        let mut module = ir::Module::new();
+        let void_t = module.resolve_type("void".to_string()).unwrap();
+        module.push(ir::Function::new(
+            "greet".to_string(), vec![], void_t, 0,
+            vec![ir::Statement::Expr(ir::Expr::Literal(ir::Literal::Str("hi".to_string())))]
+        )).unwrap();
+
+        let mut cb = writer::CodeBuilder::new();
+        Codegen::new(&mut cb).generate(&module).unwrap();
+        cb.finalize().unwrap();
+
+        let rodata = &cb.sections[1];
+        assert_eq!(rodata.name, ".rodata");
+        assert_eq!(rodata.labels[0].name, ".str0");
+        assert_eq!(rodata.labels[0].code, b"hi\0");
+        assert_eq!(cb.relocs.len(), 1);
+    }
+
+    #[test]
+    fn rejects_floats() {
+        let mut module = ir::Module::new();
+        let void_t = module.resolve_type("void".to_string()).unwrap();
+        module.push(ir::Function::new(
+            "float".to_string(), vec![], void_t, 0,
+            vec![ir::Statement::Expr(ir::Expr::Literal(ir::Literal::Float(1.5)))]
+        )).unwrap();
+
+        let mut cb = writer::CodeBuilder::new();
+        let err = Codegen::new(&mut cb).generate(&module).unwrap_err();
+        assert!(matches!(err, AssemblerError::Unsupported { .. }));
+    }
+
+    #[test]
+    fn calls_variadic_externs() {
+        use ir::flags::FunctionModifer;
+        let mut module = ir::Module::new();
+        let i32_t = module.resolve_type("i32".to_string()).unwrap();
+        let str_t = ir::DataType::Pointer { target: Box::new(module.resolve_type("u8".to_string()).unwrap()) };
+        module.push(ir::Function::new(
+            "printf".to_string(), vec![("fmt".to_string(), str_t)], i32_t.clone(),
+            FunctionModifer::Extern as u16 | FunctionModifer::Variadic as u16, vec![]
+        )).unwrap();
+        let call = module.resolve_call("printf".to_string(), vec![
+            ir::Expr::Literal(ir::Literal::Str("%d\n".to_string())),
+            ir::Expr::Literal(ir::Literal::Int(42)),
+        ]).unwrap();
+        module.push(ir::Function::new(
+            "main".to_string(), vec![], i32_t, FunctionModifer::NoMangle as u16, vec![ir::Statement::Expr(call)]
+        )).unwrap();
+
+        let mut cb = writer::CodeBuilder::new();
+        Codegen::new(&mut cb).generate(&module).unwrap();
+        cb.finalize().unwrap();
+
+        assert_eq!(cb.external, vec![(0, "printf".to_string())]);
+        let main = &cb.sections[0].labels[0];
+        // No vector registers used: mov eax, 0 right before the call
+        let call_at = main.code.iter().rposition(|b| *b == 0xE8).unwrap();
+        assert_eq!(main.code[call_at - 5..call_at + 1], [0xB8, 0, 0, 0, 0, 0xE8]);
+        assert!(cb.relocs.iter().any(|(_, name, _)| name == "printf"));
+    }
+
+    #[test]
+    fn branches_between_blocks() {
+        let mut module = ir::Module::new();
+        let i32_t = module.resolve_type("i32".to_string()).unwrap();
+        let arg = |index| ir::Expr::Reference(ir::Reference::Argument { index, v_type: i32_t.clone() });
+        module.push(ir::Function::new(
+            "max".to_string(),
+            vec![("a".to_string(), i32_t.clone()), ("b".to_string(), i32_t.clone())],
+            i32_t.clone(),
+            ir::flags::FunctionModifer::NoMangle as u16,
+            vec![ir::Statement::If {
+                cond: ir::Expr::Binary { op: ir::BinaryOp::Gt, left: Box::new(arg(0)), right: Box::new(arg(1)) },
+                then: vec![ir::Statement::Return(arg(0))],
+                otherwise: vec![ir::Statement::Return(arg(1))],
+            }]
+        )).unwrap();
+
+        let mut cb = writer::CodeBuilder::new();
+        Codegen::new(&mut cb).generate(&module).unwrap();
+        cb.finalize().unwrap();
+
+        let labels: Vec<&str> = cb.sections[0].labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(labels, ["max", ".max.b1", ".max.b2"]);
+        // cmp; jle .max.b2, falling through into .max.b1
+        let max = &cb.sections[0].labels[0];
+        let jle = max.code.len() - 6;
+        assert_eq!(max.code[jle..jle + 2], [0x0F, 0x8E]);
+        let rel = i32::from_le_bytes(max.code[jle + 2..].try_into().unwrap());
+        assert_eq!(rel as usize, cb.sections[0].labels[1].code.len());
+        assert!(cb.relocs.is_empty());
+    }
+}
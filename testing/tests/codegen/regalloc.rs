@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod regalloc_test {
+    use wind::backend::assembler::x86_64::x86;
+    use wind::backend::codegen::x86_64::abi::{self, CallLayout};
+    use wind::backend::codegen::x86_64::regalloc::{self, Loc};
+    use wind::backend::codegen::x86_64::vreg::{AluOp, Inst, VFunction};
+    use wind::backend::ir;
+
+    fn i64_t() -> ir::DataType {
+        ir::DataType::Scalar { size: 8, signed: true }
+    }
+
+    /// `v0 = 1; v1 = 2; v0 = v0 + v1; call f(); ret v0`
+    fn across_call() -> VFunction {
+        let mut func = VFunction::new("f".to_string(), false);
+        let (a, b) = (func.new_vreg(), func.new_vreg());
+        func.blocks[0].insts = vec![
+            Inst::Imm { dst: a, value: 1 },
+            Inst::Imm { dst: b, value: 2 },
+            Inst::Alu { op: AluOp::Add, dst: a, src: b },
+            Inst::Call { name: "g".to_string(), args: vec![], layout: CallLayout::new(&[], &i64_t()), ret: None, variadic: false },
+            Inst::Ret { value: Some(a) },
+        ];
+        func
+    }
+
+    #[test]
+    fn builds_intervals() {
+        let func = across_call();
+        let intervals = regalloc::intervals(&func);
+        assert_eq!((intervals[0].start, intervals[0].end), (1, 8));
+        assert_eq!((intervals[1].start, intervals[1].end), (3, 4));
+        assert!(intervals[0].crosses_call);
+        assert!(!intervals[1].crosses_call);
+    }
+
+    #[test]
+    fn follows_control_flow() {
+        // v0 is defined in the first block and read in the last one
+        let mut func = VFunction::new("f".to_string(), false);
+        let v = func.new_vreg();
+        func.blocks = vec![Default::default(), Default::default(), Default::default()];
+        func.blocks[0].insts = vec![Inst::Imm { dst: v, value: 1 }];
+        func.blocks[0].succs = vec![2];
+        func.blocks[1].insts = vec![Inst::Ret { value: None }];
+        func.blocks[2].insts = vec![Inst::Ret { value: Some(v) }];
+
+        let live_in = regalloc::liveness(&func);
+        assert!(live_in[0].is_empty());
+        assert!(live_in[2].contains(&v));
+        assert!(!live_in[1].contains(&v));
+    }
+
+    #[test]
+    fn saves_values_live_across_calls() {
+        let func = across_call();
+        let alloc = regalloc::allocate(&func);
+        let Loc::Reg(reg) = alloc.loc(func.blocks[0].insts[4].uses()[0]) else { panic!("spilled") };
+        assert!(abi::CALLEE_SAVED.contains(reg));
+        assert_eq!(alloc.callee_saved, vec![reg.clone()]);
+    }
+
+    #[test]
+    fn spills_when_out_of_registers() {
+        let func = across_call();
+        let alloc = regalloc::allocate_with(&func, &[x86::RSI, x86::RBX]);
+        // Only rbx survives the call, and v0 ends last
+        assert_eq!(alloc.locs.values().filter(|l| matches!(l, Loc::Spill(_))).count(), 0);
+        let alloc = regalloc::allocate_with(&func, &[x86::RSI]);
+        assert!(matches!(alloc.loc(func.blocks[0].insts[4].uses()[0]), Loc::Spill(8)));
+        assert_eq!(alloc.spill_size, 8);
+    }
+
+    #[test]
+    fn dumps_locations() {
+        let func = across_call();
+        let alloc = regalloc::allocate(&func);
+        let dump = alloc.dump(&func);
+        assert!(dump.starts_with("f:\n  .b0:\n"));
+        assert!(dump.contains("v1 = 2"));
+        assert!(dump.contains("v1 -> rsi"));
+    }
+}
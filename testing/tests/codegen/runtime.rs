@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod runtime_test {
+    use std::process::Command;
+
+    use wind::backend::{ir, lowering::Lowering, opt, writer, codegen::x86_64::Codegen};
+    use wind::frontend::{lexer, parser::Parser, preprocessor};
+    use wind::backend::opt::pipeline::{folding::ConstantFolding, dead_code::DeadCode, strength::Strength};
+
+    fn arg(index: usize, v_type: &ir::DataType) -> Box<ir::Expr> {
+        Box::new(ir::Expr::Reference(ir::Reference::Argument { index, v_type: v_type.clone() }))
+    }
+
+    fn int(value: u64) -> ir::Expr {
+        ir::Expr::Literal(ir::Literal::Int(value))
+    }
+
+    fn binary(op: ir::BinaryOp, left: Box<ir::Expr>, right: Box<ir::Expr>) -> ir::Expr {
+        ir::Expr::Binary { op, left, right }
+    }
+
+    /// `name(a, b) => v_type { return body(a, b); }`.
+    fn function(name: &str, v_type: &ir::DataType, params: usize, body: ir::Expr) -> ir::Function {
+        let arguments = ["a", "b"][..params].iter().map(|p| (p.to_string(), v_type.clone())).collect();
+        ir::Function::new(name.to_string(), arguments, v_type.clone(), 0, vec![ir::Statement::Return(body)])
+    }
+
+    fn call(function: &ir::Function, arguments: Vec<ir::Expr>) -> Box<ir::Expr> {
+        Box::new(ir::Expr::Call(ir::FunctionCall { reference: function.clone(), arguments }))
+    }
+
+    /// Exit status of a program whose `main` returns `body`, built with the
+    /// passes the command line runs and linked with the system `cc`.
+    fn run(test: &str, functions: Vec<ir::Function>, v_type: &ir::DataType, body: ir::Expr) -> i32 {
+        let mut module = ir::Module::new();
+        for function in functions {
+            module.push(function).unwrap();
+        }
+        module.push(ir::Function::new(
+            "main".to_string(), vec![], v_type.clone(), ir::flags::FunctionModifer::NoMangle as u16,
+            vec![ir::Statement::Return(body)]
+        )).unwrap();
+        link(test, module)
+    }
+
+    /// Exit status of the program `src`, compiled the way the command line does.
+    fn run_source(test: &str, src: &str) -> i32 {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+        let src = prep_inst.get_processed();
+
+        let mut lexer = lexer::Lexer::new(&src, false);
+        lexer.lex().unwrap();
+        let mut parser = Parser::new(&lexer);
+        let nodes = parser.parse_all_tokens().unwrap();
+        let module = Lowering::new(ir::Module::new()).lower(nodes).unwrap();
+        link(test, module)
+    }
+
+    /// Runs the passes over `module`, links it with the system `cc` and runs it.
+    fn link(test: &str, mut module: ir::Module) -> i32 {
+        let mut passes = opt::PassManager::new();
+        passes.add_pass(ConstantFolding::new());
+        passes.add_pass(DeadCode::new());
+        passes.add_pass(Strength::new());
+        passes.run_all(&mut module);
+
+        let mut cb = writer::CodeBuilder::new();
+        Codegen::new(&mut cb).generate(&module).unwrap();
+        cb.finalize().unwrap();
+
+        let dir = std::env::temp_dir().join(format!("wind-runtime-{}-{}", test, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (obj, exe) = (dir.join("main.o"), dir.join("main"));
+        cb.write_obj(obj.to_str().unwrap());
+        assert!(Command::new("cc").arg(&obj).arg("-o").arg(&exe).status().unwrap().success());
+        let status = Command::new(&exe).status().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        status.code().unwrap()
+    }
+
+    #[test]
+    fn byte_arguments_in_sil_and_dil() {
+        let module = ir::Module::new();
+        let u8_t = module.resolve_type("u8".to_string()).unwrap();
+        let bits = function("bits", &u8_t, 2, binary(ir::BinaryOp::Or, arg(0, &u8_t), arg(1, &u8_t)));
+        let body = *call(&bits, vec![int(0xF0), int(0x3C)]);
+        assert_eq!(run("bits", vec![bits], &u8_t, body), 252);
+    }
+
+    #[test]
+    fn byte_comparisons_in_sil_and_dil() {
+        let module = ir::Module::new();
+        let u8_t = module.resolve_type("u8".to_string()).unwrap();
+        let less = function("less", &u8_t, 2, binary(ir::BinaryOp::Lt, arg(0, &u8_t), arg(1, &u8_t)));
+        // less(3, 200) * 10 + less(200, 3)
+        let body = binary(
+            ir::BinaryOp::Add,
+            Box::new(binary(ir::BinaryOp::Mul, call(&less, vec![int(3), int(200)]), Box::new(int(10)))),
+            call(&less, vec![int(200), int(3)]),
+        );
+        assert_eq!(run("less", vec![less], &u8_t, body), 10);
+    }
+
+    #[test]
+    fn unsigned_division_by_constants() {
+        let module = ir::Module::new();
+        let u64_t = module.resolve_type("u64".to_string()).unwrap();
+        // the magic for 7 needs 65 bits, the one for 3 fits in 64
+        let by_7 = function("by_7", &u64_t, 1, binary(ir::BinaryOp::Div, arg(0, &u64_t), Box::new(int(7))));
+        let by_3 = function("by_3", &u64_t, 1, binary(ir::BinaryOp::Div, arg(0, &u64_t), Box::new(int(3))));
+        // by_7(7) + by_7(700) * 2 + by_3(30)
+        let body = binary(
+            ir::BinaryOp::Add,
+            Box::new(binary(
+                ir::BinaryOp::Add,
+                call(&by_7, vec![int(7)]),
+                Box::new(binary(ir::BinaryOp::Mul, call(&by_7, vec![int(700)]), Box::new(int(2)))),
+            )),
+            call(&by_3, vec![int(30)]),
+        );
+        assert_eq!(run("div", vec![by_7, by_3], &u64_t, body), 211);
+    }
+
+    #[test]
+    fn main_from_source() {
+        let src = "fn twice(x: i32) => i32 { return x * 2; }\nfn main() => i32 { return twice(21); }\n";
+        assert_eq!(run_source("source", src), 42);
+    }
+
+    #[test]
+    fn pointer_arguments_from_source() {
+        let src = "extern fn strlen(s: *u8) => u64;\nfn main() => u64 { return strlen(\"hello\"); }\n";
+        assert_eq!(run_source("pointer", src), 5);
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        // Dividing by zero would kill the program if the right sides ran
+        let src = "
+fn check(n: i32) => u8 {
+    let r: u8 = 0;
+    if n != 0 && 10 / n > 1 { r += 1; }
+    if n == 0 || 10 / n > 4 { r += 2; }
+    return r;
+}
+fn small(n: i32) => u8 { return n > 0 && n < 10; }
+fn main() => u8 { return check(0) + check(2) * 4 + check(5) * 16 + small(5) * 64 + small(0) * 128; }
+";
+        assert_eq!(run_source("logical", src), 94);
+    }
+
+    #[test]
+    fn examples_run() {
+        let example = |name| std::fs::read_to_string(format!("examples/{}.sz", name)).unwrap();
+        assert_eq!(run_source("macros", &example("01-macros")), 53);
+        assert_eq!(run_source("tokens", &example("02-tokens")), 16);
+    }
+}
@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod borrowing_test {
+    use std::borrow::Cow;
+    use wind::frontend::{lexer, token::{self, TokenType}};
+
+    #[test]
+    fn tokens_borrow_from_the_source() {
+        let src = "/// Doc.\nfn größe(a: u8) => u64 { let s = \"plain\"; return a + 12u8 * 1.5e3; }";
+        let mut lexer = lexer::Lexer::new(src, false);
+        lexer.lex().unwrap();
+
+        let range = src.as_bytes().as_ptr_range();
+        for token in &lexer.tokens {
+            let punctuation = token::PUNCTUATION.iter().any(|(_, t)| *t == token.token_type);
+            match &token.literal {
+                // Punctuation borrows its spelling from the table instead
+                Cow::Borrowed(literal) if punctuation => assert_eq!(*literal, &src[token.position.2.clone()]),
+                Cow::Borrowed(literal) => assert!(range.contains(&literal.as_ptr()), "{:?} is not in the source", token),
+                Cow::Owned(_) => panic!("{:?} was copied", token),
+            }
+        }
+    }
+
+    #[test]
+    fn rewritten_literals_are_owned() {
+        let mut lexer = lexer::Lexer::new("\"a\\tb\" 0xff 1_000 007 7", false);
+        lexer.lex().unwrap();
+        let owned: Vec<(TokenType, &str, bool)> = lexer.tokens.iter()
+            .map(|t| (t.token_type, t.literal.as_ref(), matches!(t.literal, Cow::Owned(_))))
+            .collect();
+        assert_eq!(owned, vec![
+            (TokenType::STRING, "a\tb", true),
+            (TokenType::INTEGER, "255", true),
+            (TokenType::INTEGER, "1000", true),
+            (TokenType::INTEGER, "7", true),
+            (TokenType::INTEGER, "7", false),
+        ]);
+    }
+}
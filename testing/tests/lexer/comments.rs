@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod comments_test {
+    use wind::frontend::{lexer::{self, Lexer}, preprocessor, token::TokenType};
+    use wind::reporter::lex::LexerError;
+
+    fn lex(src: &str, allow_ws: bool) -> Result<Lexer<'_>, LexerError> {
+        let mut lexer = lexer::Lexer::new(src, allow_ws);
+        lexer.lex().map_err(|mut errors| errors.remove(0))?;
+        Ok(lexer)
+    }
+
+    fn literals(src: &str) -> Vec<String> {
+        lex(src, false).unwrap().tokens.into_iter().map(|t| t.literal.into_owned()).collect()
+    }
+
+    #[test]
+    fn skips_line_comments() {
+        assert_eq!(literals("a = 1; // b = 2;\nc = a / 2; //"), vec!["a", "=", "1", ";", "c", "=", "a", "/", "2", ";"]);
+    }
+
+    #[test]
+    fn skips_nested_block_comments() {
+        assert_eq!(literals("a /* one /* two */ still one */ + /**/ b"), vec!["a", "+", "b"]);
+        assert_eq!(literals("a /* // not a line comment */ b"), vec!["a", "b"]);
+        assert!(matches!(lex("a /* /* */ b", false), Err(LexerError::UnterminatedComment { line: 1, column: 3, .. })));
+    }
+
+    #[test]
+    fn keeps_doc_comments() {
+        let lexer = lex("/// Adds.\n///\n////  plain\n//// also plain\nfn", false).unwrap();
+        let tokens: Vec<(TokenType, &str)> = lexer.tokens.iter().map(|t| (t.token_type, t.literal.as_ref())).collect();
+        assert_eq!(tokens, vec![
+            (TokenType::DOC_COMMENT, "Adds."),
+            (TokenType::DOC_COMMENT, ""),
+            (TokenType::FN, "fn"),
+        ]);
+    }
+
+    #[test]
+    fn preserves_lines_through_the_preprocessor() {
+        let src = "/* one\n two */ a; // three\n/// four\nb;";
+        let mut prep_lex_inst = lex(src, true).unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(std::mem::take(&mut prep_lex_inst.tokens));
+        prep_inst.process().unwrap();
+
+        let processed = prep_inst.get_processed();
+        let lexer = lex(&processed, false).unwrap();
+        let lines: Vec<(&str, usize)> = lexer.tokens.iter().map(|t| (t.literal.as_ref(), t.position.0.start)).collect();
+        assert_eq!(lines, vec![("a", 1), (";", 1), ("four", 2), ("b", 3), (";", 3)]);
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod keywords_test {
+    use wind::frontend::{ast::{ASTNode, Expression, Literal, Statement}, lexer, parser::Parser, token::{self, TokenType}};
+    use wind::reporter::parse::ParserError;
+
+    fn types(src: &str) -> Vec<TokenType> {
+        let mut lexer = lexer::Lexer::new(src, false);
+        lexer.lex().unwrap();
+        lexer.tokens.into_iter().map(|t| t.token_type).collect()
+    }
+
+    #[test]
+    fn classifies_every_keyword() {
+        for (keyword, token_type) in token::KEYWORDS {
+            assert_eq!(types(keyword), vec![token_type], "while lexing `{}`", keyword);
+            assert_eq!(lexer::simd::classify_ident(keyword), token_type);
+        }
+    }
+
+    #[test]
+    fn leaves_other_identifiers_alone() {
+        for ident in ["f", "fnx", "iff", "lets", "returns", "continued", "forall", "True", "FALSE", "whilE", "else2", "_if", "_"] {
+            assert_eq!(types(ident), vec![TokenType::IDENTIFIER], "while lexing `{}`", ident);
+        }
+        assert_eq!(lexer::simd::classify_ident(""), TokenType::IDENTIFIER);
+    }
+
+    #[test]
+    fn reserves_keywords() {
+        let mut lexer = lexer::Lexer::new("fn f() { let struct = 1; }", false);
+        lexer.lex().unwrap();
+        let mut parser = Parser::new(&lexer);
+        let errors = parser.parse_all_tokens().unwrap_err();
+        assert!(matches!(&errors[..], [ParserError::UnexpectedToken { expected, found, .. }]
+            if *expected == vec![TokenType::IDENTIFIER] && found == "struct"));
+    }
+
+    #[test]
+    fn parses_bool_literals() {
+        let mut lexer = lexer::Lexer::new("fn f() => u8 { while true { return false; } }", false);
+        lexer.lex().unwrap();
+        let mut parser = Parser::new(&lexer);
+        let nodes = parser.parse_all_tokens().unwrap();
+        let Some(ASTNode::FUNCTION(function)) = nodes.front() else {
+            panic!("Expected a function, found {:?}", nodes);
+        };
+        assert_eq!(function.body, vec![Statement::WHILE {
+            condition: Expression::LITERAL(Literal::BOOL(true)),
+            body: vec![Statement::RETURN(Expression::LITERAL(Literal::BOOL(false)))],
+        }]);
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod literals_test {
+    use wind::frontend::{lexer::{self, Lexer}, preprocessor, token::TokenType};
+    use wind::reporter::lex::LexerError;
+
+    fn lex(src: &str) -> Result<Lexer<'_>, LexerError> {
+        let mut lexer = lexer::Lexer::new(src, false);
+        lexer.lex().map_err(|mut errors| errors.remove(0))?;
+        Ok(lexer)
+    }
+
+    fn literals(src: &str) -> Vec<(TokenType, String)> {
+        lex(src).unwrap().tokens.into_iter()
+            .filter(|t| matches!(t.token_type, TokenType::STRING | TokenType::CHAR))
+            .map(|t| (t.token_type, t.literal.into_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn lexes_string_escapes() {
+        assert_eq!(literals(r#"f("hello\n", "a\tb\\c\"d\0", "\x41\u{e9}\u{1F600}", "");"#), vec![
+            (TokenType::STRING, "hello\n".to_string()),
+            (TokenType::STRING, "a\tb\\c\"d\0".to_string()),
+            (TokenType::STRING, "Aé😀".to_string()),
+            (TokenType::STRING, String::new()),
+        ]);
+    }
+
+    #[test]
+    fn lexes_char_literals() {
+        assert_eq!(literals(r"'a' '\'' '\n' '\u{3bb}' '\x7f'"), vec![
+            (TokenType::CHAR, "a".to_string()),
+            (TokenType::CHAR, "'".to_string()),
+            (TokenType::CHAR, "\n".to_string()),
+            (TokenType::CHAR, "λ".to_string()),
+            (TokenType::CHAR, "\x7f".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn spans_the_quotes() {
+        let lexer = lex(r#"x = "ab\n";"#).unwrap();
+        let string = lexer.tokens.iter().find(|t| t.token_type == TokenType::STRING).unwrap();
+        assert_eq!(string.position, (0..0, 4..9, 4..10));
+        assert_eq!(lexer.tokens.last().unwrap().token_type, TokenType::SEMICOLON);
+    }
+
+    #[test]
+    fn reports_unterminated_literals() {
+        assert!(matches!(lex("a = \"abc;\nb = 1;"), Err(LexerError::UnterminatedLiteral { kind, line: 1, .. }) if kind == "string"));
+        assert!(matches!(lex("'a"), Err(LexerError::UnterminatedLiteral { kind, .. }) if kind == "character"));
+        let errors = lexer::Lexer::new(r#""ends in escape\"#, false).lex().unwrap_err();
+        assert!(matches!(errors.as_slice(), [LexerError::UnterminatedLiteral { .. }, LexerError::InvalidEscape { .. }]), "{:?}", errors);
+    }
+
+    #[test]
+    fn reports_invalid_escapes() {
+        for (src, sequence) in [
+            (r#""\q""#, r"\q"),
+            (r#""\x4""#, r"\x4"),
+            (r#""\xff""#, r"\xff"),
+            (r#""\u41""#, r"\u"),
+            (r#""\u{}""#, r"\u{"),
+            (r#""\u{1234567}""#, r"\u{1234567"),
+            (r#""\u{d800}""#, r"\u{d800}"),
+        ] {
+            match lex(src) {
+                Err(LexerError::InvalidEscape { sequence: found, .. }) => assert_eq!(found, sequence, "while lexing {}", src),
+                other => panic!("Expected an invalid escape in {}, found {:?}", src, other.map(|l| l.tokens)),
+            }
+        }
+        assert!(matches!(lex("''"), Err(LexerError::InvalidCharLiteral { .. })));
+        assert!(matches!(lex("'ab'"), Err(LexerError::InvalidCharLiteral { literal, .. }) if literal == "'ab'"));
+    }
+
+    #[test]
+    fn survives_preprocessing() {
+        let src = "!macro GREET: \"hi\\t\\\"there\\\"\"\nprintf(GREET, \"%d\\n\", '\\'');";
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+
+        assert_eq!(literals(&prep_inst.get_processed()), vec![
+            (TokenType::STRING, "hi\t\"there\"".to_string()),
+            (TokenType::STRING, "%d\n".to_string()),
+            (TokenType::CHAR, "'".to_string()),
+        ]);
+    }
+}
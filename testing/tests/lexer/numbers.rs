@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod numbers_test {
+    use wind::frontend::{lexer, token::TokenType};
+    use wind::reporter::lex::LexerError;
+
+    fn lex(src: &str) -> Result<Vec<(TokenType, String)>, LexerError> {
+        let mut lexer = lexer::Lexer::new(src, false);
+        lexer.lex().map_err(|mut errors| errors.remove(0))?;
+        Ok(lexer.tokens.into_iter().map(|t| (t.token_type, t.literal.into_owned())).collect())
+    }
+
+    fn numbers(src: &str, token_type: TokenType) -> Vec<String> {
+        let tokens = lex(src).unwrap();
+        assert!(tokens.iter().all(|(t, _)| *t == token_type), "{:?}", tokens);
+        tokens.into_iter().map(|(_, literal)| literal).collect()
+    }
+
+    #[test]
+    fn lexes_integers_in_every_radix() {
+        assert_eq!(numbers("0 7 007 1_000_000 0x_FF 0xdead_BEEF 0o17 0b1010_1010", TokenType::INTEGER),
+            vec!["0", "7", "7", "1000000", "255", "3735928559", "15", "170"]);
+        // Long enough for the vectorized scan
+        assert_eq!(numbers("123456789012345678 0xFFFF_FFFF_FFFF_FFFF", TokenType::INTEGER),
+            vec!["123456789012345678", "18446744073709551615"]);
+    }
+
+    #[test]
+    fn lexes_floats() {
+        assert_eq!(numbers("1.5 0.25 1e3 2.5E-3 6.02e+23 1_0.0_1 3f32 1.5f64", TokenType::FLOAT),
+            vec!["1.5", "0.25", "1e3", "2.5E-3", "6.02e+23", "10.01", "3f32", "1.5f64"]);
+    }
+
+    #[test]
+    fn keeps_suffixes() {
+        assert_eq!(numbers("42u8 0xffu8 1_i64 7u64", TokenType::INTEGER), vec!["42u8", "255u8", "1i64", "7u64"]);
+    }
+
+    #[test]
+    fn stops_before_dots_that_are_not_fractions() {
+        let types: Vec<TokenType> = lex("1.max 2.").unwrap().into_iter().map(|(t, _)| t).collect();
+        assert_eq!(types, vec![TokenType::INTEGER, TokenType::DOT, TokenType::IDENTIFIER, TokenType::INTEGER, TokenType::DOT]);
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        for (src, reason) in [
+            ("0x", "missing digits after `0x`"),
+            ("0b_", "missing digits after `0b`"),
+            ("0b102", "invalid digit `2` in a base 2 literal"),
+            ("0o8", "invalid digit `8` in a base 8 literal"),
+            ("12ab", "unknown suffix `ab`"),
+            ("1.5e", "unknown suffix `e`"),
+            ("1.5u8", "integer suffix `u8` on a float"),
+            ("0b1f32", "`f32` on a base 2 literal"),
+        ] {
+            match lex(src) {
+                Err(LexerError::InvalidNumber { reason: found, literal, .. }) => {
+                    assert_eq!(found, reason);
+                    assert_eq!(literal, src);
+                }
+                other => panic!("Expected {} to be invalid, found {:?}", src, other),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_overflow() {
+        for (src, v_type) in [
+            ("256u8", "u8"),
+            ("129i8", "i8"),
+            ("0x1_0000_0000u32", "u32"),
+            ("18446744073709551616", "u64"),
+            ("1e400", "f64"),
+            ("1e39f32", "f32"),
+            ("1e400f32", "f32"),
+            ("1e400f64", "f64"),
+        ] {
+            match lex(src) {
+                Err(LexerError::NumberOverflow { v_type: found, .. }) => assert_eq!(found, v_type, "while lexing {}", src),
+                other => panic!("Expected {} to overflow, found {:?}", src, other),
+            }
+        }
+        // `-128i8` is a minus sign and `128i8`
+        assert!(lex("-128i8 255u8 4294967295u32").is_ok());
+    }
+}
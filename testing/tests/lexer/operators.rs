@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod operators_test {
+    use wind::frontend::{lexer, token::{self, TokenType}};
+
+    fn lex(src: &str) -> Vec<(TokenType, String)> {
+        let mut lexer = lexer::Lexer::new(src, false);
+        lexer.lex().unwrap();
+        lexer.tokens.into_iter().map(|t| (t.token_type, t.literal.into_owned())).collect()
+    }
+
+    fn types(src: &str) -> Vec<TokenType> {
+        lex(src).into_iter().map(|(t, _)| t).collect()
+    }
+
+    #[test]
+    fn lexes_every_punctuation() {
+        for (text, token_type) in token::PUNCTUATION {
+            assert_eq!(lex(text), vec![(token_type, text.to_string())], "while lexing `{}`", text);
+        }
+    }
+
+    #[test]
+    fn takes_the_longest_match() {
+        use TokenType::*;
+        assert_eq!(types("a<<=b>>c<<d>>=e"), vec![IDENTIFIER, SHL_ASSIGN, IDENTIFIER, SHR, IDENTIFIER, SHL, IDENTIFIER, SHR_ASSIGN, IDENTIFIER]);
+        assert_eq!(types("a&&b&c||d|e^f%g~h"), vec![
+            IDENTIFIER, LOGICAL_AND, IDENTIFIER, AND, IDENTIFIER, LOGICAL_OR, IDENTIFIER, OR,
+            IDENTIFIER, CARET, IDENTIFIER, PERCENT, IDENTIFIER, TILDE, IDENTIFIER
+        ]);
+        assert_eq!(types("a*=b/=c%=d&=e|=f^=g"), vec![
+            IDENTIFIER, ASTERISK_ASSIGN, IDENTIFIER, SLASH_ASSIGN, IDENTIFIER, PERCENT_ASSIGN,
+            IDENTIFIER, AND_ASSIGN, IDENTIFIER, OR_ASSIGN, IDENTIFIER, CARET_ASSIGN, IDENTIFIER
+        ]);
+        assert_eq!(types("p->x => q - >"), vec![IDENTIFIER, THIN_ARROW, IDENTIFIER, ARROW, IDENTIFIER, MINUS, GT]);
+    }
+
+    #[test]
+    fn splits_paths_and_members() {
+        use TokenType::*;
+        assert_eq!(types("std::io::print"), vec![IDENTIFIER, DOUBLE_COLON, IDENTIFIER, DOUBLE_COLON, IDENTIFIER]);
+        assert_eq!(types("a_long_name::b: c"), vec![IDENTIFIER, DOUBLE_COLON, IDENTIFIER, COLON, IDENTIFIER]);
+        assert_eq!(types("point.x.y"), vec![IDENTIFIER, DOT, IDENTIFIER, DOT, IDENTIFIER]);
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod recovery_test {
+    use wind::frontend::{lexer, token::TokenType};
+    use wind::reporter::lex::LexerError;
+
+    fn lex(src: &str) -> (Vec<(TokenType, String)>, Vec<LexerError>) {
+        let mut lexer = lexer::Lexer::new(src, false);
+        let errors = lexer.lex().err().unwrap_or_default();
+        let tokens = lexer.tokens.into_iter().map(|t| (t.token_type, t.literal.into_owned())).collect();
+        (tokens, errors)
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let (_, errors) = lex("let a = 1 $ 2;\nlet b = 0x;\nlet c = \"\\q\";\nlet d = 300u8 @ \"open\nlet e = 'xy';");
+        let found: Vec<(&str, usize)> = errors.iter().map(|e| match e {
+            LexerError::InvalidCharacter { line, .. } => ("character", *line),
+            LexerError::InvalidNumber { line, .. } => ("number", *line),
+            LexerError::InvalidEscape { line, .. } => ("escape", *line),
+            LexerError::NumberOverflow { line, .. } => ("overflow", *line),
+            LexerError::UnterminatedLiteral { line, .. } => ("unterminated", *line),
+            LexerError::InvalidCharLiteral { line, .. } => ("char", *line),
+            other => panic!("unexpected {:?}", other),
+        }).collect();
+        assert_eq!(found, vec![
+            ("character", 1), ("number", 2), ("escape", 3),
+            ("overflow", 4), ("character", 4), ("unterminated", 4), ("char", 5),
+        ]);
+    }
+
+    #[test]
+    fn leaves_error_tokens_and_goes_on() {
+        let (tokens, errors) = lex("a € b 0b12 \"x\nc");
+        assert_eq!(errors.len(), 3);
+        assert_eq!(tokens, vec![
+            (TokenType::IDENTIFIER, "a".to_string()),
+            (TokenType::ERROR, "€".to_string()),
+            (TokenType::IDENTIFIER, "b".to_string()),
+            (TokenType::ERROR, "0b12".to_string()),
+            (TokenType::ERROR, "\"x".to_string()),
+            (TokenType::IDENTIFIER, "c".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn keeps_strings_with_bad_escapes() {
+        let (tokens, errors) = lex(r#"s = "a\qb\x4" ;"#);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(tokens[2], (TokenType::STRING, "a\u{FFFD}b\u{FFFD}4".to_string()));
+        assert_eq!(tokens[3], (TokenType::SEMICOLON, ";".to_string()));
+    }
+
+    #[test]
+    fn covers_unterminated_comments() {
+        let (tokens, errors) = lex("a /* never\nclosed");
+        assert!(matches!(errors.as_slice(), [LexerError::UnterminatedComment { line: 1, column: 3, .. }]));
+        assert_eq!(tokens.last(), Some(&(TokenType::ERROR, "/* never\nclosed".to_string())));
+    }
+}
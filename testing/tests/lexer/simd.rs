@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod simd_test {
+    use wind::frontend::lexer::{self, simd::{self, Class, Level}};
+
+    const CLASSES: [Class; 4] = [Class::Ident, Class::Digits, Class::HexDigits, Class::Whitespace];
+
+    /// Every level this CPU can run.
+    fn levels() -> Vec<Level> {
+        [Level::Scalar, Level::Sse2, Level::Avx2, Level::Avx512].into_iter()
+            .filter(|level| level.width() <= simd::level().width())
+            .collect()
+    }
+
+    #[test]
+    fn every_level_agrees_with_the_scalar_scan() {
+        // Long enough for several 64 byte chunks, with a mismatch in every lane
+        let src: String = "aZ_09fF \t\ng-+".chars().cycle().take(300).collect();
+        let bytes = src.as_bytes();
+        for class in CLASSES {
+            for start in 0..bytes.len() {
+                let expected = simd::scalar::run_len(&bytes[start..], class);
+                for level in levels() {
+                    assert_eq!(simd::run_len_at(level, &bytes[start..], class), expected, "{:?} {:?} at {}", level, class, start);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_first_mismatch_in_every_lane() {
+        for length in 0..200 {
+            let mut bytes = vec![b'x'; 200];
+            bytes[length] = b'.';
+            for level in levels() {
+                assert_eq!(simd::run_len_at(level, &bytes, Class::Ident), length, "{:?}", level);
+                assert_eq!(simd::run_len_at(level, &bytes[..length], Class::Ident), length, "{:?}", level);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bytes_next_to_each_class() {
+        // Neighbours of the ranges, and bytes that fold onto them
+        for byte in [b'/', b':', b'@', b'[', b'`', b'{', b'^', b'\r', b'\x0b', 0x80, 0xC1, 0xFF] {
+            let bytes = [byte; 100];
+            for class in CLASSES {
+                for level in levels() {
+                    assert_eq!(simd::run_len_at(level, &bytes, class), 0, "{:?} {:?} on {:#x}", level, class, byte);
+                }
+            }
+        }
+        for level in levels() {
+            assert_eq!(simd::run_len_at(level, &[b'g'; 100], Class::HexDigits), 0);
+            assert_eq!(simd::run_len_at(level, &[b'G'; 100], Class::HexDigits), 0);
+            assert_eq!(simd::run_len_at(level, &[b'a'; 100], Class::Digits), 0);
+        }
+    }
+
+    #[test]
+    fn skips_long_whitespace_runs() {
+        let src = format!("a{}b\n{}\tc", " ".repeat(100), "\n".repeat(70));
+        let mut lexer = lexer::Lexer::new(&src, false);
+        lexer.lex().unwrap();
+        let positions: Vec<(&str, usize, usize)> = lexer.tokens.iter()
+            .map(|t| (t.literal.as_ref(), t.position.0.start, t.position.1.start))
+            .collect();
+        assert_eq!(positions, vec![("a", 0, 0), ("b", 0, 101), ("c", 71, 1)]);
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    fn uses_sse2_at_least() {
+        assert!(simd::level().width() >= Level::Sse2.width());
+    }
+}
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod sources_test {
+    use wind::frontend::lexer;
+    use wind::frontend::source::{FileId, SourceFile, SourceMap};
+    use wind::reporter::lex::LexerError;
+
+    #[test]
+    fn line_column_lookup() {
+        let file = SourceFile::new("a.sz".to_string(), "ab\nλx\n\nend".to_string());
+        assert_eq!(file.line_count(), 4);
+        assert_eq!(file.line_column(0), (0, 0));
+        assert_eq!(file.line_column(2), (0, 2));
+        assert_eq!(file.line_column(3), (1, 0));
+        // `λ` takes two bytes but one column
+        assert_eq!(file.line_column(5), (1, 1));
+        assert_eq!(file.line_column(7), (2, 0));
+        assert_eq!(file.line_column(11), (3, 3));
+        assert_eq!(file.line(1), "λx");
+        assert_eq!(file.line(2), "");
+        assert_eq!(file.line(3), "end");
+    }
+
+    #[test]
+    fn tokens_and_errors_name_their_file() {
+        let mut map = SourceMap::new();
+        map.add("first.sz".to_string(), "let a = 1;".to_string());
+        let second = map.add("second.sz".to_string(), "let b = 2;\nlet c = $;".to_string());
+        assert_eq!(second, FileId(1));
+        assert_eq!(map.location(second, 19).to_string(), "second.sz:2:9");
+
+        let mut lexer = lexer::Lexer::from_map(&map, second, false);
+        let errors = lexer.lex().unwrap_err();
+        assert!(lexer.tokens.iter().all(|t| t.src == second));
+        assert!(matches!(&errors[..], [LexerError::InvalidCharacter { file, line: 2, column: 9, .. }] if file == "second.sz"));
+        assert!(errors[0].to_string().contains("second.sz:2:9"));
+    }
+
+    #[test]
+    fn invalid_utf8_names_its_file() {
+        let error = lexer::decode("bad.sz", b"ok\n\xFF".to_vec()).unwrap_err();
+        assert!(error.to_string().contains("bad.sz:2:1"));
+    }
+}
@@ -2,25 +2,25 @@
 mod tokenize_test {
     use wind::frontend::{lexer::{self, Lexer}, preprocessor};
 
-    fn prepare(src: &str) -> Lexer {
-        let mut prep_lex_inst = lexer::Lexer::new(src.to_string(), true);
+    /// `src` after preprocessing, for the lexer to borrow.
+    fn prepare(src: &str) -> String {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
         let _ = prep_lex_inst.lex();
 
         let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
-        prep_inst.process();
+        prep_inst.process().unwrap();
 
-        let lex_inst = lexer::Lexer::new(prep_inst.get_processed(), false);
-
-        lex_inst
+        prep_inst.get_processed()
     }
     
     #[test]
     fn does_parse() {
 
-        let mut lexer = prepare("a = 10;");
+        let src = prepare("a = 10;");
+        let mut lexer = Lexer::new(&src, false);
         let _ = lexer.lex();
 
-        assert!(lexer.tokens.len() > 0);
+        assert!(!lexer.tokens.is_empty());
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod unicode_test {
+    use wind::frontend::{lexer, token::{Position, TokenType}};
+    use wind::reporter::lex::LexerError;
+
+    fn lex(src: &str) -> Vec<(TokenType, String, Position)> {
+        let mut lexer = lexer::Lexer::new(src, false);
+        lexer.lex().unwrap();
+        lexer.tokens.into_iter().map(|t| (t.token_type, t.literal.into_owned(), t.position)).collect()
+    }
+
+    #[test]
+    fn lexes_xid_identifiers() {
+        let idents: Vec<String> = lex("naïve = größe + _x9 + λ1 + 変数 + e\u{301};").into_iter()
+            .filter(|(t, _, _)| *t == TokenType::IDENTIFIER)
+            .map(|(_, literal, _)| literal)
+            .collect();
+        // A subscript is no identifier character
+        assert!(lexer::Lexer::new("λ₁", false).lex().is_err());
+        assert_eq!(idents, vec!["naïve", "größe", "_x9", "λ1", "変数", "e\u{301}"]);
+    }
+
+    #[test]
+    fn keeps_strings_intact() {
+        let tokens = lex(r#"s = "héllo, 世界 😀";"#);
+        assert_eq!(tokens[2].1, "héllo, 世界 😀");
+    }
+
+    #[test]
+    fn spans_count_characters_and_bytes() {
+        let tokens = lex("é = 1;\n  ünï + \"ü\"");
+        let spans: Vec<(&str, Position)> = tokens.iter().map(|(_, literal, position)| (literal.as_str(), position.clone())).collect();
+        assert_eq!(spans, vec![
+            ("é", (0..0, 0..0, 0..2)),
+            ("=", (0..0, 2..2, 3..4)),
+            ("1", (0..0, 4..4, 5..6)),
+            (";", (0..0, 5..5, 6..7)),
+            ("ünï", (1..1, 2..4, 10..15)),
+            ("+", (1..1, 6..6, 16..17)),
+            ("ü", (1..1, 8..10, 18..22)),
+        ]);
+    }
+
+    #[test]
+    fn reports_non_identifier_characters() {
+        let mut lexer = lexer::Lexer::new("a = 1;\nb = ü€;", false);
+        match lexer.lex().unwrap_err().as_slice() {
+            [LexerError::InvalidCharacter { character, line, column, text, .. }] => {
+                assert_eq!((*character, *line, *column), ('€', 2, 6));
+                assert_eq!(text, "b = ü€;\n         ^");
+            }
+            other => panic!("expected an invalid character, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        let src = b"fn main() {\n  let x\xff = 1;\n}";
+        assert!(matches!(
+            lexer::Lexer::from_bytes(src, false),
+            Err(LexerError::InvalidUtf8 { offset: 19, line: 2, column: 8, .. })
+        ));
+        assert!(lexer::Lexer::from_bytes("let é = 1;".as_bytes(), false).is_ok());
+    }
+}
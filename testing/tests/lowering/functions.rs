@@ -0,0 +1,277 @@
+#[cfg(test)]
+mod functions_test {
+    use std::collections::LinkedList;
+    use wind::backend::{ir, lowering::Lowering};
+    use wind::frontend::ast::{ASTNode, Expression, Function, Literal, Statement, Variable};
+    use wind::reporter::comp::CompilerError;
+
+    fn add_fn() -> ASTNode {
+        ASTNode::FUNCTION(Function::new(
+            "add".to_string(),
+            String::new(),
+            vec![("a".to_string(), "i32".to_string()), ("b".to_string(), "i32".to_string())],
+            "i32".to_string(),
+            0,
+            vec![
+                Statement::RETURN(Expression::BINARY {
+                    left: Box::new(Expression::VARIABLE(Variable::new("a".to_string()))),
+                    op: "+".to_string(),
+                    right: Box::new(Expression::VARIABLE(Variable::new("b".to_string()))),
+                })
+            ]
+        ))
+    }
+
+    #[test]
+    fn lowers_argument_references() {
+        let nodes: LinkedList<ASTNode> = [add_fn()].into_iter().collect();
+        let tree = Lowering::new(ir::Module::new()).lower(&nodes).unwrap();
+
+        let mut expected_tree = ir::Module::new();
+        let i32_t = expected_tree.resolve_type("i32".to_string()).unwrap();
+        let _ = expected_tree.push(
+            ir::Function::new(
+                "add".to_string(),
+                vec![("a".to_string(), i32_t.clone()), ("b".to_string(), i32_t.clone())],
+                i32_t.clone(),
+                0,
+                vec![
+                    ir::Statement::Return(
+                        ir::Expr::Binary {
+                            op: ir::BinaryOp::Add,
+                            left: Box::new( ir::Expr::Reference(ir::Reference::Argument { index: 0, v_type: i32_t.clone() }) ),
+                            right: Box::new( ir::Expr::Reference(ir::Reference::Argument { index: 1, v_type: i32_t.clone() }) ),
+                        }
+                    )
+                ]
+            )
+        );
+
+        assert_eq!(tree, expected_tree);
+    }
+
+    #[test]
+    fn resolves_forward_calls() {
+        let main = ASTNode::FUNCTION(Function::new(
+            "main".to_string(),
+            String::new(),
+            vec![],
+            "i32".to_string(),
+            ir::flags::FunctionModifer::NoMangle as u16,
+            vec![
+                Statement::RETURN(Expression::CALL {
+                    name: "add".to_string(),
+                    arguments: vec![
+                        Expression::LITERAL(Literal::INTEGER(5)),
+                        Expression::LITERAL(Literal::INTEGER(3)),
+                    ],
+                })
+            ]
+        ));
+        let nodes: LinkedList<ASTNode> = [main, add_fn()].into_iter().collect();
+        let tree = Lowering::new(ir::Module::new()).lower(&nodes).unwrap();
+
+        let body = &tree.functions["main"].body;
+        let Some(ir::Statement::Return(ir::Expr::Call(call))) = body.first() else {
+            panic!("Expected a returned call, found {:?}", body);
+        };
+        assert_eq!(call.reference.mangled, tree.functions["add"].mangled);
+        assert_eq!(call.arguments, vec![
+            ir::Expr::Literal(ir::Literal::Int(5)),
+            ir::Expr::Literal(ir::Literal::Int(3)),
+        ]);
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let broken = ASTNode::FUNCTION(Function::new(
+            "broken".to_string(),
+            String::new(),
+            vec![],
+            "i32".to_string(),
+            0,
+            vec![
+                Statement::EXPRESSION(Expression::VARIABLE(Variable::new("missing".to_string()))),
+                Statement::RETURN(Expression::CALL { name: "nowhere".to_string(), arguments: vec![] }),
+            ]
+        ));
+        let untyped = ASTNode::FUNCTION(Function::new(
+            "untyped".to_string(), String::new(), vec![], "int128".to_string(), 0, vec![]
+        ));
+        let nodes: LinkedList<ASTNode> = [broken, untyped].into_iter().collect();
+        let errors = Lowering::new(ir::Module::new()).lower(&nodes).unwrap_err();
+
+        assert!(matches!(errors[0], CompilerError::TypeNotFound { .. }));
+        assert!(matches!(errors[1], CompilerError::VariableNotFound { .. }));
+        assert!(matches!(errors[2], CompilerError::FunctionNotFound { .. }));
+    }
+
+    #[test]
+    fn lowers_control_flow() {
+        let a = || Expression::VARIABLE(Variable::new("a".to_string()));
+        let clamp = |tail: Vec<Statement>| {
+            let mut body = vec![
+                Statement::WHILE {
+                    condition: Expression::BINARY {
+                        left: Box::new(a()),
+                        op: ">".to_string(),
+                        right: Box::new(Expression::LITERAL(Literal::INTEGER(10))),
+                    },
+                    body: vec![Statement::BREAK],
+                },
+            ];
+            body.extend(tail);
+            let function = Function::new(
+                "clamp".to_string(), String::new(), vec![("a".to_string(), "i32".to_string())], "i32".to_string(), 0, body
+            );
+            [ASTNode::FUNCTION(function)].into_iter().collect::<LinkedList<ASTNode>>()
+        };
+
+        let tree = Lowering::new(ir::Module::new()).lower(&clamp(vec![])).unwrap();
+        let i32_t = tree.resolve_type("i32".to_string()).unwrap();
+        assert_eq!(tree.functions["clamp"].body, vec![
+            ir::Statement::While {
+                cond: ir::Expr::Binary {
+                    op: ir::BinaryOp::Gt,
+                    left: Box::new( ir::Expr::Reference(ir::Reference::Argument { index: 0, v_type: i32_t }) ),
+                    right: Box::new( ir::Expr::Literal( ir::Literal::Int(10) ) ),
+                },
+                body: vec![ ir::Statement::Break ],
+            },
+        ]);
+
+        let misplaced = Statement::IF { condition: a(), then: vec![Statement::RETURN(a())], otherwise: vec![Statement::CONTINUE] };
+        let errors = Lowering::new(ir::Module::new()).lower(&clamp(vec![misplaced])).unwrap_err();
+        assert!(matches!(&errors[..], [CompilerError::OutsideLoop { keyword }] if keyword == "continue"));
+    }
+
+    #[test]
+    fn allocates_locals() {
+        let let_ = |name: &str, data_type: Option<&str>, value: Option<Expression>, constant: bool| Statement::LET {
+            name: name.to_string(), data_type: data_type.map(|t| t.to_string()), value, constant
+        };
+        let locals = |tail: Vec<Statement>| {
+            let mut body = vec![
+                let_("flag", Some("u8"), None, false),
+                let_("big", Some("i64"), Some(Expression::LITERAL(Literal::INTEGER(7))), false),
+                let_("limit", None, Some(Expression::LITERAL(Literal::INTEGER(3))), true),
+                Statement::ASSIGN { name: "big".to_string(), op: "+=".to_string(), value: Expression::LITERAL(Literal::INTEGER(1)) },
+            ];
+            body.extend(tail);
+            let function = Function::new("locals".to_string(), String::new(), vec![], "void".to_string(), 0, body);
+            [ASTNode::FUNCTION(function)].into_iter().collect::<LinkedList<ASTNode>>()
+        };
+
+        let tree = Lowering::new(ir::Module::new()).lower(&locals(vec![])).unwrap();
+        let function = &tree.functions["locals"];
+        let i64_t = tree.resolve_type("i64".to_string()).unwrap();
+        let big = ir::Reference::Local { offset: 16, v_type: i64_t };
+        assert_eq!(function.body[1], ir::Statement::Let { reference: big.clone(), value: Some(ir::Expr::Literal(ir::Literal::Int(7))) });
+        assert_eq!(function.body[3], ir::Statement::CompoundAssign {
+            op: ir::BinaryOp::Add, target: big, value: ir::Expr::Literal(ir::Literal::Int(1))
+        });
+        // u8 at 1, i64 aligned at 16, i32 at 20
+        assert_eq!(function.stack_size, 20);
+
+        let errors = Lowering::new(ir::Module::new()).lower(&locals(vec![
+            Statement::ASSIGN { name: "limit".to_string(), op: "=".to_string(), value: Expression::LITERAL(Literal::INTEGER(4)) },
+            let_("unknown", None, None, false),
+        ])).unwrap_err();
+        assert!(matches!(errors[0], CompilerError::AssignToConst { .. }));
+        assert!(matches!(errors[1], CompilerError::UntypedVariable { .. }));
+    }
+
+    #[test]
+    fn lowers_unary_operators() {
+        let a = || Box::new(Expression::VARIABLE(Variable::new("a".to_string())));
+        let unary = |op: &str| Expression::UNARY { op: op.to_string(), operand: a() };
+        let function = |name: &str, return_type: &str, value| ASTNode::FUNCTION(Function::new(
+            name.to_string(), String::new(), vec![("a".to_string(), "i32".to_string())], return_type.to_string(), 0,
+            vec![Statement::RETURN(value)]
+        ));
+        let nodes: LinkedList<ASTNode> = [function("neg", "i32", unary("-")), function("not", "u8", unary("!"))].into_iter().collect();
+        let module = Lowering::new(ir::Module::new()).lower(&nodes).unwrap();
+
+        let i32_t = ir::DataType::Scalar { size: 4, signed: true };
+        let arg = || Box::new(ir::Expr::Reference(ir::Reference::Argument { index: 0, v_type: i32_t.clone() }));
+        let zero = || Box::new(ir::Expr::Literal(ir::Literal::Int(0)));
+        assert_eq!(module.functions["neg"].body, vec![
+            ir::Statement::Return(ir::Expr::Binary { op: ir::BinaryOp::Sub, left: zero(), right: arg() })
+        ]);
+        assert_eq!(module.functions["not"].body, vec![
+            ir::Statement::Return(ir::Expr::Binary { op: ir::BinaryOp::Eq, left: arg(), right: zero() })
+        ]);
+
+        let nodes: LinkedList<ASTNode> = [function("addr", "i32", unary("&"))].into_iter().collect();
+        let errors = Lowering::new(ir::Module::new()).lower(&nodes).unwrap_err();
+        assert!(matches!(&errors[..], [CompilerError::UnsupportedOperator { op }] if op == "&"));
+    }
+
+    #[test]
+    fn suffixes_fix_literal_types() {
+        let typed = |value, data_type: &str| Expression::LITERAL(Literal::TYPED {
+            literal: Box::new(Literal::INTEGER(value)), data_type: data_type.to_string()
+        });
+        let function = |name: &str, value| ASTNode::FUNCTION(Function::new(
+            name.to_string(), String::new(), vec![("a".to_string(), "u8".to_string())], "u8".to_string(), 0,
+            vec![Statement::RETURN(value)]
+        ));
+        let sum = Expression::BINARY {
+            left: Box::new(typed(200, "u8")),
+            op: "+".to_string(),
+            right: Box::new(Expression::VARIABLE(Variable::new("a".to_string()))),
+        };
+        let nodes: LinkedList<ASTNode> = [function("ok", sum), function("wide", typed(1, "i64"))].into_iter().collect();
+        let errors = Lowering::new(ir::Module::new()).lower(&nodes).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], CompilerError::TypeMismatch { expected, found } if expected == "u8" && found == "i64"));
+
+        // Operands of a comparison expect no type, so the first suffix is kept as a cast
+        let compare = Expression::BINARY { left: Box::new(typed(u64::MAX as i64, "u64")), op: ">".to_string(), right: Box::new(typed(1, "u64")) };
+        let nodes: LinkedList<ASTNode> = [function("compare", compare)].into_iter().collect();
+        let tree = Lowering::new(ir::Module::new()).lower(&nodes).unwrap();
+        let u64_t = tree.resolve_type("u64".to_string()).unwrap();
+        let cast = |v: u64| Box::new(ir::Expr::Cast { value: Box::new(ir::Expr::Literal(ir::Literal::Int(v))), v_type: u64_t.clone() });
+        assert_eq!(tree.functions["compare"].body, vec![ir::Statement::Return(ir::Expr::Binary {
+            op: ir::BinaryOp::Gt, left: cast(u64::MAX), right: Box::new(ir::Expr::Literal(ir::Literal::Int(1)))
+        })]);
+    }
+
+    #[test]
+    fn literals_fit_their_types() {
+        let typed = |value, data_type: &str| Expression::LITERAL(Literal::TYPED {
+            literal: Box::new(Literal::INTEGER(value)), data_type: data_type.to_string()
+        });
+        let negated = |literal| Expression::UNARY { op: "-".to_string(), operand: Box::new(literal) };
+        let function = |name: &str, return_type: &str, value| ASTNode::FUNCTION(Function::new(
+            name.to_string(), String::new(), vec![], return_type.to_string(), 0,
+            vec![Statement::RETURN(value)]
+        ));
+        let nodes: LinkedList<ASTNode> = [
+            function("max", "i32", typed(2147483647, "i32")),
+            function("min", "i32", negated(typed(2147483648, "i32"))),
+            function("wide", "i64", Expression::LITERAL(Literal::INTEGER(5000000000))),
+            function("unsigned", "u64", Expression::LITERAL(Literal::INTEGER(u64::MAX as i64))),
+        ].into_iter().collect();
+        assert!(Lowering::new(ir::Module::new()).lower(&nodes).is_ok());
+
+        let nodes: LinkedList<ASTNode> = [
+            function("past_max", "i32", typed(2147483648, "i32")),
+            function("past_min", "i8", negated(typed(129, "i8"))),
+            function("unsuffixed", "i32", Expression::LITERAL(Literal::INTEGER(5000000000))),
+            function("byte", "u8", Expression::LITERAL(Literal::INTEGER(256))),
+        ].into_iter().collect();
+        let errors = Lowering::new(ir::Module::new()).lower(&nodes).unwrap_err();
+        let mut errors: Vec<(String, String)> = errors.iter().map(|e| match e {
+            CompilerError::LiteralOutOfRange { literal, v_type } => (literal.clone(), v_type.clone()),
+            other => panic!("Expected CompilerError::LiteralOutOfRange, found {:?}", other),
+        }).collect();
+        errors.sort();
+        assert_eq!(errors, vec![
+            ("129".to_string(), "i8".to_string()),
+            ("2147483648".to_string(), "i32".to_string()),
+            ("256".to_string(), "u8".to_string()),
+            ("5000000000".to_string(), "i32".to_string()),
+        ]);
+    }
+}
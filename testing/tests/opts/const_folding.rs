@@ -94,4 +94,142 @@ mod const_folding_test {
 
         assert_eq!(tree, expected_tree);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn comparison_const_fold() {
+        let mut tree = ir::Module::new();
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![],
+                tree.resolve_type("u8".to_string()).unwrap(),
+                ir::flags::FunctionModifer::NoMangle as u16,
+                vec![
+                    ir::Statement::Return(
+                        ir::Expr::Binary {
+                            op: ir::BinaryOp::Gt,
+                            left: Box::new( ir::Expr::Literal( ir::Literal::Int(-1i64 as u64) ) ),
+                            right: Box::new( ir::Expr::Literal( ir::Literal::Int(3) ) ),
+                        }
+                    )
+                ]
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::folding::ConstantFolding::new());
+        opt.run_all(&mut tree);
+
+        assert_eq!(tree.functions["main"].body, vec![
+            ir::Statement::Return( ir::Expr::Literal( ir::Literal::Bool(false) ) )
+        ]);
+    }
+
+    #[test]
+    fn bitwise_const_fold() {
+        let int = |v: u64| Box::new(ir::Expr::Literal(ir::Literal::Int(v)));
+        let mut tree = ir::Module::new();
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![],
+                tree.resolve_type("u64".to_string()).unwrap(),
+                ir::flags::FunctionModifer::NoMangle as u16,
+                vec![
+                    // (17 % 5) | (12 ^ 10)
+                    ir::Statement::Return(
+                        ir::Expr::Binary {
+                            op: ir::BinaryOp::Or,
+                            left: Box::new(ir::Expr::Binary { op: ir::BinaryOp::Rem, left: int(17), right: int(5) }),
+                            right: Box::new(ir::Expr::Binary { op: ir::BinaryOp::Xor, left: int(12), right: int(10) }),
+                        }
+                    ),
+                    // Left for the target
+                    ir::Statement::Return(ir::Expr::Binary { op: ir::BinaryOp::Rem, left: int(1), right: int(0) }),
+                ]
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::folding::ConstantFolding::new());
+        opt.run_all(&mut tree);
+
+        assert_eq!(tree.functions["main"].body, vec![
+            ir::Statement::Return(ir::Expr::Literal(ir::Literal::Int(2 | 6))),
+            ir::Statement::Return(ir::Expr::Binary { op: ir::BinaryOp::Rem, left: int(1), right: int(0) }),
+        ]);
+    }
+
+    /// `expr` after folding, returned from a function of type `v_type`.
+    fn fold_returned(v_type: &str, expr: ir::Expr) -> ir::Expr {
+        let mut tree = ir::Module::new();
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![],
+                tree.resolve_type(v_type.to_string()).unwrap(),
+                ir::flags::FunctionModifer::NoMangle as u16,
+                vec![ir::Statement::Return(expr)]
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::folding::ConstantFolding::new());
+        opt.run_all(&mut tree);
+
+        match &tree.functions["main"].body[..] {
+            [ir::Statement::Return(expr)] => expr.clone(),
+            body => panic!("Expected a return, found {:?}", body),
+        }
+    }
+
+    #[test]
+    fn typed_const_fold() {
+        let int = |v: i64| Box::new(ir::Expr::Literal(ir::Literal::Int(v as u64)));
+        let binary = |op, left, right| ir::Expr::Binary { op, left, right };
+
+        // Signed types divide, take remainders and shift arithmetically
+        assert_eq!(fold_returned("i64", binary(ir::BinaryOp::Rem, int(-7), int(3))), *int(-1));
+        assert_eq!(fold_returned("i64", binary(ir::BinaryOp::Div, int(-7), int(2))), *int(-3));
+        assert_eq!(fold_returned("i32", binary(ir::BinaryOp::Div, int(7), int(-2))), *int(-3));
+        assert_eq!(fold_returned("i64", binary(ir::BinaryOp::Shr, int(-16), int(2))), *int(-4));
+        // Unsigned ones wrap at their width and shift logically
+        let minus_one = Box::new(binary(ir::BinaryOp::Sub, int(0), int(1)));
+        assert_eq!(fold_returned("u32", binary(ir::BinaryOp::Div, minus_one, int(2))), *int(2147483647));
+        assert_eq!(fold_returned("u64", binary(ir::BinaryOp::Shr, int(-1), int(60))), *int(15));
+        assert_eq!(fold_returned("u64", binary(ir::BinaryOp::Rem, int(-1), int(10))), *int(5));
+        assert_eq!(fold_returned("i8", binary(ir::BinaryOp::Add, int(100), int(100))), *int(-56));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::Mul, int(16), int(17))), *int(16));
+
+        // Overflowing division and oversized shifts are left for the target
+        let overflow = binary(ir::BinaryOp::Div, int(i64::MIN), int(-1));
+        assert_eq!(fold_returned("i64", overflow.clone()), overflow);
+        let oversized = binary(ir::BinaryOp::Shl, int(1), int(32));
+        assert_eq!(fold_returned("u32", oversized.clone()), oversized);
+    }
+
+    #[test]
+    fn compare_const_fold() {
+        let int = |v: i64| Box::new(ir::Expr::Literal(ir::Literal::Int(v as u64)));
+        let typed = |v: i64, v_type: &str| Box::new(ir::Expr::Cast {
+            value: int(v), v_type: ir::Module::new().resolve_type(v_type.to_string()).unwrap()
+        });
+        let binary = |op, left, right| ir::Expr::Binary { op, left, right };
+        let bool = |v| ir::Expr::Literal(ir::Literal::Bool(v));
+
+        // Suffixed literals compare in their own type, untyped ones as i32
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::Gt, typed(-1, "u64"), typed(1, "u64"))), bool(true));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::Lt, typed(-1, "u32"), int(1))), bool(false));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::Lt, typed(-1, "i64"), int(1))), bool(true));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::Ge, int(-1), int(1))), bool(false));
+        // Arithmetic on them keeps the type for the comparison around it
+        let sum = Box::new(binary(ir::BinaryOp::Add, typed(-2, "u16"), int(1)));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::Gt, sum, int(1))), bool(true));
+
+        // Logical operators test each side in its own type, and a constant left
+        // side that decides the result drops the right one
+        let x = || Box::new(ir::Expr::Reference(ir::Reference::Local { offset: 4, v_type: ir::DataType::Scalar { size: 4, signed: true } }));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::LogicalAnd, typed(256, "u16"), typed(256, "u64"))), bool(true));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::LogicalOr, int(2), x())), bool(true));
+        assert_eq!(fold_returned("u8", binary(ir::BinaryOp::LogicalAnd, int(0), x())), bool(false));
+        let undecided = binary(ir::BinaryOp::LogicalAnd, int(1), x());
+        assert_eq!(fold_returned("u8", undecided.clone()), undecided);
+    }
+}
@@ -97,4 +97,85 @@ mod dead_code_test {
 
         assert_eq!(tree, expected_tree);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn nested_dead_code() {
+        let mut tree = ir::Module::new();
+        let i32_t = tree.resolve_type("i32".to_string()).unwrap();
+        let flag = ir::Expr::Reference(ir::Reference::Argument { index: 0, v_type: i32_t.clone() });
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![("flag".to_string(), i32_t.clone())],
+                i32_t.clone(),
+                ir::flags::FunctionModifer::NoMangle as u16,
+                vec![
+                    ir::Statement::While {
+                        cond: flag.clone(),
+                        body: vec![
+                            ir::Statement::Break,
+                            ir::Statement::Return( ir::Expr::Literal( ir::Literal::Int(1) ) ),
+                        ]
+                    },
+                    ir::Statement::If {
+                        cond: ir::Expr::Binary {
+                            op: ir::BinaryOp::Lt,
+                            left: Box::new( ir::Expr::Literal( ir::Literal::Int(1) ) ),
+                            right: Box::new( ir::Expr::Literal( ir::Literal::Int(2) ) ),
+                        },
+                        then: vec![ ir::Statement::Return( ir::Expr::Literal( ir::Literal::Int(2) ) ) ],
+                        otherwise: vec![ ir::Statement::Return( ir::Expr::Literal( ir::Literal::Int(3) ) ) ],
+                    },
+                    ir::Statement::Return( ir::Expr::Literal( ir::Literal::Int(4) ) ),
+                ]
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::folding::ConstantFolding::new());
+        opt.add_pass(opt::pipeline::dead_code::DeadCode::new());
+        opt.run_all(&mut tree);
+
+        assert_eq!(tree.functions["main"].body, vec![
+            ir::Statement::While {
+                cond: flag,
+                body: vec![ ir::Statement::Break ]
+            },
+            ir::Statement::Block(vec![
+                ir::Statement::Return( ir::Expr::Literal( ir::Literal::Int(2) ) ),
+            ]),
+        ]);
+    }
+
+    #[test]
+    fn calls_not_dead() {
+        let mut tree = ir::Module::new();
+        let i32_t = tree.resolve_type("i32".to_string()).unwrap();
+        let bump = ir::Expr::Call(ir::FunctionCall {
+            reference: ir::Function::new("bump".to_string(), vec![], i32_t.clone(), 0, vec![]),
+            arguments: vec![],
+        });
+        let x = ir::Expr::Reference(ir::Reference::Local { offset: 4, v_type: i32_t.clone() });
+        let plus_one = |expr| ir::Expr::Binary {
+            op: ir::BinaryOp::Add,
+            left: Box::new( expr ),
+            right: Box::new( ir::Expr::Literal( ir::Literal::Int(1) ) ),
+        };
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![],
+                i32_t.clone(),
+                ir::flags::FunctionModifer::NoMangle as u16,
+                vec![
+                    ir::Statement::Expr(plus_one(bump.clone())),
+                    ir::Statement::Expr(plus_one(x)),
+                ]
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::dead_code::DeadCode::new());
+        opt.run_all(&mut tree);
+
+        assert_eq!(tree.functions["main"].body, vec![ir::Statement::Expr(plus_one(bump))]);
+    }
+}
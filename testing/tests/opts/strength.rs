@@ -299,4 +299,132 @@ mod strength_reduction_test {
 
         assert_eq!(tree, expected_tree);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn nested_reduction() {
+        let mut tree = ir::Module::new();
+        let i32_t = tree.resolve_type("i32".to_string()).unwrap();
+        let x = ir::Expr::Reference(ir::Reference::Argument { index: 0, v_type: i32_t.clone() });
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![("x".to_string(), i32_t.clone())],
+                i32_t.clone(),
+                ir::flags::FunctionModifer::NoMangle as u16,
+                vec![
+                    ir::Statement::While {
+                        cond: x.clone(),
+                        body: vec![
+                            ir::Statement::Return(
+                                ir::Expr::Binary {
+                                    op: ir::BinaryOp::Mul,
+                                    left: Box::new( x.clone() ),
+                                    right: Box::new( ir::Expr::Literal( ir::Literal::Int(8) ) ),
+                                }
+                            ),
+                        ]
+                    },
+                ]
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::strength::Strength::new());
+        opt.run_all(&mut tree);
+
+        assert_eq!(tree.functions["main"].body, vec![
+            ir::Statement::While {
+                cond: x.clone(),
+                body: vec![
+                    ir::Statement::Return(
+                        ir::Expr::Binary {
+                            op: ir::BinaryOp::Shl,
+                            left: Box::new( x ),
+                            right: Box::new( ir::Expr::Literal( ir::Literal::Int(3) ) ),
+                        }
+                    ),
+                ]
+            },
+        ]);
+    }
+
+    #[test]
+    fn pow2_shift_amounts() {
+        // (x * 16) / 32 shifts by the exponents, not by half the constants
+        let mut tree = ir::Module::new();
+        let u32_t = tree.resolve_type("u32".to_string()).unwrap();
+        let x = || Box::new( ir::Expr::Reference(ir::Reference::Local { offset: 4, v_type: tree.resolve_type("u32".to_string()).unwrap() }) );
+        let body = vec![
+            ir::Statement::Return(
+                ir::Expr::Binary {
+                    op: ir::BinaryOp::Div,
+                    left: Box::new( ir::Expr::Binary {
+                        op: ir::BinaryOp::Mul,
+                        left: x(),
+                        right: Box::new( ir::Expr::Literal( ir::Literal::Int(16) ) ),
+                    } ),
+                    right: Box::new( ir::Expr::Literal( ir::Literal::Int(32) ) ),
+                }
+            ),
+        ];
+        let expected = vec![
+            ir::Statement::Return(
+                ir::Expr::Binary {
+                    op: ir::BinaryOp::Shr,
+                    left: Box::new( ir::Expr::Binary {
+                        op: ir::BinaryOp::Shl,
+                        left: x(),
+                        right: Box::new( ir::Expr::Literal( ir::Literal::Int(4) ) ),
+                    } ),
+                    right: Box::new( ir::Expr::Literal( ir::Literal::Int(5) ) ),
+                }
+            ),
+        ];
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![],
+                u32_t,
+                ir::flags::FunctionModifer::NoMangle as u16,
+                body
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::strength::Strength::new());
+        opt.run_all(&mut tree);
+
+        assert_eq!(tree.functions["main"].body, expected);
+    }
+
+    #[test]
+    fn side_effects_kept() {
+        // Calls are neither dropped nor repeated, division by zero is left alone
+        let mut tree = ir::Module::new();
+        let i32_t = tree.resolve_type("i32".to_string()).unwrap();
+        let bump = || Box::new( ir::Expr::Call(ir::FunctionCall {
+            reference: ir::Function::new("bump".to_string(), vec![], i32_t.clone(), 0, vec![]),
+            arguments: vec![],
+        }) );
+        let x = Box::new( ir::Expr::Reference(ir::Reference::Local { offset: 4, v_type: i32_t.clone() }) );
+        let int = |v| Box::new( ir::Expr::Literal( ir::Literal::Int(v) ) );
+        let body = vec![
+            ir::Statement::Expr( ir::Expr::Binary { op: ir::BinaryOp::Mul, left: bump(), right: int(3) } ),
+            ir::Statement::Expr( ir::Expr::Binary { op: ir::BinaryOp::Mul, left: bump(), right: int(0) } ),
+            ir::Statement::Return( ir::Expr::Binary { op: ir::BinaryOp::Div, left: x, right: int(0) } ),
+        ];
+        let _ = tree.push(
+            ir::Function::new(
+                "main".to_string(),
+                vec![],
+                i32_t.clone(),
+                ir::flags::FunctionModifer::NoMangle as u16,
+                body.clone()
+            )
+        );
+        let mut opt = opt::PassManager::new();
+        opt.add_pass(opt::pipeline::folding::ConstantFolding::new());
+        opt.add_pass(opt::pipeline::strength::Strength::new());
+        opt.run_all(&mut tree);
+
+        assert_eq!(tree.functions["main"].body, body);
+    }
+}
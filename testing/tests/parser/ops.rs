@@ -1,26 +1,26 @@
 #[cfg(test)]
 mod simple_parsing_test {
     use wind::frontend::{ast::{ASTNode, Expression, Literal}, lexer::{self, Lexer}, parser::Parser, preprocessor};
-    fn prepare(src: &str) -> Lexer {
-        let mut prep_lex_inst = lexer::Lexer::new(src.to_string(), true);
+    /// `src` after preprocessing, for the lexer to borrow.
+    fn prepare(src: &str) -> String {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
         let _ = prep_lex_inst.lex();
 
         let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
-        prep_inst.process();
+        prep_inst.process().unwrap();
 
-        let lex_inst = lexer::Lexer::new(prep_inst.get_processed(), false);
-
-        lex_inst
+        prep_inst.get_processed()
     }
 
     #[test]
     fn parses_simple_operations_correctly() {
 
-        let mut lexer = prepare("var1 = 10;");
+        let src = prepare("var1 = 10;");
+        let mut lexer = Lexer::new(&src, false);
         let _ = lexer.lex();
 
         let mut parser = Parser::new(&lexer);
-        let nodes = parser.parse_all_tokens();
+        let nodes = parser.parse_all_tokens().unwrap();
         let mut iterator = nodes.iter();
         let first_line = iterator.next().unwrap();
 
@@ -47,4 +47,112 @@ mod simple_parsing_test {
             panic!("Expected ASTNode::EXPRESSION(Expression::BINARY)");
         }
     }
-}
\ No newline at end of file
+
+    fn parse(src: &str) -> Expression {
+        let src = prepare(src);
+        let mut lexer = Lexer::new(&src, false);
+        let _ = lexer.lex();
+        let mut parser = Parser::new(&lexer);
+        let nodes = parser.parse_all_tokens().unwrap();
+        match nodes.front() {
+            Some(ASTNode::EXPRESSION(expr)) => expr.clone(),
+            other => panic!("Expected ASTNode::EXPRESSION, found {:?}", other),
+        }
+    }
+
+    /// Fully parenthesised form of `expr`, e.g. `(+ a (* b c))`.
+    fn sexpr(expr: &Expression) -> String {
+        match expr {
+            Expression::LITERAL(Literal::INTEGER(v)) => v.to_string(),
+            Expression::LITERAL(literal) => format!("{:?}", literal),
+            Expression::VARIABLE(var) => var.name.clone(),
+            Expression::BINARY { left, op, right } => format!("({} {} {})", op, sexpr(left), sexpr(right)),
+            Expression::UNARY { op, operand } => format!("({} {})", op, sexpr(operand)),
+            Expression::CALL { name, arguments } => {
+                let arguments: Vec<String> = arguments.iter().map(sexpr).collect();
+                format!("{}({})", name, arguments.join(", "))
+            }
+        }
+    }
+
+    fn assert_parses(src: &str, expected: &str) {
+        assert_eq!(sexpr(&parse(src)), expected, "while parsing `{}`", src);
+    }
+
+    #[test]
+    fn multiplicative_binds_tighter_than_additive() {
+        assert_parses("a + b * c;", "(+ a (* b c))");
+        assert_parses("a * b + c;", "(+ (* a b) c)");
+        assert_parses("a - b / c - d;", "(- (- a (/ b c)) d)");
+        assert_parses("a * b * c / d;", "(/ (* (* a b) c) d)");
+    }
+
+    #[test]
+    fn additive_binds_tighter_than_relational() {
+        assert_parses("a + 1 < b - 1;", "(< (+ a 1) (- b 1))");
+        assert_parses("a <= b;", "(<= a b)");
+        assert_parses("a > b;", "(> a b)");
+        assert_parses("a >= b;", "(>= a b)");
+    }
+
+    #[test]
+    fn relational_binds_tighter_than_equality() {
+        assert_parses("a < b == c > d;", "(== (< a b) (> c d))");
+        assert_parses("a == b != c;", "(!= (== a b) c)");
+    }
+
+    #[test]
+    fn equality_binds_tighter_than_logical_and() {
+        assert_parses("a == 1 && b != 2;", "(&& (== a 1) (!= b 2))");
+        assert_parses("a && b && c;", "(&& (&& a b) c)");
+    }
+
+    #[test]
+    fn logical_and_binds_tighter_than_logical_or() {
+        assert_parses("a || b && c;", "(|| a (&& b c))");
+        assert_parses("a && b || c && d;", "(|| (&& a b) (&& c d))");
+        assert_parses("a || b || c;", "(|| (|| a b) c)");
+    }
+
+    #[test]
+    fn assignment_is_right_associative() {
+        assert_parses("a = b = c;", "(= a (= b c))");
+        assert_parses("a += b -= c + 1;", "(+= a (-= b (+ c 1)))");
+        assert_parses("a = b || c;", "(= a (|| b c))");
+    }
+
+    #[test]
+    fn prefix_operators_bind_tightest() {
+        assert_parses("-a * b;", "(* (- a) b)");
+        assert_parses("a - -b;", "(- a (- b))");
+        assert_parses("!a && b;", "(&& (! a) b)");
+        assert_parses("!!a;", "(! (! a))");
+        assert_parses("*p + &x;", "(+ (* p) (& x))");
+        assert_parses("-f(a, -1);", "(- f(a, (- 1)))");
+    }
+
+    #[test]
+    fn parentheses_group() {
+        assert_parses("(a + b) * c;", "(* (+ a b) c)");
+        assert_parses("a * (b + c);", "(* a (+ b c))");
+        assert_parses("-(a - b);", "(- (- a b))");
+        assert_parses("(a = b) = c;", "(= (= a b) c)");
+    }
+
+    #[test]
+    fn bitwise_operators_sit_between_comparisons_and_arithmetic() {
+        assert_parses("a | b ^ c & d;", "(| a (^ b (& c d)))");
+        assert_parses("a & b == c;", "(== (& a b) c)");
+        assert_parses("a < b | c;", "(< a (| b c))");
+        assert_parses("a & 1 << b + 2;", "(& a (<< 1 (+ b 2)))");
+        assert_parses("a >> 1 >> 2;", "(>> (>> a 1) 2)");
+        assert_parses("a % b * c + d % e;", "(+ (* (% a b) c) (% d e))");
+        assert_parses("~a & -b;", "(& (~ a) (- b))");
+    }
+
+    #[test]
+    fn compound_assignments_are_right_associative() {
+        assert_parses("a *= b /= c %= d;", "(*= a (/= b (%= c d)))");
+        assert_parses("a &= b |= c ^= d <<= e >>= f;", "(&= a (|= b (^= c (<<= d (>>= e f)))))");
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod recovery_test {
+    use wind::frontend::{ast::{ASTNode, Statement}, lexer::{self, Lexer}, parser::Parser, preprocessor, token::TokenType};
+    use wind::reporter::parse::ParserError;
+
+    /// `src` after preprocessing, for `lex` to borrow.
+    fn prepare(src: &str) -> String {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        let _ = prep_lex_inst.lex();
+
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+
+        prep_inst.get_processed()
+    }
+
+    fn lex(src: &str) -> Lexer<'_> {
+        let mut lex_inst = lexer::Lexer::new(src, false);
+        let _ = lex_inst.lex();
+        lex_inst
+    }
+
+    #[test]
+    fn reports_every_syntax_error() {
+        let src = prepare("
+fn main() => i32 {
+    let a = ;
+    let b: i32 = 1
+    let c = 2;
+    if c { return ); }
+    return c;
+}
+fn (x: i32) {}
+fn ok() => i32 { return 1; }
+");
+        let lexer = lex(&src);
+        let mut parser = Parser::new(&lexer);
+        let errors = parser.parse_all_tokens().unwrap_err();
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+
+        // `let a = ;`
+        assert!(matches!(&errors[0], ParserError::UnexpectedToken { found, position, .. } if found == ";" && position.0.start == 2));
+        // Missing `;` before `let c`, which is skipped along with its statement
+        assert!(matches!(&errors[1], ParserError::UnexpectedToken { expected, found, .. } if *expected == vec![TokenType::SEMICOLON] && found == "let"));
+        // `return )` inside the nested block
+        assert!(matches!(&errors[2], ParserError::UnexpectedToken { found, position, .. } if found == ")" && position.0.start == 5));
+        // Nameless function
+        assert!(matches!(&errors[3], ParserError::UnexpectedToken { expected, .. } if *expected == vec![TokenType::IDENTIFIER]));
+
+        // Everything that parsed is still available
+        let nodes = parser.get_nodes();
+        assert_eq!(nodes.len(), 2);
+        match nodes.front() {
+            Some(ASTNode::FUNCTION(main)) => assert!(matches!(main.body.as_slice(), [Statement::IF { .. }, Statement::RETURN(_)])),
+            other => panic!("Expected ASTNode::FUNCTION, found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lists_expected_tokens() {
+        let src = prepare("fn f(a: i32 b: i32) {}");
+        let lexer = lex(&src);
+        let mut parser = Parser::new(&lexer);
+        let errors = parser.parse_all_tokens().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], ParserError::UnexpectedToken { expected, found, .. }
+            if *expected == vec![TokenType::COMMA, TokenType::RPAREN] && found == "b"));
+        assert!(errors[0].to_string().contains("one of COMMA or RPAREN"));
+    }
+
+    #[test]
+    fn reports_unterminated_blocks() {
+        let src = prepare("fn f() { return 1;");
+        let lexer = lex(&src);
+        let mut parser = Parser::new(&lexer);
+        let errors = parser.parse_all_tokens().unwrap_err();
+        assert_eq!(errors, vec![ParserError::unexpected_eof(vec![TokenType::RBRACE])]);
+    }
+
+    #[test]
+    fn rejects_operators_function_bodies_lack() {
+        let src = prepare("
+fn f(p: i32) => i32 {
+    let a = p = 1;
+    a += *p;
+    return &a;
+}
+");
+        let lexer = lex(&src);
+        let mut parser = Parser::new(&lexer);
+        let errors = parser.parse_all_tokens().unwrap_err();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        let unsupported = |error: &ParserError| match error {
+            ParserError::UnsupportedOperator { op, position, .. } => (op.clone(), position.0.start),
+            other => panic!("Expected ParserError::UnsupportedOperator, found {:?}", other),
+        };
+        assert_eq!(unsupported(&errors[0]), ("=".to_string(), 2));
+        assert_eq!(unsupported(&errors[1]), ("*".to_string(), 3));
+        assert_eq!(unsupported(&errors[2]), ("&".to_string(), 4));
+        assert!(errors[0].to_string().starts_with("⚠ Assignment inside an expression is not supported, found `=`"));
+
+        // Outside of a function they still parse, for the lowering to report
+        let src = prepare("a = *p + &x;");
+        let lexer = lex(&src);
+        assert!(Parser::new(&lexer).parse_all_tokens().is_ok());
+    }
+}
@@ -1,53 +1,56 @@
 #[cfg(test)]
 mod simple_parsing_test {
-    use wind::frontend::{lexer::{self, Lexer}, parser::Parser, preprocessor};
+    use wind::frontend::{lexer::{self, Lexer}, parser::Parser, preprocessor, token::TokenType};
+    use wind::reporter::parse::ParserError;
 
-    fn prepare(src: &str) -> Lexer {
-        let mut prep_lex_inst = lexer::Lexer::new(src.to_string(), true);
+    /// `src` after preprocessing, for the lexer to borrow.
+    fn prepare(src: &str) -> String {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
         let _ = prep_lex_inst.lex();
 
         let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
-        prep_inst.process();
+        prep_inst.process().unwrap();
 
-        let lex_inst = lexer::Lexer::new(prep_inst.get_processed(), false);
-
-        lex_inst
+        prep_inst.get_processed()
     }
 
     
     #[test]
     fn does_parse() {
 
-        let mut lexer = prepare("var1 = 10;");
+        let src = prepare("var1 = 10;");
+        let mut lexer = Lexer::new(&src, false);
         let _ = lexer.lex();
 
 
         let mut parser = Parser::new(&lexer);
 
-        let nodes = parser.parse_all_tokens();
+        let nodes = parser.parse_all_tokens().unwrap();
 
-        assert!(nodes.len() > 0);
+        assert!(!nodes.is_empty());
     }
 
     #[test]
-    #[should_panic(expected = "Expected SEMICOLON, but found Some([IDENTIFIER]: \"var2\" at (2..2, 9..12))")]
     fn enforces_semicolons() {
-        let mut lexer = prepare("
+        let src = prepare("
         var1 = 10
         var2 = 20;"
     );
+        let mut lexer = Lexer::new(&src, false);
         let _ = lexer.lex();
         let mut parser = Parser::new(&lexer);
-        parser.parse_all_tokens();        
+        let errors = parser.parse_all_tokens().unwrap_err();
+        assert_eq!(errors, vec![ParserError::unexpected_token(vec![TokenType::SEMICOLON], "var2".to_string(), "<input>".to_string(), (2..2, 8..11, 27..31))]);
     }
 
     #[test]
-    #[should_panic(expected = "Unexpected end of input")]
     fn expects_expressions_to_be_complete() {
-        let mut lexer = prepare("var1 =");
+        let src = prepare("var1 =");
+        let mut lexer = Lexer::new(&src, false);
         let _ = lexer.lex();
         let mut parser = Parser::new(&lexer);
-        parser.parse_all_tokens();        
+        let errors = parser.parse_all_tokens().unwrap_err();
+        assert!(matches!(errors.as_slice(), [ParserError::UnexpectedEof { .. }]));
     }
 
 }
\ No newline at end of file
@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod statement_parsing_test {
+    use wind::backend::ir::flags::FunctionModifer;
+    use wind::frontend::{ast::{ASTNode, Expression, Function, Literal, Statement, Variable}, lexer::{self, Lexer}, parser::Parser, preprocessor};
+
+    /// `src` after preprocessing, for `lex` to borrow.
+    fn prepare(src: &str) -> String {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        let _ = prep_lex_inst.lex();
+
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+
+        prep_inst.get_processed()
+    }
+
+    fn lex(src: &str) -> Lexer<'_> {
+        let mut lex_inst = lexer::Lexer::new(src, false);
+        let _ = lex_inst.lex();
+        lex_inst
+    }
+
+    fn parse_function(src: &str) -> Function {
+        let src = prepare(src);
+        let lexer = lex(&src);
+        let mut parser = Parser::new(&lexer);
+        let nodes = parser.parse_all_tokens().unwrap();
+        assert_eq!(nodes.len(), 1);
+        match nodes.front() {
+            Some(ASTNode::FUNCTION(function)) => function.clone(),
+            other => panic!("Expected ASTNode::FUNCTION, found {:?}", other),
+        }
+    }
+
+    fn var(name: &str) -> Expression {
+        Expression::VARIABLE(Variable::new(name.to_string()))
+    }
+
+    fn int(value: i64) -> Expression {
+        Expression::LITERAL(Literal::INTEGER(value))
+    }
+
+    #[test]
+    fn parses_function_signatures() {
+        let function = parse_function("fn add(a: i32, b: i32) => i32 { return a + b; }");
+        assert_eq!(function.name, "add");
+        assert_eq!(function.arguments, vec![("a".to_string(), "i32".to_string()), ("b".to_string(), "i32".to_string())]);
+        assert_eq!(function.return_type, "i32");
+        assert_eq!(function.body, vec![Statement::RETURN(Expression::BINARY {
+            left: Box::new(var("a")),
+            op: "+".to_string(),
+            right: Box::new(var("b")),
+        })]);
+
+        let function = parse_function("fn noop() {}");
+        assert!(function.arguments.is_empty());
+        assert_eq!(function.return_type, "void");
+        assert!(function.body.is_empty());
+    }
+
+    #[test]
+    fn parses_extern_declarations() {
+        let function = parse_function("extern fn printf(format: *u8, ...) => i32;");
+        assert_eq!(function.name, "printf");
+        assert_eq!(function.arguments, vec![("format".to_string(), "*u8".to_string())]);
+        assert_eq!(function.return_type, "i32");
+        assert_eq!(function.flags, FunctionModifer::Extern as u16 | FunctionModifer::Variadic as u16);
+        assert!(function.body.is_empty());
+
+        let function = parse_function("extern fn getenv(name: *u8) => *u8;");
+        assert_eq!(function.return_type, "*u8");
+        let function = parse_function("fn first(argv: **u8) => *u8 { let arg: *u8; return arg; }");
+        assert_eq!(function.arguments, vec![("argv".to_string(), "**u8".to_string())]);
+        assert!(matches!(&function.body[0], Statement::LET { data_type: Some(t), .. } if t == "*u8"));
+
+        let function = parse_function("extern fn exit(code: i32);");
+        assert_eq!(function.return_type, "void");
+        assert_eq!(function.flags, FunctionModifer::Extern as u16);
+
+        let function = parse_function("fn add(a: i32, b: i32) => i32 { return a + b; }");
+        assert_eq!(function.flags, 0);
+    }
+
+    #[test]
+    fn parses_declarations_and_assignments() {
+        let function = parse_function("
+        fn main() => i32 {
+            let a: i32 = 1;
+            let b;
+            const c = 2;
+            a += c;
+            a <<= 2;
+            b = a;
+            print(a, b);
+            return 0;
+        }");
+        assert_eq!(function.body, vec![
+            Statement::LET { name: "a".to_string(), data_type: Some("i32".to_string()), value: Some(int(1)), constant: false },
+            Statement::LET { name: "b".to_string(), data_type: None, value: None, constant: false },
+            Statement::LET { name: "c".to_string(), data_type: None, value: Some(int(2)), constant: true },
+            Statement::ASSIGN { name: "a".to_string(), op: "+=".to_string(), value: var("c") },
+            Statement::ASSIGN { name: "a".to_string(), op: "<<=".to_string(), value: int(2) },
+            Statement::ASSIGN { name: "b".to_string(), op: "=".to_string(), value: var("a") },
+            Statement::EXPRESSION(Expression::CALL { name: "print".to_string(), arguments: vec![var("a"), var("b")] }),
+            Statement::RETURN(int(0)),
+        ]);
+    }
+
+    #[test]
+    fn parses_control_flow() {
+        let function = parse_function("
+        fn main() => i32 {
+            while a < 10 {
+                if a == 5 { break; } else if a == 6 { continue; } else { a += 1; }
+                { f(); }
+            }
+            return a;
+        }");
+        let cmp = |op: &str, value| Expression::BINARY { left: Box::new(var("a")), op: op.to_string(), right: Box::new(int(value)) };
+        assert_eq!(function.body, vec![
+            Statement::WHILE {
+                condition: cmp("<", 10),
+                body: vec![
+                    Statement::IF {
+                        condition: cmp("==", 5),
+                        then: vec![Statement::BREAK],
+                        otherwise: vec![Statement::IF {
+                            condition: cmp("==", 6),
+                            then: vec![Statement::CONTINUE],
+                            otherwise: vec![Statement::ASSIGN { name: "a".to_string(), op: "+=".to_string(), value: int(1) }],
+                        }],
+                    },
+                    Statement::BLOCK(vec![Statement::EXPRESSION(Expression::CALL { name: "f".to_string(), arguments: vec![] })]),
+                ],
+            },
+            Statement::RETURN(var("a")),
+        ]);
+    }
+
+    #[test]
+    fn parses_the_macros_example() {
+        let function = parse_function(&std::fs::read_to_string("examples/01-macros.sz").unwrap());
+        assert_eq!(function.name, "main");
+        assert!(matches!(function.body.as_slice(), [Statement::RETURN(Expression::BINARY { .. })]));
+    }
+
+    #[test]
+    fn parses_string_and_char_literals() {
+        let function = parse_function(r#"fn main() => i32 { printf("hello\n", 'A'); return 0; }"#);
+        assert_eq!(function.body[0], Statement::EXPRESSION(Expression::CALL {
+            name: "printf".to_string(),
+            arguments: vec![Expression::LITERAL(Literal::STRING("hello\n".to_string())), int(65)],
+        }));
+    }
+
+    #[test]
+    fn attaches_doc_comments_to_functions() {
+        let src = prepare("
+/// Adds two numbers.
+///
+/// Wraps on overflow.
+fn add(a: i32, b: i32) => i32 {
+    /// Not attached to anything.
+    return a + b; // the sum
+}
+
+/// Dangling.
+a = 1;
+
+fn plain() {}
+");
+        let lexer = lex(&src);
+        let mut parser = Parser::new(&lexer);
+        let nodes: Vec<ASTNode> = parser.parse_all_tokens().unwrap().iter().cloned().collect();
+        let docs: Vec<Vec<String>> = nodes.iter().filter_map(|node| match node {
+            ASTNode::FUNCTION(function) => Some(function.docs.clone()),
+            _ => None,
+        }).collect();
+        assert_eq!(docs, vec![
+            vec!["Adds two numbers.".to_string(), String::new(), "Wraps on overflow.".to_string()],
+            vec![],
+        ]);
+    }
+
+    #[test]
+    fn parses_numeric_literals() {
+        let function = parse_function("fn main() => u64 { f(0x10, 2.5, 200u8, 1.5f32, 0xFFFF_FFFF_FFFF_FFFF); return 0; }");
+        let typed = |literal, data_type: &str| Expression::LITERAL(Literal::TYPED { literal: Box::new(literal), data_type: data_type.to_string() });
+        assert_eq!(function.body[0], Statement::EXPRESSION(Expression::CALL {
+            name: "f".to_string(),
+            arguments: vec![
+                int(16),
+                Expression::LITERAL(Literal::FLOAT(2.5)),
+                typed(Literal::INTEGER(200), "u8"),
+                typed(Literal::FLOAT(1.5), "f32"),
+                int(-1),
+            ],
+        }));
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod builtins_test {
+    use wind::frontend::{lexer, preprocessor::{self, builtins}};
+
+    fn expand(src: &str) -> Vec<String> {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+        prep_inst.take_tokens().iter().map(|t| t.spelling()).collect()
+    }
+
+    #[test]
+    fn line_is_that_of_the_outermost_call() {
+        let src = "__LINE__\n!macro HERE: {\n    __LINE__\n}\n\nHERE\n";
+        assert_eq!(expand(src), vec!["1", "6"]);
+    }
+
+    #[test]
+    fn counter_counts_up_and_file_names_the_input() {
+        assert_eq!(expand("__COUNTER__ __COUNTER__ __COUNTER__"), vec!["0", "1", "2"]);
+        assert_eq!(expand("__FILE__"), vec!["\"<input>\""]);
+    }
+
+    #[test]
+    fn macros_shadow_builtins() {
+        assert_eq!(expand("!macro __LINE__: 42\n__LINE__"), vec!["42"]);
+    }
+
+    #[test]
+    fn dates_are_spelled_as_in_c() {
+        assert_eq!(builtins::date(0), "Jan  1 1970");
+        // 2000-02-29, a leap day
+        assert_eq!(builtins::date(951_782_400), "Feb 29 2000");
+        assert_eq!(builtins::date(1_792_281_600), "Oct 18 2026");
+        let today = expand("__DATE__");
+        assert_eq!(today.len(), 1);
+        assert_eq!(today[0].len(), "\"Oct 18 2026\"".len());
+    }
+}
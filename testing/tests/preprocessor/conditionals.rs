@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod conditionals_test {
+    use wind::frontend::{lexer, preprocessor::{self, macros::Macro}};
+    use wind::reporter::prep::PreprocessorError;
+
+    /// Processed text, as words, and errors of `src` with `defines` added first.
+    fn process(src: &str, defines: &[(&str, &str)]) -> (String, Vec<PreprocessorError>) {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        for (name, value) in defines {
+            prep_inst.add_macro(Macro::define(name.to_string(), value));
+        }
+        let errors = prep_inst.process().err().unwrap_or_default();
+        let words: Vec<String> = prep_inst.get_processed().split_whitespace().map(str::to_string).collect();
+        (words.join(" "), errors)
+    }
+
+    #[test]
+    fn picks_one_branch() {
+        let src = "!if MODE == 1\none\n!elif MODE == 2\ntwo\n!else\nother\n!endif\n";
+        assert_eq!(process(src, &[("MODE", "1")]).0, "one");
+        assert_eq!(process(src, &[("MODE", "2")]).0, "two");
+        assert_eq!(process(src, &[]).0, "other");
+    }
+
+    #[test]
+    fn nests_and_skips_inner_conditions() {
+        let src = "!if 0\n!if 1 / 0\nbad\n!endif\n!else\n!ifdef A\na\n!endif\n!ifndef B\nnot_b\n!endif\n!endif\n";
+        let (text, errors) = process(src, &[("A", "")]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(text, "a not_b");
+    }
+
+    #[test]
+    fn evaluates_constant_expressions() {
+        let kept = |condition: &str| {
+            let (text, errors) = process(&format!("!if {}\nyes\n!endif\n", condition), &[("TWO", "2"), ("EXPR", "(TWO + 1) * 2")]);
+            assert!(errors.is_empty(), "{}: {:?}", condition, errors);
+            text == "yes"
+        };
+        assert!(kept("1 + 2 * 3 == 7"));
+        assert!(kept("(1 + 2) * 3 == 9"));
+        assert!(kept("-1 < 0 && ~0 == -1"));
+        assert!(kept("1 << 4 == 16 && 0x10 >> 2 == 4"));
+        assert!(kept("EXPR == 6"));
+        assert!(kept("defined(TWO) && defined EXPR && !defined(NONE)"));
+        assert!(kept("UNDEFINED == 0"));
+        assert!(kept("0 || 7 % 4 == 3"));
+        assert!(kept("true"));
+        // Short-circuited operands are not evaluated
+        assert!(kept("1 || 1 / 0") && !kept("0 && 1 / 0"));
+    }
+
+    #[test]
+    fn undef_removes_macros() {
+        let (text, _) = process("!macro A: 1\n!undef A\n!ifdef A\nstill\n!else\ngone\n!endif\n", &[]);
+        assert_eq!(text, "gone");
+        let (text, _) = process("!undef A\n!if A\nkept\n!endif\n", &[("A", "1")]);
+        assert_eq!(text, "");
+    }
+
+    #[test]
+    fn directive_names_inside_expressions_are_kept() {
+        let (text, errors) = process("!ifdef A\nx = !undef;\ny = !if && !endif;\n!endif\n", &[("A", "")]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(text, "x = !undef; y = !if && !endif;");
+    }
+
+    #[test]
+    fn add_macro_replaces_definitions() {
+        let (text, _) = process("!macro LEVEL: 2\n!if LEVEL == 2\ntwo\n!endif\n", &[("LEVEL", "1")]);
+        assert_eq!(text, "two");
+    }
+
+    #[test]
+    fn skipped_code_keeps_its_lines() {
+        let mut prep_lex_inst = lexer::Lexer::new("!if 0\na\nb\n!endif\nc\n", true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+        assert_eq!(prep_inst.get_processed().find('c'), Some(4));
+    }
+
+    #[test]
+    fn reports_unbalanced_directives() {
+        let (_, errors) = process("!else\n!endif\n!if 1\n!else\n!elif 1\n!else\n", &[]);
+        let found: Vec<(&str, usize)> = errors.iter().map(|e| match e {
+            PreprocessorError::UnexpectedDirective { directive, line, .. } => (directive.as_str(), *line),
+            PreprocessorError::UnterminatedConditional { directive, line, .. } => (directive.as_str(), *line),
+            other => panic!("unexpected {:?}", other),
+        }).collect();
+        assert_eq!(found, vec![("else", 1), ("endif", 2), ("elif", 5), ("else", 6), ("if", 3)]);
+    }
+
+    #[test]
+    fn reports_invalid_conditions() {
+        for condition in ["", "1 +", "(1", "1 / 0", "1 2", "F", "1 << 64", "\"s\""] {
+            let (text, errors) = process(&format!("!macro F: (x) {{ x }}\n!if {}\nyes\n!endif\n", condition), &[]);
+            assert_eq!(text, "", "{}", condition);
+            assert!(matches!(&errors[..], [PreprocessorError::InvalidCondition { line: 2, column: 1, .. }]), "{}: {:?}", condition, errors);
+        }
+        let (_, errors) = process("!ifdef 1\n!endif\n", &[]);
+        assert!(errors[0].to_string().contains("`!ifdef` takes a macro name"));
+    }
+}
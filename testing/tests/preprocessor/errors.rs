@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod errors_test {
+    use wind::frontend::{lexer, preprocessor};
+    use wind::reporter::prep::PreprocessorError;
+
+    fn errors(src: &str) -> Vec<PreprocessorError> {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().err().unwrap_or_default()
+    }
+
+    #[test]
+    fn truncated_input_does_not_panic() {
+        assert!(errors("let a = !").is_empty());
+        assert!(matches!(&errors("!macro")[..], [PreprocessorError::UnexpectedToken { found, .. }] if found == "the end of the input"));
+        assert!(matches!(&errors("!macro F:")[..], []));
+    }
+
+    #[test]
+    fn malformed_definitions_point_at_the_problem() {
+        let errors = errors("let a = 1;\n!macro 1: x\n");
+        assert!(matches!(&errors[..], [PreprocessorError::UnexpectedToken { expected, found, line: 2, column: 8, .. }]
+            if expected == "IDENTIFIER" && found == "`1`"));
+        assert_eq!(errors[0].to_string(), "⚠ Expected IDENTIFIER, found `1` at <input>:2:8.");
+    }
+
+    #[test]
+    fn missing_parentheses_are_reported() {
+        let errors = errors("!macro F: (a, b\nF(1, 2);\n");
+        assert!(matches!(&errors[..], [PreprocessorError::MissingParenthesis { paren, line: 1, column: 11, .. }] if paren == ")"));
+
+        let errors = self::errors("!macro S: (x) { !str x }\n");
+        assert!(matches!(&errors[..], [PreprocessorError::MissingParenthesis { paren, after, .. }] if paren == "(" && after == "`!str`"));
+    }
+
+    #[test]
+    fn unterminated_bodies_are_reported() {
+        let errors = errors("let a = 1;\n!macro F: (a, b) {\n    a + b\n");
+        assert!(matches!(&errors[..], [PreprocessorError::UnterminatedMacro { name, line: 2, column: 1, .. }] if name == "F"));
+    }
+
+    #[test]
+    fn unknown_directives_are_reported() {
+        let errors = errors("!pragma once\n!bogus\nlet a = 1;\n!a && b;\n");
+        let names: Vec<&str> = errors.iter().map(|e| match e {
+            PreprocessorError::UnknownDirective { directive, .. } => directive.as_str(),
+            _ => "",
+        }).collect();
+        assert_eq!(names, vec!["pragma", "bogus"]);
+    }
+
+    #[test]
+    fn every_error_is_collected() {
+        let errors = errors("!macro 1: x\n!macro ADD: (a, b) { a + b }\nADD(1);\n!bogus\n");
+        assert!(matches!(&errors[..], [
+            PreprocessorError::UnexpectedToken { .. },
+            PreprocessorError::MacroArguments { expected: 2, found: 1, .. },
+            PreprocessorError::UnknownDirective { .. },
+        ]));
+    }
+}
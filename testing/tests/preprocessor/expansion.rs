@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod expansion_test {
+    use wind::frontend::{lexer, parser, preprocessor};
+    use wind::frontend::source::SourceMap;
+    use wind::reporter::prep::PreprocessorError;
+
+    /// Output tokens of `src`, spelled as in source and separated by spaces, and the errors.
+    fn expand(src: &str) -> (String, Vec<PreprocessorError>) {
+        let mut prep_lex_inst = lexer::Lexer::new(src, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        let errors = prep_inst.process().err().unwrap_or_default();
+        let words: Vec<String> = prep_inst.take_tokens().iter().map(|t| t.spelling()).collect();
+        (words.join(" "), errors)
+    }
+
+    #[test]
+    fn strings_and_nested_commas_stay_in_one_argument() {
+        let src = "!macro FIRST: (a, b) { a }\nFIRST(\"x, (y\", 2);\nFIRST(f(1, 2), 3);\n";
+        assert_eq!(expand(src).0, "\"x, (y\" ; f ( 1 , 2 ) ;");
+    }
+
+    #[test]
+    fn nested_calls_and_arguments_are_expanded() {
+        let src = "!macro ADD: (a, b) { a + b }\n!macro ONE: 1\nADD(ADD(ONE, 2), ONE);\n";
+        assert_eq!(expand(src).0, "1 + 2 + 1 ;");
+    }
+
+    #[test]
+    fn expansions_are_rescanned_with_what_follows() {
+        let src = "!macro CALL: F\n!macro F: (x) { x * 2 }\nCALL(3);\n";
+        assert_eq!(expand(src).0, "3 * 2 ;");
+    }
+
+    #[test]
+    fn function_like_names_without_a_call_are_left() {
+        let src = "!macro F: (x) { x }\nlet f = F;\n";
+        let (out, errors) = expand(src);
+        assert_eq!(out, "let f = F ;");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn self_recursion_is_reported() {
+        let (out, errors) = expand("!macro LOOP: LOOP + 1\nlet a = LOOP;\n");
+        assert_eq!(out, "let a = LOOP + 1 ;");
+        assert!(matches!(&errors[..], [PreprocessorError::RecursiveMacro { name, line: 2, column: 9, .. }] if name == "LOOP"));
+
+        let (_, errors) = expand("!macro A: B\n!macro B: A\nA;\n");
+        assert!(matches!(&errors[..], [PreprocessorError::RecursiveMacro { name, .. }] if name == "A"));
+    }
+
+    #[test]
+    fn bad_calls_are_reported() {
+        let (_, errors) = expand("!macro ADD: (a, b) { a + b }\nADD(1);\n");
+        assert!(matches!(&errors[..], [PreprocessorError::MacroArguments { expected: 2, found: 1, .. }]));
+
+        let (_, errors) = expand("!macro ADD: (a, b) { a + b }\nADD(1, (2;\n");
+        assert!(matches!(&errors[..], [PreprocessorError::UnterminatedMacroCall { line: 2, column: 1, .. }]));
+    }
+
+    #[test]
+    fn arguments_are_stringified_unexpanded() {
+        let src = "!macro ONE: 1\n!macro SHOW: (x) { !str(x) }\nSHOW(ONE  +   \"two\");\n";
+        assert_eq!(expand(src).0, "\"ONE + \\\"two\\\"\" ;");
+    }
+
+    #[test]
+    fn pasted_tokens_form_one_token() {
+        let src = "!macro GETTER: (name) { fn !paste(get_, name)() => i32 { return name; } }\nGETTER(width)\n";
+        assert_eq!(expand(src).0, "fn get_width ( ) => i32 { return width ; }");
+
+        let (_, errors) = expand("!macro BAD: (a, b) { !paste(a, b) }\nBAD(x, +);\n");
+        assert!(matches!(&errors[..], [PreprocessorError::InvalidPaste { text, line: 2, .. }] if text == "x+"));
+    }
+
+    #[test]
+    fn iterations_see_the_count_and_index() {
+        let src = "!macro LIST: (first) { !count: !for::arg { !index = arg !sep { , } } }\nLIST(a, b, c)\n";
+        assert_eq!(expand(src).0, "3 : 0 = a , 1 = b , 2 = c");
+    }
+
+    #[test]
+    fn parser_reads_expanded_tokens() {
+        let mut map = SourceMap::new();
+        let file = map.add("a.sz".to_string(), "!macro SEMI: ;\nfn main() => i32 { return 0 SEMI }\n".to_string());
+        let mut prep_lex_inst = lexer::Lexer::from_map(&map, file, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+        let tokens = prep_inst.take_tokens();
+        let mut parser = parser::Parser::from_tokens(&tokens, &map);
+        assert!(parser.parse_all_tokens().is_ok());
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod include_test {
+    use std::path::{Path, PathBuf};
+
+    use wind::frontend::{lexer, preprocessor::{self, include::Includes}};
+    use wind::frontend::source::SourceMap;
+    use wind::reporter::prep::PreprocessorError;
+
+    /// Fresh directory holding `files`, as (relative path, text) pairs.
+    fn tree(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("wind-include-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (path, text) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+        root
+    }
+
+    /// Preprocesses `main` in `root`, searching `dirs` as with `-I`.
+    fn process(root: &Path, main: &str, dirs: &[&str]) -> (String, Vec<String>, SourceMap) {
+        let mut map = SourceMap::new();
+        let path = root.join(main);
+        let file = map.add(path.display().to_string(), std::fs::read_to_string(&path).unwrap());
+        let mut includes = Includes::new(map.next_id());
+        includes.set_path(file, &path);
+        for dir in dirs {
+            includes.add_dir(root.join(dir));
+        }
+
+        let mut prep_lex_inst = lexer::Lexer::from_map(&map, file, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.set_includes(includes);
+        let errors = prep_inst.process().err().unwrap_or_default().iter().map(|e| e.to_string()).collect();
+        let processed = prep_inst.get_processed();
+        let files = prep_inst.take_files();
+        for included in files {
+            map.insert(included);
+        }
+        (processed, errors, map)
+    }
+
+    #[test]
+    fn splices_files_and_their_macros() {
+        let root = tree("splice", &[
+            ("main.sz", "!include \"lib/math.sz\"\nlet a = ADD(1, 2);\n"),
+            ("lib/math.sz", "!macro ADD: (x,y) { x + y }\nlet b = 3;\n"),
+        ]);
+        let (processed, errors, map) = process(&root, "main.sz", &[]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(processed.contains("let b = 3;"));
+        let words: Vec<&str> = processed.split_whitespace().collect();
+        assert!(words.join(" ").contains("let a = 1 + 2 ;"), "{}", processed);
+        assert!(map.get(wind::frontend::source::FileId(1)).name.ends_with("math.sz"));
+    }
+
+    #[test]
+    fn searches_include_dirs_after_the_including_file() {
+        let root = tree("dirs", &[
+            ("src/main.sz", "!include \"consts.sz\"\nlet a = TWO;\n"),
+            ("include/consts.sz", "!macro TWO: 2\n"),
+        ]);
+        let (processed, errors, _) = process(&root, "src/main.sz", &["include"]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(processed.contains("let a = 2;"));
+
+        let (_, errors, _) = process(&root, "src/main.sz", &[]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("Cannot find `consts.sz`") && errors[0].contains("main.sz:1:1"));
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let root = tree("cycle", &[
+            ("a.sz", "!include \"b.sz\"\n"),
+            ("b.sz", "!include \"a.sz\"\n"),
+        ]);
+        let (_, errors, _) = process(&root, "a.sz", &[]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("a.sz → ") && errors[0].contains("b.sz → ") && errors[0].ends_with("a.sz."));
+    }
+
+    #[test]
+    fn once_files_are_included_once() {
+        let root = tree("once", &[
+            ("main.sz", "!include \"once.sz\"\n!include \"twice.sz\"\n!include \"once.sz\"\n!include \"twice.sz\"\n"),
+            ("once.sz", "!once\nlet once = 1;\n"),
+            ("twice.sz", "let twice = 2;\n"),
+        ]);
+        let (processed, errors, _) = process(&root, "main.sz", &[]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(processed.matches("let once").count(), 1);
+        assert_eq!(processed.matches("let twice").count(), 2);
+    }
+
+    #[test]
+    fn directive_names_inside_expressions_are_kept() {
+        let root = tree("expr", &[
+            ("main.sz", "!include \"flags.sz\"\n!include \"flags.sz\"\n"),
+            ("flags.sz", "let flag = !once;\nlet path = !include + !macro;\n"),
+        ]);
+        let (processed, errors, _) = process(&root, "main.sz", &[]);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(processed.matches("let flag = !once;").count(), 2);
+        assert_eq!(processed.matches("let path = !include + !macro;").count(), 2);
+    }
+
+    #[test]
+    fn reports_unreadable_files() {
+        let root = tree("lex", &[
+            ("main.sz", "!include \"bad.sz\"\n"),
+            ("bad.sz", "let a = $;\n"),
+        ]);
+        let mut map = SourceMap::new();
+        let path = root.join("main.sz");
+        let file = map.add("main.sz".to_string(), std::fs::read_to_string(&path).unwrap());
+        let mut includes = Includes::new(map.next_id());
+        includes.set_path(file, &path);
+        let mut prep_lex_inst = lexer::Lexer::from_map(&map, file, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.set_includes(includes);
+        let errors = prep_inst.process().unwrap_err();
+        assert!(matches!(&errors[..], [PreprocessorError::Lexer(_)]));
+        assert!(errors[0].to_string().contains("bad.sz:1:9"));
+    }
+}
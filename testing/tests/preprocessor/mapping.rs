@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod mapping_test {
+    use wind::frontend::{lexer, preprocessor::{self, mapping::ExpansionMap}};
+    use wind::frontend::source::{FileId, SourceMap};
+    use wind::frontend::token::Token;
+
+    const SRC: &str = "!macro FT: (x,y) {\n    x + y\n}\n\nlet a = FT(1, 2);\nlet b = 3;\n";
+
+    fn expand<'m>(map: &'m mut SourceMap, src: &str) -> (Vec<Token<'m>>, ExpansionMap) {
+        let file = map.add("a.sz".to_string(), src.to_string());
+        let mut prep_lex_inst = lexer::Lexer::from_map(map, file, true);
+        prep_lex_inst.lex().unwrap();
+        let mut prep_inst = preprocessor::Preprocessor::new(prep_lex_inst.tokens);
+        prep_inst.process().unwrap();
+        (prep_inst.take_tokens(), prep_inst.take_mapping())
+    }
+
+    /// Index of the first output token spelled `literal`.
+    fn find(tokens: &[Token], literal: &str) -> usize {
+        tokens.iter().position(|t| t.literal == literal).unwrap()
+    }
+
+    #[test]
+    fn copied_tokens_keep_their_position() {
+        let mut map = SourceMap::new();
+        let (tokens, mapping) = expand(&mut map, SRC);
+        let b = find(&tokens, "b");
+        assert_eq!(tokens[b].src, FileId(0));
+        assert_eq!(tokens[b].position, (5..5, 4..4, 54..55));
+        assert!(mapping.segment(b).is_none());
+    }
+
+    #[test]
+    fn expanded_tokens_point_at_the_call() {
+        let mut map = SourceMap::new();
+        let (tokens, mapping) = expand(&mut map, SRC);
+        let plus = find(&tokens, "+");
+        // The `FT` of `FT(1, 2)`
+        assert_eq!(tokens[plus].position, (4..4, 8..9, 40..42));
+        let expansions = mapping.expansions_at(FileId(0), &tokens[plus].position);
+        assert_eq!(expansions.len(), 1);
+        assert_eq!(expansions[0].name, "FT");
+        assert_eq!(expansions[0].describe(&map), "in expansion of macro `FT` defined at a.sz:1:1");
+    }
+
+    #[test]
+    fn nested_expansions_are_stacked() {
+        let mut map = SourceMap::new();
+        let (tokens, mapping) = expand(&mut map, "!macro ONE: 1\n!macro TWO: ONE\nlet a = TWO;\n");
+        let one = find(&tokens, "1");
+        assert_eq!(tokens[one].position.0, 2..2);
+        let names: Vec<&str> = mapping.segment(one).unwrap().expansions.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["TWO", "ONE"]);
+        assert!(mapping.segment(one).unwrap().expansions[1].describe(&map).ends_with("a.sz:1:1"));
+    }
+
+    #[test]
+    fn segments_cover_expanded_tokens_in_order() {
+        let mut map = SourceMap::new();
+        let (tokens, mapping) = expand(&mut map, SRC);
+        let segments = mapping.segments();
+        assert!(segments.windows(2).all(|pair| pair[0].output.end <= pair[1].output.start));
+        let expanded: usize = segments.iter().map(|segment| segment.output.len()).sum();
+        // `1 + 2` of `FT(1, 2)`
+        assert_eq!(expanded, 3);
+        assert!(segments.last().unwrap().output.end < tokens.len());
+    }
+}
@@ -1,7 +1,6 @@
-
 #[cfg(test)]
 mod tests {
     #[path = "writer/simple.rs"]
-    mod writer_test;
+    mod simple;
     
 }
\ No newline at end of file